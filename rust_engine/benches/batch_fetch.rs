@@ -0,0 +1,66 @@
+//! Durée d'un lot de 10 tickers : téléchargements séquentiels contre `bounded_map` à
+//! `DEFAULT_CONCURRENCY`, sur un serveur SEC simulé qui répond en `LATENCY`.
+//! `cargo bench --bench batch_fetch`.
+
+use std::time::{Duration, Instant};
+
+use edgar_fetcher::http::HttpClient;
+use edgar_fetcher::sec::SecClient;
+use edgar_fetcher::{bounded_map, fetch_company_by_cik, FetchOptions, DEFAULT_CONCURRENCY};
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Latence simulée d'une réponse `companyfacts` (ordre de grandeur de sec.gov).
+const LATENCY: Duration = Duration::from_millis(300);
+
+const TICKERS: u64 = 10;
+
+/// Débit de la SEC : au plus 10 requêtes par seconde.
+const RATE: f64 = 10.0;
+
+fn annual(val: f64, year: u16) -> Value {
+    json!({ "val": val, "fy": year, "fp": "FY", "form": "10-K",
+            "start": format!("{}-01-01", year), "end": format!("{}-12-31", year), "filed": format!("{}-02-15", year + 1) })
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    let revenues: Vec<Value> = (2014..2024).map(|y| annual(1.0e9 + f64::from(y), y)).collect();
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/api/xbrl/companyfacts/CIK\d{10}\.json$"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "cik": 1, "entityName": "Bench Corp", "facts": { "us-gaap": { "Revenues": { "units": { "USD": revenues } } } } }))
+                .set_delay(LATENCY),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Durée d'un lot complet avec au plus `concurrency` téléchargements en cours.
+async fn batch(server: &MockServer, concurrency: usize) -> f64 {
+    // Client neuf à chaque mesure : le limiteur part du même seau plein
+    let http = HttpClient::new(RATE, 0, "Bench bench@example.org").unwrap();
+    let client = SecClient::new(http, &server.uri(), &server.uri());
+    let opts = FetchOptions::default();
+    let start = Instant::now();
+    let results = bounded_map(1..=TICKERS, concurrency, |cik| fetch_company_by_cik(&client, None, cik, &opts)).await;
+    let elapsed = start.elapsed().as_secs_f64();
+    assert!(results.iter().all(Result::is_ok));
+    elapsed
+}
+
+#[tokio::main]
+async fn main() {
+    let server = server().await;
+
+    let sequential = batch(&server, 1).await;
+    let concurrent = batch(&server, DEFAULT_CONCURRENCY).await;
+
+    println!("{} tickers, latence {} ms, {} req/s", TICKERS, LATENCY.as_millis(), RATE);
+    println!("séquentiel               : {:.2} s", sequential);
+    println!("bounded_map (x{})         : {:.2} s", DEFAULT_CONCURRENCY, concurrent);
+    println!("accélération : x{:.1}", sequential / concurrent);
+}
@@ -0,0 +1,82 @@
+//! Pic mémoire de l'analyse d'un gros `companyfacts` : document complet (`parse_facts`) contre
+//! concepts extraits seuls (`parse_facts_retaining`). `cargo bench --bench parse_facts`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use edgar_fetcher::sec::{parse_facts, parse_facts_retaining};
+use edgar_fetcher::FetchOptions;
+use serde_json::{json, Value};
+
+/// Allocateur système instrumenté : octets alloués en cours et pic depuis la dernière remise à zéro.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Déclarant fictif : `concepts` concepts de 80 faits chacun, dont quelques tags de la
+/// config intégrée (les autres, comme chez un vrai déclarant, ne sont pas extraits).
+fn mega_filer(concepts: usize) -> Vec<u8> {
+    let facts: Vec<Value> = (0..80u16)
+        .map(|i| {
+            let year = 2004 + i / 4;
+            json!({ "val": 1.0e9 + f64::from(i), "accn": "0000320193-23-000106", "fy": year, "fp": "FY", "form": "10-K",
+                    "start": format!("{}-01-01", year), "end": format!("{}-12-31", year), "filed": format!("{}-02-15", year + 1),
+                    "frame": format!("CY{}", year) })
+        })
+        .collect();
+    let configured = ["Revenues", "NetIncomeLoss", "Assets", "StockholdersEquity", "NetCashProvidedByUsedInOperatingActivities"];
+    let gaap: serde_json::Map<String, Value> = (0..concepts)
+        .map(|i| {
+            let name = configured.get(i).map_or_else(|| format!("CustomConcept{}", i), |tag| tag.to_string());
+            (name, json!({ "label": "Concept", "description": "Description du concept.", "units": { "USD": facts } }))
+        })
+        .collect();
+    serde_json::to_vec(&json!({ "cik": 1, "entityName": "Mega Corp", "facts": { "us-gaap": gaap } })).unwrap()
+}
+
+/// Pic d'allocation (au-delà de ce qui est déjà alloué) et durée de `f`.
+fn measure<T>(f: impl FnOnce() -> T) -> (usize, f64) {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let value = f();
+    let elapsed = start.elapsed().as_secs_f64();
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    drop(value);
+    (peak, elapsed)
+}
+
+fn main() {
+    let body = mega_filer(3000);
+    let retain = FetchOptions::default().retained_concepts().unwrap();
+
+    let (full, full_secs) = measure(|| parse_facts(&body).unwrap());
+    let (lean, lean_secs) = measure(|| parse_facts_retaining(&body, &retain).unwrap());
+
+    let mb = |bytes: usize| bytes as f64 / 1e6;
+    println!("companyfacts : {:.1} Mo", mb(body.len()));
+    println!("parse_facts            : pic {:>7.1} Mo, {:.3} s", mb(full), full_secs);
+    println!("parse_facts_retaining  : pic {:>7.1} Mo, {:.3} s", mb(lean), lean_secs);
+    println!("réduction du pic : x{:.1}", full as f64 / lean.max(1) as f64);
+}
@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::diff::SnapshotOptions;
+use crate::models::TickerEntry;
+use crate::quote::Quote;
+
+/// Durée de validité du mapping ticker -> CIK en cache.
+pub const MAPPING_TTL: Duration = Duration::from_secs(24 * 3600);
+
+const MAPPING_FILE: &str = "company_tickers.json";
+
+const FX_FILE: &str = "fx_rates.json";
+
+const QUOTES_FILE: &str = "quotes.json";
+
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// Cache disque du moteur (par défaut dans le dossier cache de la plateforme).
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// Validateurs HTTP associés à une réponse `companyfacts` en cache.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FactsMeta {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched_at: u64,
+}
+
+/// Entrée de `--cache-info` : un `companyfacts` en cache.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CachedFacts {
+    pub cik: u64,
+    /// Taille du corps JSON, en octets.
+    pub bytes: u64,
+    /// Âge depuis le dernier téléchargement (ou revalidation), en secondes.
+    pub age_secs: u64,
+}
+
+/// Séries extraites d'un CIK à une date de téléchargement, pour `--diff`, avec les options
+/// d'extraction qui les ont produites.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FinancialsSnapshot {
+    pub fetched_at: u64,
+    pub options: SnapshotOptions,
+    pub financials: BTreeMap<String, Vec<(u16, f64)>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedQuote {
+    fetched_at: u64,
+    quote: Quote,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedMapping {
+    fetched_at: u64,
+    entries: Vec<TickerEntry>,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cache { dir: dir.into() }
+    }
+
+    /// `~/.cache/edgar_fetcher` sous Linux, équivalents sous macOS/Windows.
+    pub fn default_location() -> Option<Self> {
+        dirs::cache_dir().map(|d| Cache::new(d.join("edgar_fetcher")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Mapping en cache s'il a moins de `max_age`, sinon `None`.
+    pub fn load_mapping(&self, max_age: Duration) -> Option<Vec<TickerEntry>> {
+        let raw = fs::read(self.dir.join(MAPPING_FILE)).ok()?;
+        let cached: CachedMapping = serde_json::from_slice(&raw).ok()?;
+        let age = now_secs().saturating_sub(cached.fetched_at);
+        if age > max_age.as_secs() { return None; }
+        Some(cached.entries)
+    }
+
+    /// Écrit le mapping avec son horodatage. Un échec d'écriture n'est pas bloquant :
+    /// on perd seulement le bénéfice du cache au prochain lancement.
+    pub fn store_mapping(&self, entries: &[TickerEntry]) {
+        let cached = CachedMapping { fetched_at: now_secs(), entries: entries.to_vec() };
+        if let Ok(raw) = serde_json::to_vec(&cached) {
+            let _ = fs::create_dir_all(&self.dir)
+                .and_then(|_| fs::write(self.dir.join(MAPPING_FILE), raw));
+        }
+    }
+
+    /// Corps brut et validateurs du `companyfacts` d'un CIK (clé : CIK paddé sur 10 chiffres).
+    pub fn load_facts(&self, cik_padded: &str) -> Option<(Vec<u8>, FactsMeta)> {
+        let body = fs::read(self.facts_path(cik_padded, "json")).ok()?;
+        let raw_meta = fs::read(self.facts_path(cik_padded, "meta.json")).ok()?;
+        let meta = serde_json::from_slice(&raw_meta).ok()?;
+        Some((body, meta))
+    }
+
+    /// Enregistre la réponse et ses validateurs. Non bloquant, comme pour le mapping.
+    pub fn store_facts(&self, cik_padded: &str, body: &[u8], etag: Option<String>, last_modified: Option<String>) {
+        let meta = FactsMeta { etag, last_modified, fetched_at: now_secs() };
+        if let Ok(raw_meta) = serde_json::to_vec(&meta) {
+            let _ = fs::create_dir_all(self.dir.join("facts"))
+                .and_then(|_| fs::write(self.facts_path(cik_padded, "json"), body))
+                .and_then(|_| fs::write(self.facts_path(cik_padded, "meta.json"), raw_meta));
+        }
+    }
+
+    /// Taux de change déjà téléchargés, indexés par (devise, date). Un fichier absent
+    /// ou illisible donne un cache vide.
+    pub fn load_fx_rates(&self) -> HashMap<(String, NaiveDate), f64> {
+        let Ok(raw) = fs::read(self.dir.join(FX_FILE)) else { return HashMap::new() };
+        let stored: Vec<(String, NaiveDate, f64)> = serde_json::from_slice(&raw).unwrap_or_default();
+        stored.into_iter().map(|(currency, date, rate)| ((currency, date), rate)).collect()
+    }
+
+    /// Réécrit l'ensemble des taux connus. Non bloquant, comme pour le mapping.
+    pub fn store_fx_rates(&self, rates: &HashMap<(String, NaiveDate), f64>) {
+        let stored: Vec<(&str, NaiveDate, f64)> = rates.iter().map(|((c, d), r)| (c.as_str(), *d, *r)).collect();
+        if let Ok(raw) = serde_json::to_vec(&stored) {
+            let _ = fs::create_dir_all(&self.dir)
+                .and_then(|_| fs::write(self.dir.join(FX_FILE), raw));
+        }
+    }
+
+    /// `companyfacts` présents dans le cache, par CIK croissant. Un fichier sans métadonnées
+    /// lisibles (écriture interrompue) est ignoré, comme par `load_facts`.
+    pub fn list_facts(&self) -> Vec<CachedFacts> {
+        let Ok(dir) = fs::read_dir(self.dir.join("facts")) else { return Vec::new() };
+        let now = now_secs();
+        let mut entries: Vec<CachedFacts> = dir
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                let cik_padded = name.to_str()?.strip_prefix("CIK")?.strip_suffix(".json")?;
+                let cik = cik_padded.parse().ok()?;
+                let bytes = fs::metadata(self.facts_path(cik_padded, "json")).ok()?.len();
+                let raw_meta = fs::read(self.facts_path(cik_padded, "meta.json")).ok()?;
+                let meta: FactsMeta = serde_json::from_slice(&raw_meta).ok()?;
+                Some(CachedFacts { cik, bytes, age_secs: now.saturating_sub(meta.fetched_at) })
+            })
+            .collect();
+        entries.sort_by_key(|e| e.cik);
+        entries
+    }
+
+    /// Cours en cache de moins de `max_age`, par ticker.
+    pub fn load_quotes(&self, max_age: Duration) -> HashMap<String, Quote> {
+        let now = now_secs();
+        self.read_quotes()
+            .into_iter()
+            .filter(|(_, cached)| now.saturating_sub(cached.fetched_at) <= max_age.as_secs())
+            .map(|(ticker, cached)| (ticker, cached.quote))
+            .collect()
+    }
+
+    /// Ajoute ou remplace le cours d'un ticker. Non bloquant, comme pour le mapping.
+    pub fn store_quote(&self, ticker: &str, quote: &Quote) {
+        let mut quotes = self.read_quotes();
+        quotes.insert(ticker.to_string(), CachedQuote { fetched_at: now_secs(), quote: quote.clone() });
+        if let Ok(raw) = serde_json::to_vec(&quotes) {
+            let _ = fs::create_dir_all(&self.dir)
+                .and_then(|_| fs::write(self.dir.join(QUOTES_FILE), raw));
+        }
+    }
+
+    /// Dernière archive lisible des séries d'un CIK extraites avec les mêmes `options` : une
+    /// archive produite avec d'autres options (`--years`, `--metrics`...) n'est pas comparable.
+    pub fn latest_snapshot(&self, cik: u64, options: &SnapshotOptions) -> Option<FinancialsSnapshot> {
+        let mut files: Vec<(u128, PathBuf)> = fs::read_dir(self.snapshots_dir(cik))
+            .ok()?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let key = path.file_stem()?.to_str()?.split('-').next()?.parse().ok()?;
+                Some((key, path))
+            })
+            .collect();
+        files.sort_unstable_by(|a, b| b.cmp(a));
+        files.into_iter().find_map(|(_, path)| {
+            let snapshot: FinancialsSnapshot = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+            (snapshot.options == *options).then_some(snapshot)
+        })
+    }
+
+    /// Archive les séries d'un CIK sous `snapshots/CIK.../<nanosecondes>-<pid>.json`, sauf si
+    /// elles sont identiques à la dernière archive aux mêmes options. Le fichier est créé en
+    /// exclusivité : deux extractions simultanées n'écrasent pas l'archive l'une de l'autre.
+    /// Non bloquant, comme pour le mapping.
+    pub fn store_snapshot(&self, cik: u64, financials: &HashMap<String, Vec<(u16, f64)>>, options: &SnapshotOptions) {
+        let snapshot = FinancialsSnapshot {
+            fetched_at: now_secs(),
+            options: options.clone(),
+            financials: financials.iter().map(|(name, values)| (name.clone(), values.clone())).collect(),
+        };
+        if self.latest_snapshot(cik, options).is_some_and(|latest| latest.financials == snapshot.financials) {
+            return;
+        }
+        let Ok(raw) = serde_json::to_vec(&snapshot) else { return };
+        let dir = self.snapshots_dir(cik);
+        if fs::create_dir_all(&dir).is_err() {
+            return;
+        }
+        let mut key = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        loop {
+            let path = dir.join(format!("{}-{}.json", key, std::process::id()));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let _ = file.write_all(&raw);
+                    return;
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => key += 1,
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn snapshots_dir(&self, cik: u64) -> PathBuf {
+        self.dir.join(SNAPSHOTS_DIR).join(format!("CIK{:0>10}", cik))
+    }
+
+    fn read_quotes(&self) -> HashMap<String, CachedQuote> {
+        fs::read(self.dir.join(QUOTES_FILE))
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    fn facts_path(&self, cik_padded: &str, ext: &str) -> PathBuf {
+        self.dir.join("facts").join(format!("CIK{}.{}", cik_padded, ext))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
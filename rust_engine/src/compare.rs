@@ -0,0 +1,74 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::Serialize;
+
+use crate::growth::yoy_growth;
+use crate::models::CompanyFinancials;
+use crate::ratios::compute_ratios;
+
+/// Métriques et ratios pour lesquels une valeur plus élevée est préférable ;
+/// les autres (dette, CapEx, passifs...) sont alignés sans vainqueur.
+const HIGHER_IS_BETTER: &[&str] = &[
+    "Revenue", "Gross Profit", "Net Income", "Operating Income (EBIT)", "EPS Diluted",
+    "Operating Cash Flow", "Free Cash Flow", "Owner Earnings", "Total Equity", "Cash & Equiv.",
+    "Net Margin", "Operating Margin", "ROE", "Current Ratio",
+];
+
+/// Vue côte à côte de deux entreprises.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub tickers: [String; 2],
+    pub metrics: BTreeMap<String, MetricComparison>,
+    pub ratios: BTreeMap<String, MetricComparison>,
+}
+
+/// Une métrique alignée entre les deux entreprises (clé : ticker ; `null` si non publiée).
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricComparison {
+    pub values: BTreeMap<String, Option<Snapshot>>,
+    /// Ticker en tête, pour les métriques où plus est mieux et publiées par les deux.
+    pub winner: Option<String>,
+}
+
+/// Dernier exercice publié d'une métrique, avec sa variation annuelle.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Snapshot {
+    pub fiscal_year: u16,
+    pub value: f64,
+    pub yoy: Option<f64>,
+}
+
+/// Aligne la dernière valeur, la croissance YoY et les ratios clés de deux entreprises.
+pub fn compare(a: &CompanyFinancials, b: &CompanyFinancials) -> Comparison {
+    let ratios = [compute_ratios(&a.financials), compute_ratios(&b.financials)];
+    Comparison {
+        tickers: [a.ticker.clone(), b.ticker.clone()],
+        metrics: align([&a.ticker, &b.ticker], [&a.financials, &b.financials]),
+        ratios: align([&a.ticker, &b.ticker], [&ratios[0], &ratios[1]]),
+    }
+}
+
+fn align(tickers: [&String; 2], series: [&HashMap<String, Vec<(u16, f64)>>; 2]) -> BTreeMap<String, MetricComparison> {
+    let yoy = [yoy_growth(series[0]), yoy_growth(series[1])];
+    let names: std::collections::BTreeSet<&String> = series.iter().flat_map(|s| s.keys()).collect();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let snapshots: Vec<Option<Snapshot>> = (0..2)
+                .map(|i| {
+                    let &(fiscal_year, value) = series[i].get(name)?.last()?;
+                    let yoy = yoy[i].get(name).and_then(|s| s.iter().find(|(y, _)| *y == fiscal_year)).map(|&(_, g)| g);
+                    Some(Snapshot { fiscal_year, value, yoy })
+                })
+                .collect();
+            let winner = match (snapshots[0], snapshots[1]) {
+                (Some(x), Some(y)) if HIGHER_IS_BETTER.contains(&name.as_str()) && x.value != y.value => {
+                    Some(if x.value > y.value { tickers[0] } else { tickers[1] }.clone())
+                }
+                _ => None,
+            };
+            let values = tickers.iter().map(|t| t.to_string()).zip(snapshots).collect();
+            (name.clone(), MetricComparison { values, winner })
+        })
+        .collect()
+}
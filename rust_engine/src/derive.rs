@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::extract::MetricDef;
+
+/// Toutes les métriques que `derive_metrics` peut ajouter aux séries extraites.
+pub const DERIVED_METRICS: &[&str] = &[
+    "Free Cash Flow",
+    "Owner Earnings",
+    "Total Debt",
+    "Gross Profit",
+    "EBITDA",
+    "Effective Tax Rate",
+    "Payout Ratio",
+    "Book Value Per Share",
+    "Tangible Book Value",
+    "Net Buyback Yield",
+];
+
+/// Métriques de flux calculées par `derive_metrics` (en plus des flux de la config).
+pub const DERIVED_FLOWS: &[&str] = &["Free Cash Flow", "Owner Earnings", "EBITDA"];
+
+/// Nature d'une métrique dérivée, pour `--list-metrics` : période (`flow`, `instant` ou
+/// `ratio`) et dimension (`monetary`, `per_share` ou `pure`).
+pub fn derived_kind(name: &str) -> (&'static str, &'static str) {
+    match name {
+        "Free Cash Flow" | "Owner Earnings" | "Gross Profit" | "EBITDA" => ("flow", "monetary"),
+        "Total Debt" | "Tangible Book Value" => ("instant", "monetary"),
+        "Book Value Per Share" => ("instant", "per_share"),
+        // Effective Tax Rate, Payout Ratio, Net Buyback Yield
+        _ => ("ratio", "pure"),
+    }
+}
+
+/// Noms de toutes les métriques de flux : celles de la config puis les dérivées.
+pub fn flow_metric_names(config: &[MetricDef]) -> Vec<&str> {
+    config
+        .iter()
+        .filter(|def| !def.is_instant)
+        .map(|def| def.name)
+        .chain(DERIVED_FLOWS.iter().copied())
+        .collect()
+}
+
+/// Ajoute aux séries extraites les métriques calculées (FCF, marge brute, payout...).
+///
+/// Une métrique dérivée n'est insérée que pour les années où toutes ses composantes
+/// existent, et n'apparaît pas du tout si aucune année n'est calculable.
+pub fn derive_metrics(results: &mut HashMap<String, Vec<(u16, f64)>>) {
+    // Free Cash Flow : la CapEx est publiée comme un paiement positif, on la soustrait
+    let fcf = combine(results, "Operating Cash Flow", "CapEx", |ocf, capex| Some(ocf - capex));
+    insert_if_any(results, "Free Cash Flow", fcf);
+
+    // Owner earnings (Buffett) : résultat net + D&A - CapEx de maintenance,
+    // approximée pour l'instant par la CapEx totale
+    let capex: HashMap<u16, f64> = results.get("CapEx").into_iter().flatten().copied().collect();
+    let owner_earnings: Vec<(u16, f64)> = combine(results, "Net Income", "Depreciation & Amortization", |ni, da| Some(ni + da))
+        .into_iter()
+        .filter_map(|(year, cash)| capex.get(&year).map(|capex| (year, cash - capex)))
+        .collect();
+    insert_if_any(results, "Owner Earnings", owner_earnings);
+
+    // Total Debt : dette long terme + part à moins d'un an (comptée nulle si non publiée)
+    let short_term: HashMap<u16, f64> = results.get("Short Term Debt").into_iter().flatten().copied().collect();
+    let total_debt: Vec<(u16, f64)> = results
+        .get("Long Term Debt")
+        .into_iter()
+        .flatten()
+        .map(|&(year, long_term)| (year, long_term + short_term.get(&year).copied().unwrap_or(0.0)))
+        .collect();
+    insert_if_any(results, "Total Debt", total_debt);
+
+    // Gross Profit : complété par Revenue - Cost of Revenue pour les années où le tag manque
+    let gross = combine(results, "Revenue", "Cost of Revenue", |rev, cogs| Some(rev - cogs));
+    fill_missing_years(results, "Gross Profit", gross);
+
+    // EBITDA : EBIT + D&A. Sans EBIT publié, on le reconstruit par le bas :
+    // résultat net + charges d'intérêts + impôt sur les bénéfices
+    let taxes: HashMap<u16, f64> = results.get("Income Tax").into_iter().flatten().copied().collect();
+    let ebit: HashMap<u16, f64> = results.get("Operating Income (EBIT)").into_iter().flatten().copied().collect();
+    let bottom_up = combine(results, "Net Income", "Interest Expense", |ni, interest| Some(ni + interest))
+        .into_iter()
+        .filter_map(|(year, v)| taxes.get(&year).map(|tax| (year, v + tax)));
+    // L'EBIT publié, inséré en dernier, prime sur la reconstruction
+    let ebit_by_year: HashMap<u16, f64> = bottom_up.chain(ebit).collect();
+    let ebitda: Vec<(u16, f64)> = results
+        .get("Depreciation & Amortization")
+        .into_iter()
+        .flatten()
+        .filter_map(|&(year, da)| ebit_by_year.get(&year).map(|e| (year, e + da)))
+        .collect();
+    insert_if_any(results, "EBITDA", ebitda);
+
+    // Taux d'imposition effectif : les années où il n'a pas de sens sont omises (et signalées
+    // dans `flags.omitted_tax_rate`)
+    let tax_rate = combine(results, "Income Tax", "Pretax Income", |tax, pretax| effective_tax_rate(tax, pretax).ok());
+    insert_if_any(results, "Effective Tax Rate", tax_rate);
+
+    // Payout ratio : part du résultat net distribuée en dividendes, sans objet en cas de perte
+    let payout = combine(results, "Dividends Paid", "Net Income", |div, ni| (ni > 0.0).then(|| div / ni));
+    insert_if_any(results, "Payout Ratio", payout);
+
+    // Actif net par action, et actif net tangible (hors goodwill et incorporels). Un poste que
+    // l'entreprise ne publie jamais compte pour zéro ; publié d'autres exercices, son absence
+    // une année donnée est une lacune et l'année est écartée.
+    let bvps = combine(results, "Total Equity", "Shares Outstanding", |equity, shares| (shares > 0.0).then(|| equity / shares));
+    insert_if_any(results, "Book Value Per Share", bvps);
+    let reported = |name: &str| -> Option<HashMap<u16, f64>> {
+        results.get(name).filter(|series| !series.is_empty()).map(|series| series.iter().copied().collect())
+    };
+    let (goodwill, intangibles) = (reported("Goodwill"), reported("Intangibles"));
+    let deducted = |item: &Option<HashMap<u16, f64>>, year: u16| match item {
+        None => Some(0.0),
+        Some(by_year) => by_year.get(&year).copied(),
+    };
+    let tangible: Vec<(u16, f64)> = results
+        .get("Total Equity")
+        .into_iter()
+        .flatten()
+        .filter_map(|&(year, equity)| Some((year, equity - deducted(&goodwill, year)? - deducted(&intangibles, year)?)))
+        .collect();
+    insert_if_any(results, "Tangible Book Value", tangible);
+
+    // Net buyback yield : rachats nets des émissions d'actions, rapportés aux capitaux propres.
+    // Une année sans émission publiée est comptée sans émission.
+    let issuance: HashMap<u16, f64> = results.get("Stock Issuance").into_iter().flatten().copied().collect();
+    let equity: HashMap<u16, f64> = results.get("Total Equity").into_iter().flatten().copied().collect();
+    let buyback_yield: Vec<(u16, f64)> = results
+        .get("Buybacks")
+        .into_iter()
+        .flatten()
+        .filter_map(|&(year, buybacks)| {
+            let eq = equity.get(&year).copied().filter(|&e| e != 0.0)?;
+            let issued = issuance.get(&year).copied().unwrap_or(0.0);
+            Some((year, (buybacks - issued) / eq))
+        })
+        .collect();
+    insert_if_any(results, "Net Buyback Yield", buyback_yield);
+}
+
+/// Raison pour laquelle le taux d'imposition effectif d'un exercice est omis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxRateOmission {
+    /// Résultat avant impôt nul ou négatif.
+    PretaxLoss,
+    /// Impôt négatif : crédit d'impôt, reprise d'impôts différés.
+    TaxCredit,
+    /// Impôt supérieur au résultat avant impôt.
+    AbovePretax,
+}
+
+/// `Income Tax / Pretax Income`, seulement pour un résultat avant impôt positif et un taux
+/// compris entre 0 et 100 %. Un crédit d'impôt ponctuel ou une perte avant impôt donnent
+/// sinon des taux négatifs ou démesurés, trompeurs pour le NOPAT : l'année est omise.
+pub(crate) fn effective_tax_rate(tax: f64, pretax: f64) -> Result<f64, TaxRateOmission> {
+    if pretax <= 0.0 {
+        return Err(TaxRateOmission::PretaxLoss);
+    }
+    match tax / pretax {
+        rate if rate < 0.0 => Err(TaxRateOmission::TaxCredit),
+        rate if rate > 1.0 => Err(TaxRateOmission::AbovePretax),
+        rate => Ok(rate),
+    }
+}
+
+/// Complète une série publiée avec des valeurs calculées, sans écraser les années existantes.
+/// Rien à compléter : aucune série vide n'est créée pour une métrique absente.
+fn fill_missing_years(results: &mut HashMap<String, Vec<(u16, f64)>>, name: &str, computed: Vec<(u16, f64)>) {
+    if computed.is_empty() {
+        return;
+    }
+    let series = results.entry(name.to_string()).or_default();
+    for (year, value) in computed {
+        if !series.iter().any(|&(y, _)| y == year) {
+            series.push((year, value));
+        }
+    }
+    series.sort_by_key(|k| k.0);
+}
+
+/// Combine deux séries année par année ; `f` peut écarter une année en renvoyant `None`.
+pub(crate) fn combine(
+    results: &HashMap<String, Vec<(u16, f64)>>,
+    a: &str,
+    b: &str,
+    f: impl Fn(f64, f64) -> Option<f64>,
+) -> Vec<(u16, f64)> {
+    let (Some(sa), Some(sb)) = (results.get(a), results.get(b)) else { return Vec::new() };
+    let by_year: HashMap<u16, f64> = sb.iter().copied().collect();
+    sa.iter()
+        .filter_map(|&(year, va)| by_year.get(&year).and_then(|&vb| f(va, vb)).map(|v| (year, v)))
+        .collect()
+}
+
+fn insert_if_any(results: &mut HashMap<String, Vec<(u16, f64)>>, name: &str, series: Vec<(u16, f64)>) {
+    if !series.is_empty() {
+        results.insert(name.to_string(), series);
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, SecondsFormat};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::FinancialsSnapshot;
+
+/// Écart relatif en deçà duquel deux valeurs sont considérées identiques (arrondis de la SEC).
+const TOLERANCE: f64 = 1e-9;
+
+/// Options d'extraction qui changent les séries archivées : une archive ne sert de référence
+/// qu'à une extraction faite avec les mêmes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SnapshotOptions {
+    pub years: Option<u16>,
+    pub min_year: Option<u16>,
+    pub max_year: Option<u16>,
+    pub adjust_splits: bool,
+    pub fuzzy: bool,
+    pub strict: bool,
+    pub outlier_factor: Option<f64>,
+    /// Devise des montants archivés : celle de publication, ou `USD` après `--convert-usd`.
+    pub currency: Option<String>,
+    /// Tags de chaque métrique extraite, par `taxonomie/nom`.
+    pub metrics: BTreeMap<String, Vec<String>>,
+}
+
+/// Section `diff` (`--diff`) : ce qui a changé depuis la dernière archive des séries du CIK
+/// extraites avec les mêmes options.
+#[derive(Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDiff {
+    /// Date (UTC, RFC 3339) de l'archive de référence ; `null` s'il n'y en avait aucune.
+    pub since: Option<String>,
+    /// Valeurs retraitées, par métrique.
+    pub changed: BTreeMap<String, Vec<ValueChange>>,
+    /// Exercices absents de l'archive (nouveaux dépôts), par métrique.
+    pub added: BTreeMap<String, Vec<u16>>,
+    /// Exercices de l'archive qui ne sont plus publiés, par métrique (métrique disparue comprise).
+    pub removed: BTreeMap<String, Vec<u16>>,
+}
+
+/// Valeur d'un exercice qui diffère de l'archive.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+pub struct ValueChange {
+    pub fiscal_year: u16,
+    pub old: f64,
+    pub new: f64,
+}
+
+/// Compare les séries courantes à l'archive `previous`. Sans archive, le diff est vide
+/// (`since` à `null`) : la première extraction sert de référence aux suivantes.
+pub fn diff_snapshot(previous: Option<&FinancialsSnapshot>, current: &HashMap<String, Vec<(u16, f64)>>) -> SnapshotDiff {
+    let Some(previous) = previous else { return SnapshotDiff::default() };
+    let mut diff = SnapshotDiff {
+        since: DateTime::from_timestamp(previous.fetched_at as i64, 0).map(|d| d.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ..Default::default()
+    };
+    for (name, values) in current {
+        let old = previous.financials.get(name).map(Vec::as_slice).unwrap_or_default();
+        let mut changed = Vec::new();
+        let mut added = Vec::new();
+        for &(fiscal_year, new) in values {
+            match old.iter().find(|(y, _)| *y == fiscal_year) {
+                Some(&(_, old)) if !same_value(old, new) => changed.push(ValueChange { fiscal_year, old, new }),
+                Some(_) => {}
+                None => added.push(fiscal_year),
+            }
+        }
+        if !changed.is_empty() {
+            diff.changed.insert(name.clone(), changed);
+        }
+        if !added.is_empty() {
+            diff.added.insert(name.clone(), added);
+        }
+    }
+    for (name, values) in &previous.financials {
+        let current = current.get(name).map(Vec::as_slice).unwrap_or_default();
+        let removed: Vec<u16> = values.iter().map(|&(y, _)| y).filter(|y| !current.iter().any(|(c, _)| c == y)).collect();
+        if !removed.is_empty() {
+            diff.removed.insert(name.clone(), removed);
+        }
+    }
+    diff
+}
+
+fn same_value(old: f64, new: f64) -> bool {
+    (old - new).abs() <= TOLERANCE * old.abs().max(new.abs())
+}
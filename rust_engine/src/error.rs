@@ -0,0 +1,60 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Erreurs remontées par le moteur.
+#[derive(Error, Debug)]
+pub enum EngineError {
+    #[error("aucun ticker fourni (usage : edgar_fetcher <TICKER>)")]
+    MissingTickerArg,
+
+    #[error("argument invalide : {0}")]
+    InvalidArgument(String),
+
+    #[error("ticker introuvable dans le mapping SEC : {0}")]
+    TickerNotFound(String),
+
+    #[error("aucune entreprise ne correspond à : {0}")]
+    NameNotFound(String),
+
+    #[error("companyfacts du CIK {0} absent du cache (--offline)")]
+    CacheMiss(u64),
+
+    #[error("format de company_tickers.json non reconnu")]
+    UnrecognizedMapping,
+
+    #[error("mapping des tickers absent du cache (--offline)")]
+    MappingNotCached,
+
+    #[error("erreur HTTP : {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("impossible de lire {path} : {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("impossible d'écrire {path} : {source}")]
+    Write { path: PathBuf, source: io::Error },
+
+    #[error("réponse JSON invalide : {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("fichier de métriques {path} invalide : {message}")]
+    MetricsFile { path: PathBuf, message: String },
+
+    #[error("taux de change {currency}/USD introuvable au {date}")]
+    ExchangeRate { currency: String, date: chrono::NaiveDate },
+
+    #[error("cours introuvable pour {0}")]
+    QuoteUnavailable(String),
+
+    #[error("erreur SQLite : {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("erreur Parquet : {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
+
+    #[error("erreur Arrow : {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+}
+
+pub type Result<T> = std::result::Result<T, EngineError>;
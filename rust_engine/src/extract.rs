@@ -0,0 +1,810 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use chrono::{NaiveDate, Datelike};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tracing::{debug, debug_span};
+
+use crate::models::{FactData, FactUnit, PeriodValue, Taxonomy};
+use crate::sanity::Outlier;
+
+/// Dimension attendue d'une métrique : seules les unités compatibles sont retenues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitKind {
+    /// Montant dans une devise (`USD`, `EUR`, ...).
+    Monetary,
+    /// Nombre d'actions (`shares`).
+    Shares,
+    /// Montant par action (`USD/shares`, `EUR/shares`, ...).
+    PerShare,
+}
+
+impl UnitKind {
+    /// Nom de la dimension dans un fichier `--metrics` (`monetary`, `shares`, `per_share`).
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitKind::Monetary => "monetary",
+            UnitKind::Shares => "shares",
+            UnitKind::PerShare => "per_share",
+        }
+    }
+
+    /// Vrai si l'unité SEC (`USD`, `shares`, `USD/shares`...) correspond à la dimension.
+    pub fn matches(self, unit: &str) -> bool {
+        match self {
+            UnitKind::Monetary => is_currency(unit),
+            UnitKind::Shares => unit == "shares",
+            UnitKind::PerShare => unit
+                .strip_suffix("/shares")
+                .is_some_and(is_currency),
+        }
+    }
+}
+
+/// Code devise ISO 4217 : trois lettres majuscules.
+pub(crate) fn is_currency(unit: &str) -> bool {
+    unit.len() == 3 && unit.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+/// Définition d'une métrique : nom logique, tags XBRL par ordre de priorité,
+/// nature (flux ou stock) et unité attendue.
+#[derive(Debug, Clone, Copy)]
+pub struct MetricDef {
+    pub name: &'static str,
+    pub tags: &'static [&'static str],
+    pub is_instant: bool,
+    pub expected_unit: UnitKind,
+    /// Tags de repli stricts : seul le premier tag qui fournit des faits est lu, les suivants
+    /// ne servent que s'il est totalement absent et ne sont jamais mêlés à lui.
+    pub exclusive_tags: bool,
+}
+
+impl MetricDef {
+    /// Métrique de flux (compte de résultat, flux de trésorerie) : durée ~1 an.
+    pub const fn flow(name: &'static str, tags: &'static [&'static str], expected_unit: UnitKind) -> Self {
+        MetricDef { name, tags, is_instant: false, expected_unit, exclusive_tags: false }
+    }
+
+    /// Métrique de stock (bilan) : snapshot à une date.
+    pub const fn instant(name: &'static str, tags: &'static [&'static str], expected_unit: UnitKind) -> Self {
+        MetricDef { name, tags, is_instant: true, expected_unit, exclusive_tags: false }
+    }
+
+    /// Voir `exclusive_tags`.
+    pub const fn exclusive(self) -> Self {
+        MetricDef { exclusive_tags: true, ..self }
+    }
+
+    /// Même métrique réduite à son tag canonique (le premier) : sans repli sur les suivants.
+    pub fn canonical(self) -> Self {
+        MetricDef { tags: self.tags.get(..1).unwrap_or(self.tags), ..self }
+    }
+}
+
+/// Config Complète US GAAP
+pub const US_GAAP_METRICS: &[MetricDef] = &[
+    // --- FLUX (On vérifie la durée ~1 an) ---
+    MetricDef::flow("Revenue", &["Revenues", "SalesRevenueNet", "RevenueFromContractWithCustomerExcludingAssessedTax", "SalesRevenueGoodsNet"], UnitKind::Monetary),
+    MetricDef::flow("Cost of Revenue", &["CostOfRevenue", "CostOfGoodsAndServicesSold", "CostOfGoodsSold"], UnitKind::Monetary),
+    MetricDef::flow("Gross Profit", &["GrossProfit"], UnitKind::Monetary),
+    MetricDef::flow("Net Income", &["NetIncomeLoss", "ProfitLoss", "NetIncomeLossAvailableToCommonStockholdersBasic"], UnitKind::Monetary),
+    MetricDef::flow("Operating Income (EBIT)", &["OperatingIncomeLoss"], UnitKind::Monetary),
+    MetricDef::flow("EPS Diluted", &["EarningsPerShareDiluted", "EarningsPerShareBasicAndDiluted"], UnitKind::PerShare),
+    MetricDef::flow("Operating Cash Flow", &["NetCashProvidedByUsedInOperatingActivities"], UnitKind::Monetary),
+    MetricDef::flow("CapEx", &["PaymentsToAcquirePropertyPlantAndEquipment", "PaymentsToAcquireProductiveAssets"], UnitKind::Monetary),
+    MetricDef::flow("Dividends Paid", &["PaymentsOfDividendsCommonStock", "PaymentsOfDividends"], UnitKind::Monetary),
+    MetricDef::flow("Dividends Per Share", &["CommonStockDividendsPerShareDeclared", "CommonStockDividendsPerShareCashPaid"], UnitKind::PerShare),
+    MetricDef::flow("Buybacks", &["PaymentsForRepurchaseOfCommonStock"], UnitKind::Monetary),
+    MetricDef::flow("Stock Issuance", &["ProceedsFromIssuanceOfCommonStock"], UnitKind::Monetary),
+    MetricDef::flow("Depreciation & Amortization", &["DepreciationDepletionAndAmortization", "DepreciationAmortizationAndAccretionNet"], UnitKind::Monetary),
+    MetricDef::flow("Interest Expense", &["InterestExpense", "InterestExpenseDebt"], UnitKind::Monetary),
+    MetricDef::flow("Income Tax", &["IncomeTaxExpenseBenefit"], UnitKind::Monetary),
+    MetricDef::flow("Pretax Income", &["IncomeLossFromContinuingOperationsBeforeIncomeTaxesExtraordinaryItemsNoncontrollingInterest", "IncomeLossFromContinuingOperationsBeforeIncomeTaxesMinorityInterestAndIncomeLossFromEquityMethodInvestments"], UnitKind::Monetary),
+    MetricDef::flow("R&D", &["ResearchAndDevelopmentExpense", "ResearchAndDevelopmentExpenseExcludingAcquiredInProcessCost"], UnitKind::Monetary),
+    // SBC : la charge de l'exercice (réintégrée au tableau de flux), jamais les annexes voisines
+    // (coût restant à reconnaître, juste valeur des droits acquis) que `max_abs` pourrait retenir
+    MetricDef::flow("SBC", &["ShareBasedCompensation", "AllocatedShareBasedCompensationExpense"], UnitKind::Monetary).exclusive(),
+
+    // --- STOCKS (On prend le snapshot de fin d'année) ---
+    MetricDef::instant("Total Assets", &["Assets"], UnitKind::Monetary),
+    MetricDef::instant("Total Liabilities", &["Liabilities"], UnitKind::Monetary),
+    MetricDef::instant("Total Current Assets", &["AssetsCurrent"], UnitKind::Monetary),
+    MetricDef::instant("Total Current Liabilities", &["LiabilitiesCurrent"], UnitKind::Monetary),
+    MetricDef::instant("Inventory", &["InventoryNet"], UnitKind::Monetary),
+    MetricDef::instant("Total Equity", &["StockholdersEquity", "StockholdersEquityIncludingPortionAttributableToNoncontrollingInterest"], UnitKind::Monetary),
+    MetricDef::instant("Retained Earnings", &["RetainedEarningsAccumulatedDeficit"], UnitKind::Monetary),
+    MetricDef::instant("Goodwill", &["Goodwill"], UnitKind::Monetary),
+    MetricDef::instant("Intangibles", &["IntangibleAssetsNetExcludingGoodwill"], UnitKind::Monetary),
+    MetricDef::instant("Cash & Equiv.", &["CashAndCashEquivalentsAtCarryingValue", "CashCashEquivalentsAndShortTermInvestments"], UnitKind::Monetary),
+    MetricDef::instant("Long Term Debt", &["LongTermDebt", "LongTermDebtNoncurrent"], UnitKind::Monetary),
+    MetricDef::instant("Short Term Debt", &["DebtCurrent", "LongTermDebtCurrent"], UnitKind::Monetary),
+    MetricDef::instant("Shares Outstanding", &["CommonStockSharesOutstanding", "WeightedAverageNumberOfDilutedSharesOutstanding", "WeightedAverageNumberOfSharesOutstandingBasicAndDiluted"], UnitKind::Shares),
+];
+
+/// Même liste de métriques logiques, avec les concepts de la taxonomie `ifrs-full`
+/// (émetteurs étrangers déposant en IFRS : 20-F / 40-F).
+pub const IFRS_METRICS: &[MetricDef] = &[
+    // --- FLUX ---
+    MetricDef::flow("Revenue", &["Revenue", "RevenueFromContractsWithCustomers"], UnitKind::Monetary),
+    MetricDef::flow("Cost of Revenue", &["CostOfSales"], UnitKind::Monetary),
+    MetricDef::flow("Gross Profit", &["GrossProfit"], UnitKind::Monetary),
+    MetricDef::flow("Net Income", &["ProfitLossAttributableToOwnersOfParent", "ProfitLoss"], UnitKind::Monetary),
+    MetricDef::flow("Operating Income (EBIT)", &["ProfitLossFromOperatingActivities"], UnitKind::Monetary),
+    MetricDef::flow("EPS Diluted", &["DilutedEarningsLossPerShare", "BasicAndDilutedEarningsLossPerShare"], UnitKind::PerShare),
+    MetricDef::flow("Operating Cash Flow", &["CashFlowsFromUsedInOperatingActivities"], UnitKind::Monetary),
+    MetricDef::flow("CapEx", &["PurchaseOfPropertyPlantAndEquipmentClassifiedAsInvestingActivities", "PurchaseOfPropertyPlantAndEquipment"], UnitKind::Monetary),
+    MetricDef::flow("Dividends Paid", &["DividendsPaidClassifiedAsFinancingActivities", "DividendsPaid"], UnitKind::Monetary),
+    MetricDef::flow("Dividends Per Share", &["DividendsRecognisedAsDistributionsToOwnersPerShare"], UnitKind::PerShare),
+    MetricDef::flow("Buybacks", &["PaymentsToAcquireOrRedeemEntitysShares"], UnitKind::Monetary),
+    MetricDef::flow("Stock Issuance", &["ProceedsFromIssuingShares"], UnitKind::Monetary),
+    MetricDef::flow("Depreciation & Amortization", &["DepreciationAndAmortisationExpense"], UnitKind::Monetary),
+    MetricDef::flow("Interest Expense", &["InterestExpense", "FinanceCosts"], UnitKind::Monetary),
+    MetricDef::flow("Income Tax", &["IncomeTaxExpenseContinuingOperations"], UnitKind::Monetary),
+    MetricDef::flow("Pretax Income", &["ProfitLossBeforeTax"], UnitKind::Monetary),
+    MetricDef::flow("R&D", &["ResearchAndDevelopmentExpense"], UnitKind::Monetary),
+    MetricDef::flow("SBC", &["AdjustmentsForSharebasedPayments"], UnitKind::Monetary),
+
+    // --- STOCKS ---
+    MetricDef::instant("Total Assets", &["Assets"], UnitKind::Monetary),
+    MetricDef::instant("Total Liabilities", &["Liabilities"], UnitKind::Monetary),
+    MetricDef::instant("Total Current Assets", &["CurrentAssets"], UnitKind::Monetary),
+    MetricDef::instant("Total Current Liabilities", &["CurrentLiabilities"], UnitKind::Monetary),
+    MetricDef::instant("Inventory", &["Inventories"], UnitKind::Monetary),
+    MetricDef::instant("Total Equity", &["EquityAttributableToOwnersOfParent", "Equity"], UnitKind::Monetary),
+    MetricDef::instant("Retained Earnings", &["RetainedEarnings"], UnitKind::Monetary),
+    MetricDef::instant("Goodwill", &["Goodwill"], UnitKind::Monetary),
+    MetricDef::instant("Intangibles", &["IntangibleAssetsOtherThanGoodwill"], UnitKind::Monetary),
+    MetricDef::instant("Cash & Equiv.", &["CashAndCashEquivalents"], UnitKind::Monetary),
+    MetricDef::instant("Long Term Debt", &["NoncurrentPortionOfNoncurrentBorrowings", "LongtermBorrowings"], UnitKind::Monetary),
+    MetricDef::instant("Short Term Debt", &["CurrentBorrowingsAndCurrentPortionOfNoncurrentBorrowings", "ShorttermBorrowings"], UnitKind::Monetary),
+    MetricDef::instant("Shares Outstanding", &["NumberOfSharesOutstanding", "AdjustedWeightedAverageShares", "WeightedAverageShares"], UnitKind::Shares),
+];
+
+/// Config de métriques associée à une taxonomie.
+pub fn metrics_for(taxonomy: Taxonomy) -> &'static [MetricDef] {
+    match taxonomy {
+        Taxonomy::UsGaap => US_GAAP_METRICS,
+        Taxonomy::IfrsFull => IFRS_METRICS,
+    }
+}
+
+/// Fait candidat pour une métrique, avant sélection d'une valeur par année.
+struct Candidate<'a> {
+    /// Exercice fiscal du fait (voir `assign_fiscal_years`).
+    year: u16,
+    /// Concept XBRL d'origine.
+    tag: &'a str,
+    /// Rang du tag dans la liste de la métrique (0 = prioritaire).
+    tag_rank: usize,
+    /// Unité SEC du fait (`USD`, `EUR/shares`...).
+    unit: &'a str,
+    fy: Option<u16>,
+    fp: Option<&'a str>,
+    val: f64,
+    end: NaiveDate,
+    /// Vrai si le fait provient d'un dépôt annuel (10-K, 20-F...) avec `fp == "FY"`.
+    annual: bool,
+    /// Date de dépôt : départage un chiffre original et son retraitement (10-K/A...).
+    filed: Option<NaiveDate>,
+    accn: Option<&'a str>,
+    form: Option<&'a str>,
+}
+
+/// Ordre total et stable entre deux faits (le plus grand l'emporte) : dépôt le plus récent, puis
+/// numéro d'enregistrement (ordre lexicographique), puis tag le plus prioritaire, unité et valeur.
+/// Le fait retenu ne dépend ainsi jamais de l'ordre de parcours des `HashMap` : deux extractions
+/// du même `companyfacts` sont identiques.
+fn by_provenance(a: &Candidate, b: &Candidate) -> Ordering {
+    (a.filed, a.accn, Reverse(a.tag_rank), a.unit)
+        .cmp(&(b.filed, b.accn, Reverse(b.tag_rank), b.unit))
+        .then_with(|| a.val.total_cmp(&b.val))
+}
+
+/// Nature de la période d'après le `frame` SEC (`CY2022`, `CY2022Q1`, `CY2022Q4I`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Annual,
+    Quarterly,
+    Instant,
+}
+
+fn frame_kind(frame: &str) -> Option<FrameKind> {
+    let rest = frame.strip_prefix("CY")?;
+    let (year, tail) = rest.split_at(rest.len().min(4));
+    if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) { return None; }
+    match tail {
+        "" => Some(FrameKind::Annual),
+        "Q1" | "Q2" | "Q3" | "Q4" => Some(FrameKind::Quarterly),
+        "Q1I" | "Q2I" | "Q3I" | "Q4I" => Some(FrameKind::Instant),
+        _ => None,
+    }
+}
+
+/// Formulaires de rapport annuel (y compris amendements et périodes de transition).
+fn is_annual_form(form: &str) -> bool {
+    matches!(form, "10-K" | "10-K/A" | "10-KT" | "10-KT/A" | "20-F" | "20-F/A" | "40-F" | "40-F/A")
+}
+
+/// Règle ayant départagé plusieurs valeurs candidates pour un même exercice.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    /// Dépôt le plus récent (flux, ou stocks sans clôture d'exercice identifiable).
+    LatestFiled,
+    /// Date de fin la plus proche de la clôture de l'exercice (stocks).
+    ClosestToYearEnd,
+    /// Valeur absolue maximale (stocks sans dépôt annuel pour l'année).
+    MaxAbs,
+}
+
+/// Origine du tag retenu pour une métrique.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Un des tags de la config.
+    Exact,
+    /// Concept au nom proche, faute de tag de la config publié.
+    Fuzzy,
+}
+
+/// Exercice pour lequel plusieurs valeurs distinctes étaient candidates.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct YearConflict {
+    pub fiscal_year: u16,
+    /// Nombre de valeurs distinctes en concurrence.
+    pub values: usize,
+    pub resolution: Resolution,
+}
+
+/// Origine de la valeur retenue pour un exercice.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct FactSource {
+    pub fiscal_year: u16,
+    /// Date de fin du fait (clôture de l'exercice pour un stock) : date de référence d'un change.
+    pub end: NaiveDate,
+    /// Dépôt EDGAR du fait retenu (`0000320193-23-000106`), pour retrouver le document.
+    pub accn: Option<String>,
+    /// Type de formulaire de ce dépôt (`10-K`, `10-Q`, `20-F`...).
+    pub form: Option<String>,
+}
+
+/// Diagnostic d'extraction d'une métrique annuelle (section `data_quality`).
+#[derive(Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct MetricQuality {
+    /// Concept XBRL ayant fourni les valeurs retenues (le plus prioritaire si plusieurs).
+    pub matched_tag: Option<String>,
+    /// Tag de la config (`exact`) ou concept approché (`fuzzy`, option `--fuzzy`).
+    pub matched_by: Option<MatchKind>,
+    /// Unité SEC des valeurs retenues (`USD`, `EUR`, `JPY/shares`...), la plus fréquente si plusieurs.
+    pub unit: Option<String>,
+    /// Faits lus dans les unités compatibles, tous tags confondus.
+    pub raw_facts: usize,
+    /// Faits écartés : sans valeur ni date, durée hors période, comparatif non aligné.
+    pub filtered_out: usize,
+    pub conflicts: Vec<YearConflict>,
+    /// Fait retenu pour chaque exercice, par ordre chronologique.
+    pub sources: Vec<FactSource>,
+    /// Valeurs aberrantes détectées après extraction (voir `sanity::check_outliers`).
+    pub outliers: Vec<Outlier>,
+}
+
+/// Diagnostic d'extraction indexé par nom de métrique.
+pub type DataQuality = HashMap<String, MetricQuality>;
+
+/// Concept publié dans un `companyfacts`, avec son nombre d'unités et de faits (`--concepts`).
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConceptSummary {
+    pub name: String,
+    pub units: usize,
+    pub facts: usize,
+}
+
+/// Inventaire trié des concepts d'une taxonomie, pour repérer les tags à ajouter à la config.
+pub fn list_concepts(facts: &HashMap<String, FactData>) -> Vec<ConceptSummary> {
+    let mut concepts: Vec<ConceptSummary> = facts
+        .iter()
+        .map(|(name, data)| ConceptSummary {
+            name: name.clone(),
+            units: data.units.len(),
+            facts: data.units.values().map(Vec::len).sum(),
+        })
+        .collect();
+    concepts.sort_by(|a, b| a.name.cmp(&b.name));
+    concepts
+}
+
+/// Granularité d'extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Period {
+    /// Exercices complets (défaut).
+    #[default]
+    Annual,
+    /// Trimestres fiscaux, indexés `AAAA-Qn`.
+    Quarterly,
+}
+
+/// Extrait une série (année, valeur) par métrique depuis les facts d'une taxonomie.
+pub fn extract_financials(facts: &HashMap<String, FactData>, config: &[MetricDef]) -> HashMap<String, Vec<(u16, f64)>> {
+    extract_with_quality(facts, config, false).0
+}
+
+/// Comme `extract_financials`, avec en plus le diagnostic d'extraction de chaque métrique.
+///
+/// Avec `fuzzy`, une métrique dont aucun tag n'est publié se rabat sur le concept le plus
+/// proche de ses tags (voir `fuzzy_tag`), signalé par `matched_by: fuzzy`.
+pub fn extract_with_quality(
+    facts: &HashMap<String, FactData>,
+    config: &[MetricDef],
+    fuzzy: bool,
+) -> (HashMap<String, Vec<(u16, f64)>>, DataQuality) {
+    let mut results: HashMap<String, Vec<(u16, f64)>> = HashMap::new();
+    let mut quality = DataQuality::new();
+
+    for def in config {
+        let _span = debug_span!("metric", name = def.name, period = "annual").entered();
+        let fallback = (fuzzy && !def.tags.iter().any(|t| facts.contains_key(*t)))
+            .then(|| fuzzy_tag(facts, def, config))
+            .flatten();
+        let tags: Vec<&str> = match fallback {
+            Some(tag) => {
+                debug!(tag, "tag approché retenu");
+                vec![tag]
+            }
+            None => def.tags.to_vec(),
+        };
+        let (candidates, raw_facts) = collect_candidates(facts, def, &tags, Period::Annual);
+        let fiscal_year_end = fiscal_year_end(&candidates);
+
+        let mut by_year: HashMap<u16, Vec<&Candidate>> = HashMap::new();
+        for c in &candidates {
+            by_year.entry(c.year).or_default().push(c);
+        }
+
+        let mut conflicts = Vec::new();
+        let mut tags_used = Vec::new();
+        let mut units_used: HashMap<&str, usize> = HashMap::new();
+        let mut final_vec: Vec<(u16, f64)> = Vec::new();
+        let mut sources = Vec::new();
+        for (year, cands) in &by_year {
+            let Some((chosen, resolution)) = select_value(cands, def.is_instant, fiscal_year_end) else { continue };
+            let values = distinct_values(cands);
+            if values > 1 {
+                conflicts.push(YearConflict { fiscal_year: *year, values, resolution });
+            }
+            tags_used.push(chosen.tag);
+            *units_used.entry(chosen.unit).or_default() += 1;
+            final_vec.push((*year, chosen.val));
+            sources.push(FactSource {
+                fiscal_year: *year,
+                end: chosen.end,
+                accn: chosen.accn.map(str::to_string),
+                form: chosen.form.map(str::to_string),
+            });
+        }
+        final_vec.sort_by_key(|k| k.0);
+        conflicts.sort_by_key(|c| c.fiscal_year);
+        sources.sort_by_key(|s| s.fiscal_year);
+        debug!(years = final_vec.len(), conflicts = conflicts.len(), "série annuelle retenue");
+
+        let matched_tag = tags.iter().find(|t| tags_used.contains(t)).map(|t| t.to_string());
+        let metric_quality = MetricQuality {
+            matched_by: matched_tag.as_ref().map(|_| if fallback.is_some() { MatchKind::Fuzzy } else { MatchKind::Exact }),
+            matched_tag,
+            unit: most_frequent(units_used),
+            raw_facts,
+            filtered_out: raw_facts - candidates.len(),
+            conflicts,
+            sources,
+            outliers: Vec::new(),
+        };
+        quality.insert(def.name.to_string(), metric_quality);
+        results.insert(def.name.to_string(), final_vec);
+    }
+
+    (results, quality)
+}
+
+/// Clé la plus fréquente ; à égalité, la première dans l'ordre alphabétique.
+fn most_frequent(counts: HashMap<&str, usize>) -> Option<String> {
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(key, _)| key.to_string())
+}
+
+/// Devise de publication : la devise la plus fréquente parmi les unités monétaires
+/// et par action retenues. Aucune conversion n'est faite ; elle sert à interpréter les montants.
+pub fn reporting_currency(quality: &DataQuality) -> Option<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for unit in quality.values().filter_map(|q| q.unit.as_deref()) {
+        let currency = unit.strip_suffix("/shares").unwrap_or(unit);
+        if is_currency(currency) {
+            *counts.entry(currency).or_default() += 1;
+        }
+    }
+    most_frequent(counts)
+}
+
+/// Nombre de valeurs distinctes parmi les candidats d'un exercice.
+fn distinct_values(cands: &[&Candidate]) -> usize {
+    let mut values: Vec<f64> = cands.iter().map(|c| c.val).collect();
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    values.len()
+}
+
+/// Extrait une série trimestrielle par métrique, indexée par (`fy`, `fp`) : `2023-Q2`.
+///
+/// Flux : faits d'une durée de ~1 trimestre (80-100 jours). Le T4 n'a pas de 10-Q ; il n'apparaît
+/// que si le 10-K publie explicitement le trimestre. Stocks : snapshots de fin de trimestre,
+/// le bilan de clôture du 10-K servant de T4.
+pub fn extract_quarterly(facts: &HashMap<String, FactData>, config: &[MetricDef]) -> HashMap<String, Vec<PeriodValue>> {
+    let mut results = HashMap::new();
+
+    for def in config {
+        let _span = debug_span!("metric", name = def.name, period = "quarterly").entered();
+        let (candidates, _) = collect_candidates(facts, def, def.tags, Period::Quarterly);
+
+        let mut by_quarter: HashMap<(u16, u8), Vec<&Candidate>> = HashMap::new();
+        for c in &candidates {
+            if let Some(q) = c.fp.and_then(quarter_of) {
+                by_quarter.entry((c.year, q)).or_default().push(c);
+            }
+        }
+
+        let mut keyed: Vec<((u16, u8), f64)> = by_quarter
+            .into_iter()
+            .filter_map(|(key, cands)| cands.into_iter().max_by(|a, b| by_provenance(a, b)).map(|c| (key, c.val)))
+            .collect();
+        keyed.sort_by_key(|k| k.0);
+        debug!(quarters = keyed.len(), "série trimestrielle retenue");
+
+        let series = keyed
+            .into_iter()
+            .map(|((year, q), value)| PeriodValue { period: format!("{}-Q{}", year, q), value })
+            .collect();
+        results.insert(def.name.to_string(), series);
+    }
+
+    results
+}
+
+/// Applique les informations de page de garde (`dei`) : le nombre d'actions
+/// `EntityCommonStockSharesOutstanding` d'un rapport annuel remplace, pour son exercice,
+/// la valeur `Shares Outstanding` tirée des tags GAAP (souvent une moyenne pondérée).
+///
+/// Un déclarant à plusieurs catégories d'actions (GOOGL/GOOG, BRK-A/BRK-B) publie un fait
+/// par catégorie, dans le même dépôt et à la même date : ces faits sont additionnés. Pour un
+/// exercice, le dépôt le plus récent l'emporte.
+///
+/// Le diagnostic de `Shares Outstanding` suit le remplacement : source (dépôt, formulaire,
+/// date) des exercices remplacés, tag et unité de la page de garde ; les conflits entre
+/// tags GAAP de ces exercices n'ont plus d'objet.
+pub fn apply_cover_shares(results: &mut HashMap<String, Vec<(u16, f64)>>, quality: &mut DataQuality, dei: &HashMap<String, FactData>) {
+    let mut by_filing: HashMap<(u16, Option<&str>, Option<&str>), CoverShares> = HashMap::new();
+    for unit in annual_dei_facts(dei, COVER_SHARES_CONCEPT, "shares") {
+        let (Some(fy), Some(val)) = (unit.fy, unit.val) else { continue };
+        let key = (fy, unit.accn.as_deref(), unit.end.as_deref());
+        by_filing
+            .entry(key)
+            .or_insert(CoverShares {
+                filed: parse_date(unit.filed.as_deref()),
+                end: unit.end.as_deref(),
+                accn: unit.accn.as_deref(),
+                form: unit.form.as_deref(),
+                total: 0.0,
+            })
+            .total += val;
+    }
+    let mut latest: HashMap<u16, CoverShares> = HashMap::new();
+    for ((fy, _, _), candidate) in by_filing {
+        match latest.get(&fy) {
+            // Ordre total (dépôt, date, valeur) : le résultat ne dépend pas de l'ordre du HashMap
+            Some(kept) if (kept.filed, kept.end).cmp(&(candidate.filed, candidate.end)).then(kept.total.total_cmp(&candidate.total)).is_ge() => {}
+            _ => {
+                latest.insert(fy, candidate);
+            }
+        }
+    }
+    if latest.is_empty() { return; }
+
+    let metric_quality = quality.entry("Shares Outstanding".to_string()).or_default();
+    metric_quality.matched_tag = Some(COVER_SHARES_CONCEPT.to_string());
+    metric_quality.matched_by = Some(MatchKind::Exact);
+    metric_quality.unit = Some("shares".to_string());
+    metric_quality.sources.retain(|s| !latest.contains_key(&s.fiscal_year));
+    metric_quality.conflicts.retain(|c| !latest.contains_key(&c.fiscal_year));
+    metric_quality.sources.extend(latest.iter().filter_map(|(&fiscal_year, shares)| {
+        Some(FactSource {
+            fiscal_year,
+            end: parse_date(shares.end)?,
+            accn: shares.accn.map(str::to_string),
+            form: shares.form.map(str::to_string),
+        })
+    }));
+    metric_quality.sources.sort_by_key(|s| s.fiscal_year);
+
+    let series = results.entry("Shares Outstanding".to_string()).or_default();
+    series.retain(|(year, _)| !latest.contains_key(year));
+    series.extend(latest.into_iter().map(|(year, shares)| (year, shares.total)));
+    series.sort_by_key(|k| k.0);
+}
+
+/// Faits `EntityCommonStockSharesOutstanding` d'un même dépôt à une même date, additionnés.
+struct CoverShares<'a> {
+    filed: Option<NaiveDate>,
+    end: Option<&'a str>,
+    accn: Option<&'a str>,
+    form: Option<&'a str>,
+    total: f64,
+}
+
+/// Concept `dei` du nombre d'actions en circulation publié en page de garde.
+const COVER_SHARES_CONCEPT: &str = "EntityCommonStockSharesOutstanding";
+
+/// Dernier flottant publié (`dei:EntityPublicFloat`), indexé par sa date de mesure.
+pub fn latest_public_float(dei: &HashMap<String, FactData>) -> Option<PeriodValue> {
+    annual_dei_facts(dei, "EntityPublicFloat", "USD")
+        .filter_map(|unit| Some((unit.end.clone()?, unit.filed.clone(), unit.val?)))
+        .max_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)))
+        .map(|(period, _, value)| PeriodValue { period, value })
+}
+
+/// Faits `dei` d'un concept publiés dans un rapport annuel.
+fn annual_dei_facts<'a>(dei: &'a HashMap<String, FactData>, concept: &str, unit: &str) -> impl Iterator<Item = &'a FactUnit> {
+    dei.get(concept)
+        .and_then(|data| data.units.get(unit))
+        .into_iter()
+        .flatten()
+        .filter(|u| u.form.as_deref().is_some_and(is_annual_form))
+}
+
+/// Numéro de trimestre fiscal d'après `fp` (le `FY` d'un 10-K tient lieu de T4).
+fn quarter_of(fp: &str) -> Option<u8> {
+    match fp {
+        "Q1" => Some(1),
+        "Q2" => Some(2),
+        "Q3" => Some(3),
+        "Q4" | "FY" => Some(4),
+        _ => None,
+    }
+}
+
+/// Similarité minimale (distance d'édition normalisée) pour accepter un concept approché.
+const FUZZY_MIN_SIMILARITY: f64 = 0.85;
+
+/// Concept publié le plus proche des tags d'une métrique, s'il est assez sûr : nom contenant
+/// un tag (ou contenu dans un tag) et de longueur comparable, ou similarité d'édition d'au
+/// moins `FUZZY_MIN_SIMILARITY`. Les concepts déjà prévus par la config pour une autre
+/// métrique et ceux sans unité compatible sont exclus.
+fn fuzzy_tag<'a>(facts: &'a HashMap<String, FactData>, def: &MetricDef, config: &[MetricDef]) -> Option<&'a str> {
+    let reserved = |name: &str| config.iter().any(|d| d.tags.contains(&name));
+    facts
+        .iter()
+        .filter(|(name, data)| !reserved(name) && data.units.keys().any(|u| def.expected_unit.matches(u)))
+        .filter_map(|(name, _)| {
+            let score = def.tags.iter().map(|tag| tag_similarity(tag, name)).fold(0.0, f64::max);
+            (score >= FUZZY_MIN_SIMILARITY).then_some((name.as_str(), score))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(name, _)| name)
+}
+
+/// Similarité entre 0 et 1 de deux noms de concept. L'inclusion de l'un dans l'autre compte
+/// comme une correspondance si les longueurs diffèrent de moins de 40 %.
+fn tag_similarity(tag: &str, name: &str) -> f64 {
+    let (tag, name) = (tag.to_lowercase(), name.to_lowercase());
+    let (short, long) = if tag.len() <= name.len() { (&tag, &name) } else { (&name, &tag) };
+    if long.contains(short.as_str()) && short.len() as f64 >= 0.6 * long.len() as f64 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&tag, &name) as f64 / long.len().max(1) as f64
+}
+
+/// Distance d'édition (insertions, suppressions, substitutions) entre deux chaînes.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Collecte les faits d'une métrique compatibles avec la granularité demandée,
+/// déjà rattachés à leur exercice fiscal, avec le nombre de faits bruts examinés.
+fn collect_candidates<'a>(facts: &'a HashMap<String, FactData>, def: &MetricDef, tags: &[&str], period: Period) -> (Vec<Candidate<'a>>, usize) {
+    let mut candidates = Vec::new();
+    let mut raw = 0;
+
+    for (tag_rank, tag) in tags.iter().enumerate() {
+        let Some((tag, data)) = facts.get_key_value(*tag) else {
+            debug!(tag, "concept absent");
+            continue;
+        };
+        let tag = tag.as_str();
+        // On ne garde que les unités de la dimension attendue (USD, shares, USD/shares...)
+        // pour ne pas mélanger des valeurs incomparables avant le dédoublonnage
+        let mut units_by_name: Vec<(&String, &Vec<FactUnit>)> = data.units.iter().collect();
+        units_by_name.sort_by_key(|(name, _)| *name);
+        for (unit_name, units) in units_by_name {
+            if !def.expected_unit.matches(unit_name) { continue; }
+            raw += units.len();
+            for unit in units {
+                let Some(val) = unit.val else { continue };
+                // CONDITION SINE QUA NON : Avoir une date de fin
+                let Some(d_end) = parse_date(unit.end.as_deref()) else { continue };
+
+                // CAS 1 : FLUX (Revenue, OCF, SBC...) : la durée doit correspondre à la période.
+                // CAS 2 : STOCKS (Shares, Debt, Equity...) : on prend tout ce qui a une date,
+                // la sélection plus bas fait le tri entre Q1, Q2, Q3 et FY.
+                if !def.is_instant && !has_period_duration(unit, d_end, period) { continue; }
+
+                let annual = unit.fp.as_deref() == Some("FY")
+                    && unit.form.as_deref().is_some_and(is_annual_form);
+                let filed = parse_date(unit.filed.as_deref());
+                candidates.push(Candidate {
+                    year: d_end.year() as u16,
+                    tag,
+                    tag_rank,
+                    unit: unit_name,
+                    fy: unit.fy,
+                    fp: unit.fp.as_deref(),
+                    val,
+                    end: d_end,
+                    annual,
+                    filed,
+                    accn: unit.accn.as_deref(),
+                    form: unit.form.as_deref(),
+                });
+            }
+        }
+        if def.exclusive_tags && !candidates.is_empty() {
+            break;
+        }
+    }
+
+    let kept_by_period = candidates.len();
+    assign_fiscal_years(&mut candidates);
+    debug!(raw, kept_by_period, aligned = candidates.len(), "faits filtrés");
+    (candidates, raw)
+}
+
+/// Vrai si un fait de flux couvre la période voulue. Le `frame` SEC (CY2022 vs CY2022Q1)
+/// tranche quand il est présent ; sinon il faut une date de début pour mesurer la durée.
+///
+/// Année complète : 340-380 jours, ce qui couvre les calendriers 52/53 semaines (364 ou
+/// 371 jours) et les comparatifs retraités aux bornes légèrement décalées. La fenêtre étant
+/// large, on exige en plus `fp == "FY"` pour écarter les cumuls partiels d'environ 361 jours.
+/// Trimestre : 80-100 jours.
+fn has_period_duration(unit: &FactUnit, d_end: NaiveDate, period: Period) -> bool {
+    let expected = match period {
+        Period::Annual => FrameKind::Annual,
+        Period::Quarterly => FrameKind::Quarterly,
+    };
+    if let Some(kind) = unit.frame.as_deref().and_then(frame_kind) {
+        return kind == expected;
+    }
+
+    let Some(d_start) = parse_date(unit.start.as_deref()) else { return false };
+    let duration_days = (d_end - d_start).num_days();
+    match period {
+        Period::Annual => ANNUAL_DURATION_DAYS.contains(&duration_days) && unit.fp.as_deref() == Some("FY"),
+        Period::Quarterly => (80..=100).contains(&duration_days),
+    }
+}
+
+/// Durée (jours) acceptée pour un flux annuel sans `frame`.
+const ANNUAL_DURATION_DAYS: std::ops::RangeInclusive<i64> = 340..=380;
+
+/// Rattache chaque fait à son exercice fiscal.
+///
+/// Le champ `fy` de la SEC est l'exercice *du dépôt*, pas du fait : un 10-K FY2023 contient aussi
+/// les comparatifs 2022 et 2021 avec `fy = 2023`. On prend donc comme référence la date de fin la
+/// plus tardive de chaque groupe (`fy`, `fp`) — la période courante du dépôt — et on recule d'autant
+/// d'années que le fait est ancien. L'exercice suit ainsi la convention de l'entreprise, y compris
+/// pour les clôtures décalées (septembre chez Apple, fin janvier chez les distributeurs) où
+/// `end.year()` se trompe d'un an.
+///
+/// Les faits sans `fy` gardent `end.year()`. Ceux qui ne tombent pas à un nombre entier d'années
+/// de la période de référence (ex. bilan de clôture repris dans un 10-Q) ne peuvent pas être
+/// rattachés sans ambiguïté et sont écartés.
+fn assign_fiscal_years(candidates: &mut Vec<Candidate>) {
+    let mut anchors: HashMap<(u16, Option<&str>), NaiveDate> = HashMap::new();
+    for c in candidates.iter() {
+        if let Some(fy) = c.fy {
+            let anchor = anchors.entry((fy, c.fp)).or_insert(c.end);
+            if c.end > *anchor { *anchor = c.end; }
+        }
+    }
+
+    candidates.retain_mut(|c| {
+        let Some(fy) = c.fy else { return true };
+        let days = (anchors[&(fy, c.fp)] - c.end).num_days() as f64;
+        let offset = (days / 365.25).round();
+        if (days - offset * 365.25).abs() > ALIGNMENT_TOLERANCE_DAYS { return false; }
+        match fy.checked_sub(offset as u16) {
+            Some(year) => { c.year = year; true }
+            None => false,
+        }
+    });
+}
+
+/// Écart toléré (jours) entre un comparatif et l'anniversaire de la période de référence :
+/// couvre les calendriers 52/53 semaines.
+const ALIGNMENT_TOLERANCE_DAYS: f64 = 45.0;
+
+/// Choisit la valeur d'une année parmi les candidats.
+///
+/// - Flux : la valeur déposée le plus récemment, pour retenir un éventuel retraitement
+///   (souvent à la baisse) plutôt que le chiffre d'origine. Les valeurs annuelles
+///   (`fp == "FY"` d'un 10-K) sont prioritaires ; tous les candidats couvrant déjà une année
+///   complète (voir `has_period_duration`), la règle du MAX absolu ne s'applique pas.
+/// - Stocks : la valeur annuelle dont la date de fin est la plus proche de la clôture de l'exercice
+///   (puis la plus récemment déposée).
+/// - Stocks sans dépôt annuel pour l'année : on retombe sur l'ancienne heuristique du MAX absolu,
+///   qui élimine les valeurs trimestrielles (souvent plus petites).
+fn select_value<'c, 'a>(cands: &[&'c Candidate<'a>], is_instant: bool, fiscal_year_end: Option<(u32, u32)>) -> Option<(&'c Candidate<'a>, Resolution)> {
+    let annual: Vec<&Candidate> = cands.iter().copied().filter(|c| c.annual).collect();
+    if !is_instant {
+        let pool = if annual.is_empty() { cands } else { &annual };
+        return pool.iter().copied().max_by(|a, b| by_provenance(a, b)).map(|c| (c, Resolution::LatestFiled));
+    }
+    if annual.is_empty() {
+        return max_abs(cands).map(|c| (c, Resolution::MaxAbs));
+    }
+
+    match fiscal_year_end {
+        Some(fye) => annual
+            .into_iter()
+            .min_by(|a, b| {
+                days_from_year_end(a.end, fye)
+                    .cmp(&days_from_year_end(b.end, fye))
+                    .then_with(|| by_provenance(b, a))
+            })
+            .map(|c| (c, Resolution::ClosestToYearEnd)),
+        None => annual.into_iter().max_by(|a, b| by_provenance(a, b)).map(|c| (c, Resolution::LatestFiled)),
+    }
+}
+
+fn max_abs<'c, 'a>(cands: &[&'c Candidate<'a>]) -> Option<&'c Candidate<'a>> {
+    cands
+        .iter()
+        .copied()
+        .max_by(|a, b| a.val.abs().total_cmp(&b.val.abs()).then_with(|| by_provenance(a, b)))
+}
+
+/// Clôture d'exercice (mois, jour) la plus fréquente parmi les faits annuels.
+fn fiscal_year_end(candidates: &[Candidate]) -> Option<(u32, u32)> {
+    let mut counts: HashMap<(u32, u32), usize> = HashMap::new();
+    for c in candidates.iter().filter(|c| c.annual) {
+        *counts.entry((c.end.month(), c.end.day())).or_default() += 1;
+    }
+    counts.into_iter().max_by_key(|&(md, n)| (n, md)).map(|(md, _)| md)
+}
+
+/// Distance (jours) entre une date et la date anniversaire de clôture la plus proche.
+/// L'exercice pouvant se terminer l'année civile suivante (clôture fin janvier),
+/// on compare aux anniversaires de l'année précédente, courante et suivante.
+fn days_from_year_end(end: NaiveDate, (month, day): (u32, u32)) -> i64 {
+    (end.year() - 1..=end.year() + 1)
+        .filter_map(|y| year_end_date(y, month, day))
+        .map(|target| (end - target).num_days().abs())
+        .min()
+        .unwrap_or(i64::MAX)
+}
+
+/// Date de clôture pour une année donnée (le 29 février retombe au 28 les années non bissextiles).
+fn year_end_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .or_else(|| NaiveDate::from_ymd_opt(year, month, day - 1))
+}
+
+fn parse_date(raw: Option<&str>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(raw?, "%Y-%m-%d").ok()
+}
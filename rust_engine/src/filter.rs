@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use crate::models::PeriodValue;
+
+/// Ne garde que les `n` derniers exercices.
+///
+/// La fenêtre est calculée sur l'exercice le plus récent toutes métriques confondues,
+/// pour que les séries restent alignées année par année (une métrique sans chiffre
+/// récent n'étend pas sa fenêtre vers le passé).
+pub fn last_years(financials: &mut HashMap<String, Vec<(u16, f64)>>, n: u16) {
+    let Some(latest) = financials.values().flatten().map(|&(y, _)| y).max() else { return };
+    let first = latest.saturating_sub(n.saturating_sub(1));
+    for series in financials.values_mut() {
+        series.retain(|&(y, _)| y >= first);
+    }
+}
+
+/// Comme `last_years`, pour les séries trimestrielles (`--period quarterly`) : la fenêtre
+/// part de l'exercice le plus récent de ces séries.
+pub fn last_years_quarterly(quarterly: &mut HashMap<String, Vec<PeriodValue>>, n: u16) {
+    let Some(latest) = quarterly.values().flatten().filter_map(PeriodValue::fiscal_year).max() else { return };
+    let first = latest.saturating_sub(n.saturating_sub(1));
+    for series in quarterly.values_mut() {
+        series.retain(|v| v.fiscal_year().is_some_and(|y| y >= first));
+    }
+}
+
+/// Ne garde que les exercices compris entre `min` et `max` (bornes incluses).
+pub fn year_range(financials: &mut HashMap<String, Vec<(u16, f64)>>, min: Option<u16>, max: Option<u16>) {
+    let (min, max) = (min.unwrap_or(u16::MIN), max.unwrap_or(u16::MAX));
+    for series in financials.values_mut() {
+        series.retain(|&(y, _)| (min..=max).contains(&y));
+    }
+}
+
+/// Comme `year_range`, pour les séries trimestrielles (exercice de la période `AAAA-Qn`).
+pub fn year_range_quarterly(quarterly: &mut HashMap<String, Vec<PeriodValue>>, min: Option<u16>, max: Option<u16>) {
+    let (min, max) = (min.unwrap_or(u16::MIN), max.unwrap_or(u16::MAX));
+    for series in quarterly.values_mut() {
+        series.retain(|v| v.fiscal_year().is_some_and(|y| (min..=max).contains(&y)));
+    }
+}
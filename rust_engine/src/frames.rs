@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use tracing::{debug, instrument};
+
+use crate::error::{EngineError, Result};
+use crate::sec::SecClient;
+
+/// Réponse de l'API `frames` : un concept pour tous les déclarants sur une période.
+#[derive(Deserialize, Debug)]
+struct FrameResponse {
+    data: Vec<FramePoint>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FramePoint {
+    cik: u64,
+    val: f64,
+}
+
+/// Requête `frames` découpée depuis la CLI : `Revenues/USD/CY2022`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameQuery {
+    pub concept: String,
+    pub unit: String,
+    pub period: String,
+}
+
+impl FrameQuery {
+    /// Analyse `CONCEPT/UNITE/PERIODE` ; la période suit la syntaxe SEC
+    /// (`CY2022` annuel, `CY2022Q1` trimestriel, `CY2022Q4I` instantané).
+    pub fn parse(raw: &str) -> Result<Self> {
+        let invalid = || EngineError::InvalidArgument(format!("frame attend CONCEPT/UNITE/PERIODE (ex. Revenues/USD/CY2022), reçu '{}'", raw));
+        let mut parts = raw.split('/').map(str::trim);
+        let (Some(concept), Some(unit), Some(period), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        if concept.is_empty() || unit.is_empty() || !is_frame_period(period) {
+            return Err(invalid());
+        }
+        Ok(FrameQuery { concept: concept.to_string(), unit: unit.to_string(), period: period.to_string() })
+    }
+}
+
+/// `CYaaaa`, suivi éventuellement de `Qn` et de `I` (instantané).
+fn is_frame_period(period: &str) -> bool {
+    let Some(rest) = period.strip_prefix("CY").filter(|r| r.is_ascii()) else { return false };
+    let (year, rest) = rest.split_at(rest.len().min(4));
+    if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) { return false; }
+    let rest = rest.strip_suffix('I').unwrap_or(rest);
+    match rest.strip_prefix('Q') {
+        Some(q) => matches!(q, "1" | "2" | "3" | "4"),
+        None => rest.is_empty(),
+    }
+}
+
+/// Télécharge un concept `us-gaap` pour tous les déclarants sur une période
+/// (`/api/xbrl/frames/us-gaap/{concept}/{unit}/{period}.json`) : paires (CIK, valeur).
+#[instrument(level = "debug", skip(client))]
+pub async fn fetch_frame(client: &SecClient, concept: &str, unit: &str, period: &str) -> Result<Vec<(u64, f64)>> {
+    let url = client.data_url(&format!("/api/xbrl/frames/us-gaap/{}/{}/{}.json", concept, unit, period));
+    let frame: FrameResponse = client.http().fetch_with_retry(&url).await?.json()?;
+    debug!(points = frame.data.len(), "frame téléchargée");
+    Ok(frame.data.into_iter().map(|p| (p.cik, p.val)).collect())
+}
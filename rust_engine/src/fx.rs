@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::future::Future;
+use std::sync::Mutex;
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::cache::Cache;
+use crate::derive::derive_metrics;
+use crate::error::{EngineError, Result};
+use crate::extract::is_currency;
+use crate::http::HttpClient;
+use crate::models::CompanyFinancials;
+
+/// Fournisseur de taux par défaut : Frankfurter (taux de référence BCE, gratuit, sans clé).
+pub const DEFAULT_FX_URL: &str = "https://api.frankfurter.app";
+
+/// Remplace l'hôte du fournisseur de taux (proxy, serveur de test).
+pub const FX_BASE_URL_ENV: &str = "FX_BASE_URL";
+
+/// Source de taux de change vers l'USD.
+pub trait RateProvider {
+    /// Nombre d'USD pour une unité de `currency` à la date `date` (ou au dernier jour
+    /// de cotation qui la précède).
+    fn usd_rate(&self, currency: &str, date: NaiveDate) -> impl Future<Output = Result<f64>> + Send;
+}
+
+/// Client de l'API Frankfurter : `GET /{date}?from={devise}&to=USD`.
+#[derive(Debug)]
+pub struct Frankfurter {
+    http: HttpClient,
+    base: String,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    rates: HashMap<String, f64>,
+}
+
+impl Frankfurter {
+    pub fn new(http: HttpClient, base: &str) -> Self {
+        Frankfurter { http, base: base.trim_end_matches('/').to_string() }
+    }
+
+    /// Hôte par défaut, sauf surcharge par `FX_BASE_URL`.
+    pub fn from_env(http: HttpClient) -> Self {
+        let base = env::var(FX_BASE_URL_ENV).ok().filter(|v| !v.trim().is_empty());
+        Frankfurter::new(http, base.as_deref().unwrap_or(DEFAULT_FX_URL))
+    }
+}
+
+impl RateProvider for Frankfurter {
+    async fn usd_rate(&self, currency: &str, date: NaiveDate) -> Result<f64> {
+        let url = format!("{}/{}?from={}&to=USD", self.base, date, currency);
+        let resp: RatesResponse = self.http.fetch_with_retry(&url).await?.json()?;
+        resp.rates
+            .get("USD")
+            .copied()
+            .ok_or_else(|| EngineError::ExchangeRate { currency: currency.to_string(), date })
+    }
+}
+
+/// Taux mémorisés par (devise, date) : en mémoire pour le lot en cours et, avec un cache
+/// disque, d'un lancement à l'autre (un taux historique ne change pas).
+pub struct CachedRates<P> {
+    provider: P,
+    rates: Mutex<HashMap<(String, NaiveDate), f64>>,
+    cache: Option<Cache>,
+}
+
+impl<P: RateProvider> CachedRates<P> {
+    pub fn new(provider: P, cache: Option<Cache>) -> Self {
+        let rates = cache.as_ref().map(Cache::load_fx_rates).unwrap_or_default();
+        CachedRates { provider, rates: Mutex::new(rates), cache }
+    }
+
+    pub async fn rate(&self, currency: &str, date: NaiveDate) -> Result<f64> {
+        let key = (currency.to_string(), date);
+        if let Some(&rate) = self.lock().get(&key) {
+            return Ok(rate);
+        }
+        let rate = self.provider.usd_rate(currency, date).await?;
+        debug!(currency, %date, rate, "taux de change téléchargé");
+        let mut rates = self.lock();
+        rates.insert(key, rate);
+        if let Some(cache) = &self.cache {
+            cache.store_fx_rates(&rates);
+        }
+        Ok(rate)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, NaiveDate), f64>> {
+        self.rates.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Taux appliqués lors d'une conversion (section `fx`).
+#[derive(Serialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+pub struct FxConversion {
+    pub to: String,
+    /// Taux par devise d'origine puis par date de fin des faits convertis.
+    pub rates: BTreeMap<String, BTreeMap<NaiveDate, f64>>,
+}
+
+/// Convertit en USD les séries monétaires et par action publiées dans une autre devise,
+/// au taux de la date de fin de chaque fait retenu. Les métriques dérivées (FCF, actif net
+/// par action...) sont ensuite recalculées à partir des valeurs converties.
+pub async fn convert_to_usd<P: RateProvider>(data: &mut CompanyFinancials, rates: &CachedRates<P>) -> Result<()> {
+    let needs_conversion = data.data_quality.values().any(|q| foreign_currency(q.unit.as_deref()).is_some());
+    if !needs_conversion {
+        return Ok(());
+    }
+
+    // Les dérivées, recalculées plus bas, et les années complétées par calcul (sans fait source)
+    // ne doivent pas garder des valeurs en devise d'origine
+    data.financials.retain(|name, _| data.data_quality.contains_key(name));
+    let mut applied = FxConversion { to: "USD".to_string(), ..Default::default() };
+    for (name, series) in data.financials.iter_mut() {
+        let Some(quality) = data.data_quality.get_mut(name) else { continue };
+        if quality.unit.as_deref() == Some("shares") { continue; }
+        let ends: HashMap<u16, NaiveDate> = quality.sources.iter().map(|s| (s.fiscal_year, s.end)).collect();
+        series.retain(|(year, _)| ends.contains_key(year));
+        let Some(unit) = quality.unit.clone() else { continue };
+        let Some(currency) = foreign_currency(Some(&unit)) else { continue };
+        for (year, value) in series.iter_mut() {
+            let date = ends[year];
+            let rate = rates.rate(currency, date).await?;
+            *value *= rate;
+            applied.rates.entry(currency.to_string()).or_default().insert(date, rate);
+        }
+        quality.unit = Some(unit.replacen(currency, "USD", 1));
+    }
+    derive_metrics(&mut data.financials);
+    data.fx = Some(applied);
+    Ok(())
+}
+
+/// Devise d'une unité monétaire (`EUR`) ou par action (`EUR/shares`), sauf l'USD.
+fn foreign_currency(unit: Option<&str>) -> Option<&str> {
+    let unit = unit?;
+    let currency = unit.strip_suffix("/shares").unwrap_or(unit);
+    (is_currency(currency) && currency != "USD").then_some(currency)
+}
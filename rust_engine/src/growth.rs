@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+/// Taux de croissance annuel composé (CAGR) de chaque métrique listée.
+///
+/// On compare la première et la dernière valeur disponibles : `(dernière / première)^(1/années) - 1`,
+/// où `années` est l'écart entre les deux exercices. `window` restreint le point de départ aux
+/// N derniers exercices. Le résultat vaut `None` (null en JSON) quand il n'a pas de sens :
+/// valeur de départ nulle ou négative, valeur finale négative, moins de deux exercices.
+pub fn compute_cagr(
+    results: &HashMap<String, Vec<(u16, f64)>>,
+    metrics: &[&str],
+    window: Option<u16>,
+) -> HashMap<String, Option<f64>> {
+    metrics
+        .iter()
+        .filter_map(|&name| results.get(name).map(|series| (name.to_string(), cagr(series, window))))
+        .collect()
+}
+
+fn cagr(series: &[(u16, f64)], window: Option<u16>) -> Option<f64> {
+    let &(last_year, last) = series.last()?;
+    let first_allowed = window.map_or(0, |n| last_year.saturating_sub(n));
+    let &(first_year, first) = series.iter().find(|&&(y, _)| y >= first_allowed)?;
+
+    let years = last_year.checked_sub(first_year).filter(|&n| n > 0)?;
+    if first <= 0.0 || last < 0.0 { return None; }
+
+    let rate = (last / first).powf(1.0 / years as f64) - 1.0;
+    rate.is_finite().then_some(rate)
+}
+
+/// Variation annuelle (YoY) de chaque métrique : `(courant - précédent) / |précédent|`.
+///
+/// Seules les paires d'exercices consécutifs sont comparées ; une année précédée d'un trou
+/// dans la série ou d'une valeur nulle est omise.
+pub fn yoy_growth(results: &HashMap<String, Vec<(u16, f64)>>) -> HashMap<String, Vec<(u16, f64)>> {
+    results
+        .iter()
+        .map(|(name, series)| {
+            let changes = series
+                .windows(2)
+                .filter(|w| w[1].0 == w[0].0 + 1 && w[0].1 != 0.0)
+                .map(|w| (w[1].0, (w[1].1 - w[0].1) / w[0].1.abs()))
+                .collect();
+            (name.clone(), changes)
+        })
+        .collect()
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+use reqwest::{Client, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+use crate::error::{EngineError, Result};
+use crate::rate_limit::{RateLimiter, DEFAULT_RATE};
+
+/// User-Agent de repli. La SEC demande que chaque client déclare son propre contact :
+/// à remplacer via `--user-agent` ou `SEC_USER_AGENT`.
+pub const DEFAULT_USER_AGENT: &str = "ValueDashboard contact@example.com";
+
+/// Variable d'environnement lue quand `--user-agent` n'est pas fourni.
+pub const USER_AGENT_ENV: &str = "SEC_USER_AGENT";
+
+/// User-Agent effectif : `--user-agent`, sinon `SEC_USER_AGENT`, sinon la valeur de repli.
+/// Il doit contenir un contact de type e-mail ; un avertissement est émis si c'est
+/// encore le placeholder.
+pub fn resolve_user_agent(flag: Option<&str>) -> Result<String> {
+    let env = std::env::var(USER_AGENT_ENV).ok().filter(|v| !v.trim().is_empty());
+    let user_agent = flag.map(str::to_string).or(env).unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+    let user_agent = user_agent.trim().to_string();
+
+    if !user_agent.split_whitespace().any(is_email_like) {
+        return Err(EngineError::InvalidArgument(format!(
+            "User-Agent sans adresse e-mail de contact : '{}' (ex. \"MonApp prenom.nom@domaine.fr\")",
+            user_agent
+        )));
+    }
+    if user_agent.contains("example.com") {
+        tracing::warn!(
+            "User-Agent générique '{}' : définissez --user-agent ou {} avec votre propre contact",
+            user_agent,
+            USER_AGENT_ENV
+        );
+    }
+    Ok(user_agent)
+}
+
+/// `local@domaine.tld`, éventuellement entre chevrons.
+fn is_email_like(token: &str) -> bool {
+    let token = token.trim_matches(|c| c == '<' || c == '>');
+    let Some((local, domain)) = token.split_once('@') else { return false };
+    let Some((host, tld)) = domain.rsplit_once('.') else { return false };
+    !local.is_empty() && !host.is_empty() && tld.len() >= 2 && !domain.contains('@')
+}
+
+/// Nombre de tentatives supplémentaires par défaut sur erreur transitoire.
+pub const DEFAULT_MAX_RETRIES: u32 = 5;
+
+/// Attente maximale imposée par un header `Retry-After` : au-delà (ex. `86400`), un serveur
+/// mal configuré bloquerait le lot entier. Du même ordre que le backoff le plus long (64 s).
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Délai maximal par défaut d'une requête, réponse complète comprise (`--timeout`).
+/// Une connexion bloquée, avant les headers ou pendant la lecture du corps, échoue en
+/// timeout et repasse par la politique de retry.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Réponse lue en entier. Le corps est téléchargé dans la boucle de retry : un timeout
+/// pendant sa lecture donne lieu à une nouvelle tentative, comme avant les headers.
+#[derive(Debug)]
+pub struct FetchedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl FetchedResponse {
+    /// Corps décodé en JSON.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Corps en texte (UTF-8, caractères invalides remplacés).
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Client HTTP partagé : toutes les requêtes SEC passent par le limiteur
+/// de débit et par la politique de retry.
+#[derive(Debug)]
+pub struct HttpClient {
+    client: Client,
+    limiter: RateLimiter,
+    max_retries: u32,
+}
+
+impl HttpClient {
+    pub fn new(rate: f64, max_retries: u32, user_agent: &str) -> Result<Self> {
+        HttpClient::with_timeout(rate, max_retries, user_agent, DEFAULT_TIMEOUT)
+    }
+
+    /// Comme `new`, avec un délai maximal par requête autre que `DEFAULT_TIMEOUT`.
+    pub fn with_timeout(rate: f64, max_retries: u32, user_agent: &str, timeout: Duration) -> Result<Self> {
+        let client = Client::builder()
+            .user_agent(user_agent)
+            .timeout(timeout)
+            .build()?;
+        Ok(HttpClient { client, limiter: RateLimiter::new(rate), max_retries })
+    }
+
+    /// Client avec le débit et la politique de retry par défaut ; User-Agent lu dans
+    /// `SEC_USER_AGENT`.
+    pub fn with_defaults() -> Result<Self> {
+        HttpClient::new(DEFAULT_RATE, DEFAULT_MAX_RETRIES, &resolve_user_agent(None)?)
+    }
+
+    /// GET avec retry exponentiel (1s, 2s, 4s...) sur 429, 503 et timeout, y compris pendant
+    /// la lecture du corps. Le header `Retry-After` (en secondes, plafonné à `MAX_RETRY_AFTER`)
+    /// est prioritaire sur le backoff.
+    /// Les autres statuts d'erreur (404...) échouent immédiatement.
+    pub async fn fetch_with_retry(&self, url: &str) -> Result<FetchedResponse> {
+        self.fetch_with_headers(url, HeaderMap::new()).await
+    }
+
+    /// Comme `fetch_with_retry`, avec des headers supplémentaires
+    /// (ex. `If-None-Match` pour les requêtes conditionnelles).
+    pub async fn fetch_with_headers(&self, url: &str, headers: HeaderMap) -> Result<FetchedResponse> {
+        let mut attempt = 0;
+        loop {
+            self.limiter.acquire().await;
+            let delay = match self.client.get(url).headers(headers.clone()).send().await {
+                Ok(resp) if is_retryable(resp.status()) && attempt < self.max_retries => {
+                    retry_after(&resp).unwrap_or_else(|| backoff(attempt))
+                }
+                Ok(resp) => {
+                    let resp = resp.error_for_status()?;
+                    let (status, response_headers) = (resp.status(), resp.headers().clone());
+                    match resp.bytes().await {
+                        Ok(body) => return Ok(FetchedResponse { status, headers: response_headers, body: body.into() }),
+                        Err(e) if e.is_timeout() && attempt < self.max_retries => backoff(attempt),
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                Err(e) if e.is_timeout() && attempt < self.max_retries => backoff(attempt),
+                Err(e) => return Err(e.into()),
+            };
+            attempt += 1;
+            tracing::debug!(url, attempt, delay_s = delay.as_secs(), "nouvelle tentative");
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.min(6))
+}
+
+fn retry_after(resp: &Response) -> Option<Duration> {
+    parse_retry_after(resp.headers().get(RETRY_AFTER)?.to_str().ok()?)
+}
+
+/// Délai d'un header `Retry-After` en secondes, plafonné à `MAX_RETRY_AFTER`. `None` si la
+/// valeur n'est pas un nombre de secondes (le backoff s'applique alors).
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(|secs| Duration::from_secs(secs).min(MAX_RETRY_AFTER))
+}
@@ -0,0 +1,445 @@
+pub mod cache;
+pub mod compare;
+pub mod derive;
+pub mod diff;
+pub mod error;
+pub mod extract;
+pub mod filter;
+pub mod frames;
+pub mod fx;
+pub mod growth;
+pub mod http;
+pub mod metrics;
+pub mod models;
+pub mod output;
+pub mod parquet_export;
+pub mod peers;
+pub mod quote;
+pub mod rate_limit;
+pub mod ratios;
+pub mod sanity;
+pub mod scores;
+pub mod sec;
+pub mod segments;
+pub mod splits;
+pub mod sqlite;
+pub mod ttm;
+pub mod valuation;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use futures::stream::{self, StreamExt};
+use tracing::{debug, instrument, warn};
+
+pub use error::{EngineError, Result};
+use extract::{apply_cover_shares, extract_quarterly, extract_with_quality, latest_public_float, reporting_currency, DataQuality, MetricDef, Period};
+use models::{CompanyFacts, CompanyFinancials, FactData, FactsContainer, Taxonomy, TickerEntry};
+use cache::{Cache, MAPPING_TTL};
+use http::HttpClient;
+use sec::{RetainedConcepts, SecClient};
+use metrics::MetricsConfig;
+use ttm::compute_ttm;
+
+/// Nombre de `companyfacts` téléchargés en parallèle lors d'un lot (`--concurrency`).
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Avertissement émis quand le `companyfacts` ne contient aucune taxonomie financière.
+pub const NO_FINANCIAL_FACTS: &str = "no us-gaap facts available";
+
+/// Préfixe de l'avertissement émis quand le `companyfacts` est tronqué ou invalide.
+pub const MALFORMED_FACTS: &str = "malformed companyfacts JSON";
+
+/// Nombre maximal de concepts d'une taxonomie demandés un par un en mode `--lean` : au-delà,
+/// un seul `companyfacts` complet coûte moins de requêtes que les `companyconcept`.
+pub const LEAN_MAX_CONCEPTS: usize = 30;
+
+/// Concepts de page de garde demandés en mode `--lean` (actions en circulation, flottant).
+const LEAN_DEI_CONCEPTS: [&str; 2] = ["EntityCommonStockSharesOutstanding", "EntityPublicFloat"];
+
+/// Options d'extraction pour un ticker.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    pub period: Period,
+    /// Calcule aussi les chiffres sur douze mois glissants (section `ttm`).
+    pub ttm: bool,
+    /// Limite l'historique aux N derniers exercices (tout par défaut).
+    pub years: Option<u16>,
+    /// Bornes incluses de l'historique (`--min-year` / `--max-year`), appliquées avant `years`.
+    pub min_year: Option<u16>,
+    pub max_year: Option<u16>,
+    /// Corrige l'historique des divisions d'actions détectées (`--adjust-splits`).
+    pub adjust_splits: bool,
+    /// Ajoute la section `segments` (`--segments`).
+    pub segments: bool,
+    /// Rabat une métrique sans tag publié sur un concept au nom proche (`--fuzzy`, séries annuelles).
+    pub fuzzy: bool,
+    /// Métriques extraites : listes intégrées, éventuellement modifiées par `--metrics`.
+    pub metrics: MetricsConfig,
+    /// Seuil de détection des valeurs aberrantes (`--outlier-factor`), `DEFAULT_OUTLIER_FACTOR` par défaut.
+    pub outlier_factor: Option<f64>,
+    /// Retire des séries les valeurs aberrantes au lieu de seulement les signaler (`--strict`).
+    pub strict: bool,
+    /// Demande chaque concept extrait via `companyconcept` plutôt que le `companyfacts` complet
+    /// (`--lean`), tant qu'il y en a au plus `LEAN_MAX_CONCEPTS`.
+    pub lean: bool,
+    /// Compare les séries à la dernière archive du CIK aux mêmes options, puis les archive
+    /// dans le cache (section `diff`, `--diff`), via `record_snapshot` une fois les montants
+    /// dans leur devise finale. Sans `diff`, rien n'est archivé.
+    pub diff: bool,
+    /// Lit les `companyfacts` exclusivement depuis le cache, sans aucune requête (`--offline`).
+    pub offline: bool,
+}
+
+impl FetchOptions {
+    /// Concepts à conserver à l'analyse d'un `companyfacts` : tags des métriques extraites et,
+    /// avec `segments`, le concept ventilé. Tous avec `fuzzy`, qui cherche parmi les concepts publiés.
+    pub fn retained_concepts(&self) -> Option<RetainedConcepts> {
+        if self.fuzzy {
+            return None;
+        }
+        let tags = self.metrics.us_gaap.iter().chain(&self.metrics.ifrs_full).flat_map(|def| def.tags.iter().copied());
+        Some(tags.chain(self.segments.then_some(segments::SEGMENT_CONCEPT)).collect())
+    }
+
+    /// Options qui déterminent les séries archivées par `--diff` pour `data`, devise de ses
+    /// montants comprise.
+    pub fn snapshot_options(&self, data: &CompanyFinancials) -> diff::SnapshotOptions {
+        let metrics = [("us-gaap", &self.metrics.us_gaap), ("ifrs-full", &self.metrics.ifrs_full)]
+            .into_iter()
+            .flat_map(|(taxonomy, defs)| defs.iter().map(move |def| (format!("{}/{}", taxonomy, def.name), def.tags.iter().map(|t| t.to_string()).collect())))
+            .collect();
+        diff::SnapshotOptions {
+            years: self.years,
+            min_year: self.min_year,
+            max_year: self.max_year,
+            adjust_splits: self.adjust_splits,
+            fuzzy: self.fuzzy,
+            strict: self.strict,
+            outlier_factor: self.outlier_factor,
+            currency: if data.fx.is_some() { Some("USD".to_string()) } else { data.reporting_currency.clone() },
+            metrics,
+        }
+    }
+
+    /// Concepts d'une taxonomie à demander un par un en mode `--lean`, sans doublon ;
+    /// `None` s'ils sont trop nombreux (ou avec `fuzzy`, qui a besoin de tous les concepts publiés).
+    fn lean_concepts(&self, defs: &[MetricDef], segments: bool) -> Option<Vec<&'static str>> {
+        if self.fuzzy {
+            return None;
+        }
+        let mut tags: Vec<&'static str> = Vec::new();
+        let all = defs.iter().flat_map(|def| def.tags.iter().copied());
+        for tag in all.chain(segments.then_some(segments::SEGMENT_CONCEPT)) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        (tags.len() <= LEAN_MAX_CONCEPTS).then_some(tags)
+    }
+}
+
+/// Exécute `task` sur chaque élément avec au plus `concurrency` tâches en cours, et rend les
+/// résultats dans l'ordre des éléments.
+///
+/// La concurrence borne les requêtes simultanées ; le limiteur de débit du client, partagé par
+/// toutes les tâches, borne en plus les requêtes par seconde. Augmenter la concurrence au-delà
+/// de ce que le débit autorise ne fait qu'allonger la file d'attente du limiteur.
+pub async fn bounded_map<T, R, F, Fut>(items: impl IntoIterator<Item = T>, concurrency: usize, mut task: F) -> Vec<R>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = R>,
+{
+    let mut indexed: Vec<(usize, R)> = stream::iter(items.into_iter().enumerate())
+        .map(|(i, item)| {
+            let running = task(item);
+            async move { (i, running.await) }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    indexed.sort_by_key(|(i, _)| *i);
+    indexed.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Mapping depuis le cache disque s'il est frais (< 24 h), sinon depuis la SEC.
+/// `refresh` force le re-téléchargement.
+pub async fn load_mapping(client: &SecClient, cache: Option<&Cache>, refresh: bool) -> Result<Vec<TickerEntry>> {
+    if let (Some(cache), false) = (cache, refresh) {
+        if let Some(entries) = cache.load_mapping(MAPPING_TTL) {
+            debug!(entries = entries.len(), "mapping lu depuis le cache");
+            return Ok(entries);
+        }
+    }
+
+    let entries = client.fetch_mapping().await?;
+    if let Some(cache) = cache {
+        cache.store_mapping(&entries);
+    }
+    Ok(entries)
+}
+
+/// Mapping du cache quel que soit son âge, sans requête (`--offline`).
+pub fn load_cached_mapping(cache: Option<&Cache>) -> Result<Vec<TickerEntry>> {
+    cache.and_then(|c| c.load_mapping(Duration::MAX)).ok_or(EngineError::MappingNotCached)
+}
+
+/// `companyfacts` d'un CIK lu exclusivement depuis le cache (`--offline`), réduit à `retain`
+/// comme par `fetch_facts_retaining`.
+pub fn load_cached_facts(cache: Option<&Cache>, cik: u64, retain: Option<&RetainedConcepts>) -> Result<CompanyFacts> {
+    let (body, _) = cache.and_then(|c| c.load_facts(&pad_cik(cik))).ok_or(EngineError::CacheMiss(cik))?;
+    let facts = match retain {
+        Some(retain) => sec::parse_facts_retaining(&body, retain),
+        None => sec::parse_facts(&body),
+    };
+    Ok(facts?)
+}
+
+/// Forme canonique d'un ticker : majuscules, sans espaces, classes d'actions au format SEC
+/// (`BRK.B` -> `BRK-B`).
+pub fn normalize_ticker(ticker: &str) -> String {
+    ticker.trim().to_uppercase().replace('.', "-")
+}
+
+/// Retrouve le CIK d'un ticker dans le mapping.
+pub fn resolve_cik(mapping: &[TickerEntry], ticker: &str) -> Result<u64> {
+    let target_ticker = normalize_ticker(ticker);
+    mapping
+        .iter()
+        .find(|entry| normalize_ticker(&entry.ticker) == target_ticker)
+        .map(|entry| entry.cik_str)
+        .ok_or(EngineError::TickerNotFound(target_ticker))
+}
+
+/// Recherche des entreprises par nom : sous-chaîne insensible à la casse sur le champ `title`.
+pub fn resolve_by_name<'a>(mapping: &'a [TickerEntry], query: &str) -> Vec<&'a TickerEntry> {
+    let needle = query.trim().to_lowercase();
+    let mut matches: Vec<&TickerEntry> = mapping
+        .iter()
+        .filter(|entry| entry.title.to_lowercase().contains(&needle))
+        .collect();
+    matches.sort_by(|a, b| a.title.cmp(&b.title).then_with(|| a.ticker.cmp(&b.ticker)));
+    matches
+}
+
+/// Récupère et consolide les données d'un ticker à partir d'un mapping déjà chargé.
+#[instrument(level = "debug", skip(client, cache, mapping, opts))]
+pub async fn fetch_company(client: &SecClient, cache: Option<&Cache>, mapping: &[TickerEntry], ticker: &str, opts: &FetchOptions) -> Result<CompanyFinancials> {
+    let target_ticker = normalize_ticker(ticker);
+    let target_cik = resolve_cik(mapping, &target_ticker)?;
+    fetch_cik(client, cache, target_ticker, target_cik, opts).await
+}
+
+/// Récupère un déclarant directement par son CIK, sans passer par le mapping des tickers
+/// (déclarants non cotés ou radiés). Le CIK complété sur 10 chiffres tient lieu de ticker.
+#[instrument(level = "debug", skip(client, cache, opts))]
+pub async fn fetch_company_by_cik(client: &SecClient, cache: Option<&Cache>, cik: u64, opts: &FetchOptions) -> Result<CompanyFinancials> {
+    fetch_cik(client, cache, pad_cik(cik), cik, opts).await
+}
+
+async fn fetch_cik(client: &SecClient, cache: Option<&Cache>, label: String, cik: u64, opts: &FetchOptions) -> Result<CompanyFinancials> {
+    // 2. Fetch Facts
+    let lean = if opts.lean && !opts.offline { fetch_lean_facts(client, cik, opts).await? } else { None };
+    let facts = match lean {
+        Some(facts) => Ok(facts),
+        None if opts.offline => load_cached_facts(cache, cik, opts.retained_concepts().as_ref()),
+        // Seuls les concepts extraits sont matérialisés : moins de mémoire sur les gros déclarants
+        None => client.fetch_facts_retaining(cache, &pad_cik(cik), opts.retained_concepts().as_ref()).await,
+    };
+    match facts {
+        Ok(facts) => Ok(build_company(label, cik, facts, opts)),
+        // Le mapping a abouti : on restitue au moins le ticker et le CIK
+        Err(EngineError::Json(e)) => Ok(malformed_company(label, cik, &e)),
+        Err(e) => Err(e),
+    }
+}
+
+/// `--diff` : compare les séries extraites à la dernière archive aux mêmes options, puis les
+/// archive à leur tour. À appeler après une éventuelle conversion en USD, pour que `diff`
+/// porte sur les montants de `financials`. Une entreprise sans séries n'est pas archivée.
+pub fn record_snapshot(cache: &Cache, data: &mut CompanyFinancials, opts: &FetchOptions) {
+    if data.financials.is_empty() {
+        return;
+    }
+    let options = opts.snapshot_options(data);
+    data.diff = Some(diff::diff_snapshot(cache.latest_snapshot(data.cik, &options).as_ref(), &data.financials));
+    cache.store_snapshot(data.cik, &data.financials, &options);
+}
+
+/// Reconstitue un `companyfacts` réduit aux concepts extraits à partir des `companyconcept`.
+/// Les concepts us-gaap sont demandés d'abord, les IFRS seulement si aucun n'est publié.
+/// `None` quand le mode lean ne s'applique pas (trop de concepts) : le `companyfacts` complet prend le relais.
+async fn fetch_lean_facts(client: &SecClient, cik: u64, opts: &FetchOptions) -> Result<Option<CompanyFacts>> {
+    let cik_padded = &pad_cik(cik);
+    let Some(gaap_tags) = opts.lean_concepts(&opts.metrics.us_gaap, opts.segments) else {
+        debug!(max = LEAN_MAX_CONCEPTS, "trop de concepts pour --lean : companyfacts complet");
+        return Ok(None);
+    };
+    let (mut entity_name, us_gaap) = fetch_concepts(client, cik_padded, "us-gaap", &gaap_tags).await?;
+    let mut ifrs_full = HashMap::new();
+    if us_gaap.is_empty() {
+        let Some(ifrs_tags) = opts.lean_concepts(&opts.metrics.ifrs_full, false) else {
+            debug!(max = LEAN_MAX_CONCEPTS, "aucun concept us-gaap, trop de concepts IFRS pour --lean : companyfacts complet");
+            return Ok(None);
+        };
+        let (name, facts) = fetch_concepts(client, cik_padded, "ifrs-full", &ifrs_tags).await?;
+        entity_name = entity_name.or(name);
+        ifrs_full = facts;
+    }
+    let (name, dei) = fetch_concepts(client, cik_padded, "dei", &LEAN_DEI_CONCEPTS).await?;
+    Ok(Some(CompanyFacts {
+        cik: Some(cik),
+        entity_name: entity_name.or(name).unwrap_or_default(),
+        facts: FactsContainer {
+            us_gaap: (!us_gaap.is_empty()).then_some(us_gaap),
+            ifrs_full: (!ifrs_full.is_empty()).then_some(ifrs_full),
+            dei: Some(dei),
+        },
+    }))
+}
+
+/// Demande chaque concept d'une taxonomie (au plus `DEFAULT_CONCURRENCY` à la fois, sous le
+/// limiteur de débit du client). Rend le nom du déclarant et les concepts publiés.
+async fn fetch_concepts(
+    client: &SecClient,
+    cik_padded: &str,
+    taxonomy: &str,
+    tags: &[&str],
+) -> Result<(Option<String>, HashMap<String, FactData>)> {
+    let responses = bounded_map(tags.iter().copied(), DEFAULT_CONCURRENCY, |tag| async move {
+        client.fetch_concept(cik_padded, taxonomy, tag).await.map(|concept| (tag, concept))
+    })
+    .await;
+    let mut entity_name = None;
+    let mut concepts = HashMap::new();
+    for response in responses {
+        if let (tag, Some(concept)) = response? {
+            entity_name.get_or_insert(concept.entity_name);
+            concepts.insert(tag.to_string(), FactData { units: concept.units });
+        }
+    }
+    Ok((entity_name, concepts))
+}
+
+/// Résultat partiel d'un `companyfacts` illisible : identifiants seuls, avec l'erreur
+/// d'analyse (et sa position) en avertissement.
+pub fn malformed_company(ticker: String, cik: u64, error: &serde_json::Error) -> CompanyFinancials {
+    CompanyFinancials {
+        ticker,
+        cik,
+        warning: Some(format!("{} : {}", MALFORMED_FACTS, error)),
+        ..Default::default()
+    }
+}
+
+/// CIK au format des URL SEC : 10 chiffres, complété par des zéros.
+pub fn pad_cik(cik: u64) -> String {
+    format!("{:0>10}", cik)
+}
+
+/// Analyse un CIK saisi (`320193`, `0000320193` ou `CIK0000320193`) : 10 chiffres au plus.
+pub fn parse_cik(raw: &str) -> Result<u64> {
+    let digits = raw.trim();
+    let digits = digits.strip_prefix("CIK").or_else(|| digits.strip_prefix("cik")).unwrap_or(digits);
+    match digits.parse::<u64>() {
+        Ok(cik) if digits.len() <= 10 && digits.bytes().all(|b| b.is_ascii_digit()) && cik > 0 => Ok(cik),
+        _ => Err(EngineError::InvalidArgument(format!("CIK invalide : '{}' (attendu : jusqu'à 10 chiffres)", raw))),
+    }
+}
+
+/// Taxonomie financière d'un `companyfacts` : US GAAP en priorité, IFRS pour les émetteurs étrangers.
+pub fn select_taxonomy(facts: &CompanyFacts) -> Option<(Taxonomy, &HashMap<String, FactData>)> {
+    match (&facts.facts.us_gaap, &facts.facts.ifrs_full) {
+        (Some(gaap), _) => Some((Taxonomy::UsGaap, gaap)),
+        (None, Some(ifrs)) => Some((Taxonomy::IfrsFull, ifrs)),
+        (None, None) => None,
+    }
+}
+
+/// Extraction pure (sans réseau) des séries annuelles d'un `companyfacts`, métriques
+/// dérivées comprises. `config` doit correspondre à la taxonomie retenue par `select_taxonomy`.
+pub fn extract(facts: &CompanyFacts, config: &[MetricDef]) -> HashMap<String, Vec<(u16, f64)>> {
+    let (mut financials, _) = extract_reported(facts, config, false);
+    derive::derive_metrics(&mut financials);
+    financials
+}
+
+/// Séries publiées, sans les métriques dérivées, et diagnostic d'extraction par métrique.
+fn extract_reported(facts: &CompanyFacts, config: &[MetricDef], fuzzy: bool) -> (HashMap<String, Vec<(u16, f64)>>, DataQuality) {
+    let (mut financials, mut quality) = select_taxonomy(facts)
+        .map(|(_, f)| extract_with_quality(f, config, fuzzy))
+        .unwrap_or_default();
+    // Le nombre d'actions de la page de garde est plus fiable que les moyennes pondérées GAAP
+    if let Some(dei) = &facts.facts.dei {
+        apply_cover_shares(&mut financials, &mut quality, dei);
+    }
+    (financials, quality)
+}
+
+/// Consolide un `companyfacts` déjà téléchargé : extraction, métriques dérivées, filtres.
+pub fn build_company(ticker: String, cik: u64, facts: CompanyFacts, opts: &FetchOptions) -> CompanyFinancials {
+    let source = select_taxonomy(&facts).map(|(t, f)| (t, f, opts.metrics.for_taxonomy(t)));
+    // Émetteur sans états financiers XBRL (ex. seulement des faits dei) : on le signale
+    // plutôt que de renvoyer des séries vides sans explication
+    let warning = source.is_none().then(|| {
+        warn!(ticker, "aucun fait us-gaap ni ifrs-full");
+        NO_FINANCIAL_FACTS.to_string()
+    });
+    let taxonomy = source.map(|(t, _, _)| t);
+    let (mut financials, mut data_quality) = extract_reported(&facts, source.map_or(&[], |(_, _, config)| config), opts.fuzzy);
+    // Divisions d'actions corrigées avant les dérivées qui reposent sur le nombre d'actions
+    let splits = opts.adjust_splits.then(|| splits::adjust_splits(&mut financials));
+    // Après les divisions, dont les sauts ne sont pas des anomalies
+    let factor = opts.outlier_factor.unwrap_or(sanity::DEFAULT_OUTLIER_FACTOR);
+    sanity::check_outliers(&mut financials, &mut data_quality, factor, opts.strict);
+    derive::derive_metrics(&mut financials);
+    if opts.min_year.is_some() || opts.max_year.is_some() {
+        filter::year_range(&mut financials, opts.min_year, opts.max_year);
+    }
+    if let Some(n) = opts.years {
+        filter::last_years(&mut financials, n);
+    }
+    let mut quarterly = (opts.period == Period::Quarterly || opts.ttm)
+        .then(|| source.map(|(_, f, config)| extract_quarterly(f, config)).unwrap_or_default());
+    // Mêmes filtres d'historique que les séries annuelles, par exercice
+    if let Some(quarterly) = quarterly.as_mut() {
+        if opts.min_year.is_some() || opts.max_year.is_some() {
+            filter::year_range_quarterly(quarterly, opts.min_year, opts.max_year);
+        }
+        if let Some(n) = opts.years {
+            filter::last_years_quarterly(quarterly, n);
+        }
+    }
+    let segments = opts.segments.then(|| source.map(|(_, f, _)| segments::segment_report(f)));
+    let ttm = opts.ttm.then(|| match (&quarterly, source) {
+        (Some(q), Some((_, _, config))) => compute_ttm(&financials, q, config),
+        _ => HashMap::new(),
+    });
+
+    CompanyFinancials {
+        ticker,
+        cik,
+        name: facts.entity_name,
+        taxonomy,
+        reporting_currency: reporting_currency(&data_quality),
+        financials,
+        public_float: facts.facts.dei.as_ref().and_then(latest_public_float),
+        quarterly,
+        ttm,
+        splits,
+        segments: segments.flatten(),
+        fx: None,
+        quote: None,
+        diff: None,
+        data_quality,
+        warning,
+    }
+}
+
+/// Récupère et consolide les données financières SEC d'un ticker.
+pub async fn fetch_financials(ticker: &str) -> Result<CompanyFinancials> {
+    let client = SecClient::from_env(HttpClient::with_defaults()?);
+    let cache = Cache::default_location();
+    let mapping = load_mapping(&client, cache.as_ref(), false).await?;
+    fetch_company(&client, cache.as_ref(), &mapping, ticker, &FetchOptions::default()).await
+}
@@ -1,161 +1,1115 @@
-use std::env;
-use std::collections::HashMap;
-use reqwest::header;
-use serde::Deserialize;
-use anyhow::Result;
-use chrono::{NaiveDate, Datelike};
-
-#[derive(Deserialize, Debug)]
-struct TickerEntry {
-    cik_str: u64,
-    ticker: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct CompanyFacts {
-    entityName: String,
-    facts: FactsContainer,
-}
-
-#[derive(Deserialize, Debug)]
-struct FactsContainer {
-    #[serde(rename = "us-gaap")]
-    us_gaap: Option<HashMap<String, FactData>>,
-}
-
-#[derive(Deserialize, Debug)]
-struct FactData {
-    units: HashMap<String, Vec<FactUnit>>,
-}
-
-#[derive(Deserialize, Debug)]
-struct FactUnit {
-    val: Option<f64>,
-    fy: Option<u16>,
-    fp: Option<String>,
-    start: Option<String>,
-    end: Option<String>,
-}
-
-const USER_AGENT: &str = "ValueDashboard contact@example.com"; 
-
-fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 { return Ok(()); }
-    let target_ticker = args[1].to_uppercase();
-
-    let client = reqwest::blocking::Client::builder()
-        .user_agent(USER_AGENT)
-        .build()?;
-
-    // 1. Mapping
-    let url_mapping = "https://www.sec.gov/files/company_tickers.json";
-    let mapping_resp: HashMap<String, TickerEntry> = client.get(url_mapping).send()?.json()?;
-
-    let mut target_cik = 0;
-    for (_, entry) in mapping_resp {
-        if entry.ticker == target_ticker {
-            target_cik = entry.cik_str;
-            break;
-        }
-    }
-
-    if target_cik == 0 { return Ok(()); }
-    let cik_padded = format!("{:0>10}", target_cik);
-    
-    // 2. Fetch Facts
-    let url_facts = format!("https://data.sec.gov/api/xbrl/companyfacts/CIK{}.json", cik_padded);
-    let facts: CompanyFacts = client.get(&url_facts).send()?.json()?;
-
-    // 3. Config Complète
-    let metrics_config = vec![
-        // --- FLUX (On vérifie la durée ~1 an) ---
-        ("Revenue", vec!["Revenues", "SalesRevenueNet", "RevenueFromContractWithCustomerExcludingAssessedTax", "SalesRevenueGoodsNet"], false),
-        ("Net Income", vec!["NetIncomeLoss", "ProfitLoss", "NetIncomeLossAvailableToCommonStockholdersBasic"], false),
-        ("Operating Income (EBIT)", vec!["OperatingIncomeLoss"], false),
-        ("EPS Diluted", vec!["EarningsPerShareDiluted", "EarningsPerShareBasicAndDiluted"], false),
-        ("Operating Cash Flow", vec!["NetCashProvidedByUsedInOperatingActivities"], false),
-        ("CapEx", vec!["PaymentsToAcquirePropertyPlantAndEquipment", "PaymentsToAcquireProductiveAssets"], false),
-        ("SBC", vec!["ShareBasedCompensation", "EmployeeServiceShareBasedCompensationNonvestedAwardsTotalCompensationCostNotYetRecognized", "ShareBasedCompensationArrangementByShareBasedPaymentAwardEquityInstrumentsOtherThanOptionsVestedInPeriodTotalFairValue"], false),
-        
-        // --- STOCKS (On prend le snapshot de fin d'année) ---
-        ("Total Equity", vec!["StockholdersEquity", "StockholdersEquityIncludingPortionAttributableToNoncontrollingInterest"], true),
-        ("Cash & Equiv.", vec!["CashAndCashEquivalentsAtCarryingValue", "CashCashEquivalentsAndShortTermInvestments"], true),
-        ("Long Term Debt", vec!["LongTermDebt", "LongTermDebtNoncurrent"], true),
-        ("Shares Outstanding", vec!["CommonStockSharesOutstanding", "WeightedAverageNumberOfDilutedSharesOutstanding", "WeightedAverageNumberOfSharesOutstandingBasicAndDiluted"], true),
-    ];
-
-    let mut results: HashMap<String, Vec<(u16, f64)>> = HashMap::new();
-
-    if let Some(gaap) = facts.facts.us_gaap {
-        for (metric_name, tags, is_instant) in metrics_config {
-            let mut extracted_data = Vec::new();
-            
-            for tag in tags {
-                if let Some(data) = gaap.get(tag) {
-                    // On parcourt TOUTES les unités (USD, shares, etc.) sans distinction
-                    for (_unit_name, units) in &data.units {
-                        for unit in units {
-                            if let Some(val) = unit.val {
-                                // CONDITION SINE QUA NON : Avoir une date de fin
-                                if let Some(end_s) = &unit.end {
-                                    if let Ok(d_end) = NaiveDate::parse_from_str(end_s, "%Y-%m-%d") {
-                                        
-                                        // CAS 1 : FLUX (Revenue, OCF, SBC...)
-                                        if !is_instant {
-                                            // Il faut une date de début pour calculer la durée
-                                            if let Some(start_s) = &unit.start {
-                                                if let Ok(d_start) = NaiveDate::parse_from_str(start_s, "%Y-%m-%d") {
-                                                    let duration_days = (d_end - d_start).num_days();
-                                                    // On garde si c'est une année complète (350-380 jours)
-                                                    if duration_days > 350 && duration_days < 380 {
-                                                        let year = d_end.year() as u16;
-                                                        extracted_data.push((year, val));
-                                                    }
-                                                }
-                                            }
-                                        } 
-                                        // CAS 2 : STOCKS (Shares, Debt, Equity...)
-                                        else {
-                                            // On prend tout ce qui a une date. 
-                                            // La logique de dédoublonnage (Max Absolu) plus bas fera le tri entre Q1, Q2, Q3 et FY.
-                                            // Généralement, le chiffre de fin d'année (FY) est le plus élevé ou le plus significatif.
-                                            // C'est un pari statistique qui marche à 99% pour éviter de perdre des données mal taguées.
-                                            let year = d_end.year() as u16;
-                                            extracted_data.push((year, val));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgAction, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::level_filters::LevelFilter;
+use tracing::warn;
+
+use edgar_fetcher::models::{CompanyFacts, CompanyFinancials, TickerEntry};
+use edgar_fetcher::cache::Cache;
+use edgar_fetcher::compare::compare;
+use edgar_fetcher::derive::{derived_kind, flow_metric_names, DERIVED_METRICS};
+use edgar_fetcher::frames::{fetch_frame, FrameQuery};
+use edgar_fetcher::fx::{convert_to_usd, CachedRates, Frankfurter};
+use edgar_fetcher::extract::{list_concepts, MetricDef, Period};
+use edgar_fetcher::growth::{compute_cagr, yoy_growth};
+use edgar_fetcher::http::{resolve_user_agent, HttpClient, DEFAULT_MAX_RETRIES, DEFAULT_TIMEOUT};
+use edgar_fetcher::metrics::MetricsConfig;
+use edgar_fetcher::models::Taxonomy;
+use edgar_fetcher::peers::{parse_ticker_list, peer_ranks, peer_stats, read_peer_file, PeerRank};
+use edgar_fetcher::quote::{CachedQuotes, Stooq};
+use edgar_fetcher::output::{
+    to_csv_batch_with, to_json_string, to_table_with, AltmanZ, EngineOutput, FailedTicker, FinancialSeries, Format, MetricSelection,
+    MetricKinds, MetricSort, OrderedSeries, Rounding, Scores, Valuation,
+};
+use edgar_fetcher::rate_limit::DEFAULT_RATE;
+use edgar_fetcher::parquet_export::export_parquet;
+use edgar_fetcher::sqlite::export_sqlite;
+use edgar_fetcher::ratios::{
+    compute_dupont, compute_earnings_quality, compute_leverage, compute_margin_trends, compute_ratios, compute_roic, compute_sign_flags,
+    compute_working_capital, DEFAULT_MARGIN_THRESHOLD,
+};
+use edgar_fetcher::sec::{parse_facts, parse_facts_retaining, SecClient};
+use edgar_fetcher::scores::{altman_z, altman_zone, piotroski};
+use edgar_fetcher::valuation::{dcf_valuation, enterprise_value, graham_valuation, multiples, normalized_earnings, DcfAssumptions};
+use edgar_fetcher::{build_company, malformed_company, resolve_cik, select_taxonomy, fetch_company, fetch_company_by_cik, pad_cik, parse_cik, load_cached_facts, load_cached_mapping, load_mapping, normalize_ticker, record_snapshot, resolve_by_name, bounded_map, FetchOptions, DEFAULT_CONCURRENCY, EngineError, Result};
+
+/// Options de la ligne de commande.
+struct Options {
+    tickers: Vec<String>,
+    rate: f64,
+    max_retries: u32,
+    /// Délai maximal d'une requête HTTP (`--timeout`), au-delà duquel elle est retentée.
+    timeout: Duration,
+    /// Téléchargements simultanés d'un lot (`--concurrency`), en plus du limiteur de débit.
+    concurrency: usize,
+    refresh_cache: bool,
+    /// Dossier du cache (`--cache-dir`) à la place de l'emplacement de la plateforme.
+    cache_dir: Option<PathBuf>,
+    /// Liste le contenu du cache (`cache`) au lieu de lancer une extraction.
+    cache_info: bool,
+    /// Recherche par nom d'entreprise (`--name`) au lieu d'un ticker.
+    name: Option<String>,
+    /// Fenêtre (en exercices) du calcul de CAGR ; tout l'historique par défaut.
+    cagr_years: Option<u16>,
+    /// Pente annuelle au-delà de laquelle une marge évolue (`--margin-threshold`).
+    margin_threshold: Option<f64>,
+    /// Fenêtre (en exercices) des résultats normalisés (`--normalized-years`).
+    normalized_years: Option<u16>,
+    format: Format,
+    /// Métriques de `financials` retenues (`--only`) et leur ordre (`--sort`).
+    selection: MetricSelection,
+    /// Fichier de sortie (`--out`) ; stdout par défaut.
+    out: Option<PathBuf>,
+    /// Base SQLite alimentée en plus de la sortie (`--sqlite`).
+    sqlite: Option<PathBuf>,
+    /// Fichier Parquet écrit en plus de la sortie (`--parquet`).
+    parquet: Option<PathBuf>,
+    /// `companyfacts` local (`--facts-file`) traité hors ligne à la place d'un téléchargement.
+    facts_file: Option<PathBuf>,
+    /// JSON indenté (`--pretty`) plutôt que sur une ligne.
+    pretty: bool,
+    /// Arrondi des nombres du JSON (`--round` / `--raw`).
+    rounding: Rounding,
+    /// Requête `frames` (`frame CONCEPT/UNITE/PERIODE`) à la place des tickers.
+    frame: Option<FrameQuery>,
+    /// Statistiques de groupe sur les tickers d'un fichier (`--peers`).
+    peers: bool,
+    /// Pairs de `--benchmark` (même secteur), auxquels chaque ticker demandé est comparé.
+    benchmark: Vec<String>,
+    /// Tickers lus sur l'entrée standard (`--stdin`) : la sortie reste un lot, même pour un seul.
+    stdin: bool,
+    /// Comparaison côte à côte de deux tickers (`compare A B`).
+    compare: bool,
+    /// Cours de l'action (`--price`), nécessaire à la capitalisation et à la valeur d'entreprise.
+    price: Option<f64>,
+    /// Cours de chaque ticker demandé au fournisseur de cours (`--quote`).
+    quote: bool,
+    /// CIK explicites (`--cik`), récupérés sans passer par le mapping des tickers.
+    ciks: Vec<u64>,
+    /// Liste les concepts publiés (`concepts`) au lieu d'extraire les métriques.
+    concepts: bool,
+    /// Désactive la barre de progression des lots (`--no-progress`).
+    no_progress: bool,
+    /// Affiche le schéma JSON de la sortie (`--print-schema`) au lieu de lancer une extraction.
+    print_schema: bool,
+    /// Liste les métriques produites (`--list-metrics`) au lieu de lancer une extraction.
+    list_metrics: bool,
+    /// Conversion en USD des montants publiés dans une autre devise (`--convert-usd`).
+    convert_usd: bool,
+    /// Valorisation DCF demandée (`--dcf`, hypothèses ajustables par `--dcf-*`).
+    dcf: Option<DcfAssumptions>,
+    /// Contact déclaré à la SEC (`--user-agent`, sinon `SEC_USER_AGENT`).
+    user_agent: Option<String>,
+    /// Niveau de détail des logs sur stderr (`-v` : debug, `-vv` : trace).
+    verbose: u8,
+    /// Aucun diagnostic sur stderr (`--quiet`) : ni logs, ni progression, ni confirmations.
+    /// Seule une erreur fatale y est encore signalée.
+    quiet: bool,
+    fetch: FetchOptions,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            tickers: Vec::new(),
+            rate: DEFAULT_RATE,
+            max_retries: DEFAULT_MAX_RETRIES,
+            timeout: DEFAULT_TIMEOUT,
+            concurrency: DEFAULT_CONCURRENCY,
+            refresh_cache: false,
+            cache_dir: None,
+            cache_info: false,
+            name: None,
+            cagr_years: None,
+            margin_threshold: None,
+            normalized_years: None,
+            format: Format::Json,
+            selection: MetricSelection::default(),
+            out: None,
+            sqlite: None,
+            parquet: None,
+            facts_file: None,
+            pretty: false,
+            rounding: Rounding::default(),
+            frame: None,
+            peers: false,
+            stdin: false,
+            benchmark: Vec::new(),
+            compare: false,
+            price: None,
+            quote: false,
+            ciks: Vec::new(),
+            concepts: false,
+            no_progress: false,
+            print_schema: false,
+            list_metrics: false,
+            convert_usd: false,
+            dcf: None,
+            user_agent: None,
+            verbose: 0,
+            quiet: false,
+            fetch: FetchOptions::default(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("Erreur : {}", e);
+        process::exit(1);
+    }
+}
+
+async fn run() -> Result<()> {
+    let opts = options(parse_cli())?;
+    init_logging(opts.verbose, opts.quiet);
+    warn_unknown_metrics(&opts);
+
+    if opts.print_schema {
+        let schema = schemars::schema_for!(EngineOutput);
+        return emit(&opts, &json_text(&json!(schema), &opts));
+    }
+    if opts.list_metrics {
+        return emit(&opts, &json_text(&metrics_json(&opts.fetch.metrics), &opts));
+    }
+    let cache = match &opts.cache_dir {
+        Some(dir) => Some(Cache::new(dir)),
+        None => Cache::default_location(),
+    };
+    if opts.cache_info {
+        return emit(&opts, &json_text(&cache_info_json(cache.as_ref()), &opts));
+    }
+
+    // Fichier companyfacts local : ni mapping ni téléchargement
+    if let Some(path) = &opts.facts_file {
+        let text = fs::read(path).map_err(|source| EngineError::Read { path: path.clone(), source })?;
+        let ticker = opts.tickers.first().map(|t| normalize_ticker(t)).unwrap_or_default();
+        if opts.concepts {
+            let facts = parse_facts(&text)?;
+            let cik = facts.cik.unwrap_or_default();
+            return emit(&opts, &json_text(&concepts_json(&ticker, cik, &facts), &opts));
+        }
+        let parsed = match opts.fetch.retained_concepts() {
+            Some(retain) => parse_facts_retaining(&text, &retain),
+            None => parse_facts(&text),
+        };
+        let data = match parsed {
+            Ok(facts) => {
+                let cik = facts.cik.unwrap_or_default();
+                build_company(ticker.clone(), cik, facts, &opts.fetch)
             }
-            
-            // Dédoublonnage : On garde la valeur MAX absolue pour chaque année
-            // Cela permet d'éliminer les valeurs trimestrielles (souvent plus petites) qui auraient pu passer
-            // pour les métriques de Stock.
-            let mut unique_map: HashMap<u16, f64> = HashMap::new();
-            for (fy, val) in extracted_data {
-                let entry = unique_map.entry(fy).or_insert(val);
-                if val.abs() > entry.abs() {
-                    *entry = val;
-                }
+            Err(e) => malformed_company(ticker.clone(), 0, &e),
+        };
+        let batch = [(ticker, Ok(data))];
+        store(&opts, &batch)?;
+        return emit(&opts, &render(&batch, &opts, false));
+    }
+    let mut tickers = opts.tickers.clone();
+
+    // Le mapping n'est téléchargé qu'une fois pour tout le lot
+    let user_agent = resolve_user_agent(opts.user_agent.as_deref())?;
+    let client = SecClient::from_env(HttpClient::with_timeout(opts.rate, opts.max_retries, &user_agent, opts.timeout)?);
+
+    // Mode frames : un concept pour tous les déclarants, sans passer par le mapping
+    if let Some(query) = &opts.frame {
+        let values = fetch_frame(&client, &query.concept, &query.unit, &query.period).await?;
+        let out = json!({ "concept": query.concept, "unit": query.unit, "period": query.period, "values": values });
+        return emit(&opts, &json_text(&out, &opts));
+    }
+    // Taux de change : même User-Agent et même politique de retry que les appels SEC
+    let fx = if opts.convert_usd {
+        let http = HttpClient::with_timeout(opts.rate, opts.max_retries, &user_agent, opts.timeout)?;
+        Some(CachedRates::new(Frankfurter::from_env(http), cache.clone()))
+    } else {
+        None
+    };
+    let quotes = if opts.quote {
+        let http = HttpClient::with_timeout(opts.rate, opts.max_retries, &user_agent, opts.timeout)?;
+        Some(CachedQuotes::new(Stooq::from_env(http), cache.clone()))
+    } else {
+        None
+    };
+    let sources = Sources { fx: fx.as_ref(), quotes: quotes.as_ref() };
+    // CIK explicites uniquement : inutile de charger le mapping des tickers, sauf pour retrouver
+    // le symbole à coter avec --quote
+    let mapping = if tickers.is_empty() && opts.name.is_none() && !opts.quote {
+        Vec::new()
+    } else {
+        if opts.fetch.offline {
+            load_cached_mapping(cache.as_ref())?
+        } else {
+            load_mapping(&client, cache.as_ref(), opts.refresh_cache).await?
+        }
+    };
+
+    // Recherche par nom : une seule entreprise -> on enchaîne, sinon on liste les candidats
+    if let Some(query) = &opts.name {
+        let matches = resolve_by_name(&mapping, query);
+        let mut ciks: Vec<u64> = matches.iter().map(|e| e.cik_str).collect();
+        ciks.sort_unstable();
+        ciks.dedup();
+        match (matches.first(), ciks.len()) {
+            (None, _) => return Err(EngineError::NameNotFound(query.clone())),
+            (Some(entry), 1) => tickers.push(entry.ticker.clone()),
+            _ => {
+                let list: Vec<Value> = matches
+                    .iter()
+                    .map(|e| json!({ "ticker": e.ticker, "cik": e.cik_str, "name": e.title }))
+                    .collect();
+                return emit(&opts, &json_text(&json!({ "query": query, "matches": list }), &opts));
             }
-            
-            let mut final_vec: Vec<(u16, f64)> = unique_map.into_iter().collect();
-            final_vec.sort_by_key(|k| k.0);
+        }
+    }
+
+    let mut targets: Vec<Target> = tickers
+        .into_iter()
+        .map(Target::Ticker)
+        .chain(opts.ciks.iter().copied().map(Target::Cik))
+        .collect();
+    // Benchmark : les pairs rejoignent le lot, sans doublon avec les entreprises classées
+    let subjects: Vec<String> = targets.iter().map(Target::label).collect();
+    targets.extend(opts.benchmark.iter().map(|t| Target::Ticker(t.clone())).filter(|t| !subjects.contains(&t.label())));
 
-            results.insert(metric_name.to_string(), final_vec);
+    // Inventaire des concepts : companyfacts brut, sans extraction
+    if opts.concepts {
+        let target = &targets[0];
+        let cik = match target {
+            Target::Ticker(ticker) => resolve_cik(&mapping, ticker)?,
+            Target::Cik(cik) => *cik,
+        };
+        let facts = if opts.fetch.offline {
+            load_cached_facts(cache.as_ref(), cik, None)?
+        } else {
+            client.fetch_facts(cache.as_ref(), &pad_cik(cik)).await?
+        };
+        return emit(&opts, &json_text(&concepts_json(&target.label(), cik, &facts), &opts));
+    }
+
+    // Un seul ticker : on garde la sortie historique (un objet, code d'erreur si échec)
+    if targets.len() == 1 && !opts.peers && !opts.stdin && opts.benchmark.is_empty() {
+        let data = targets[0].fetch(&client, cache.as_ref(), &mapping, &opts.fetch, sources).await?;
+        let batch = [(targets[0].label(), Ok(data))];
+        store(&opts, &batch)?;
+        emit(&opts, &render(&batch, &opts, false))?;
+        return Ok(());
+    }
+
+    // Plusieurs tickers : au plus `--concurrency` téléchargements en cours, toujours soumis au
+    // limiteur de débit, résultats remis dans l'ordre de la ligne de commande. Un échec
+    // n'interrompt pas le lot.
+    let progress = progress_bar(&opts, targets.len());
+    let batch: Vec<(String, Result<CompanyFinancials>)> = bounded_map(&targets, opts.concurrency, |target| {
+        let (client, cache, mapping, fetch, progress) = (&client, cache.as_ref(), &mapping, &opts.fetch, &progress);
+        async move {
+            let result = target.fetch(client, cache, mapping, fetch, sources).await;
+            progress.set_message(target.label());
+            progress.inc(1);
+            (target.label(), result)
         }
+    })
+    .await;
+    progress.finish_and_clear();
+
+    store(&opts, &batch)?;
+    if opts.peers {
+        let companies: Vec<CompanyFinancials> = batch.iter().filter_map(|(_, res)| res.as_ref().ok().cloned()).collect();
+        return emit(&opts, &json_text(&json!({ "peers": peer_stats(&companies), "failed": failed_tickers(&batch) }), &opts));
+    }
+    if !opts.benchmark.is_empty() {
+        let peer_labels: Vec<String> = opts.benchmark.iter().map(|t| normalize_ticker(t)).collect();
+        let fetched = || batch.iter().filter_map(|(label, res)| Some((label, res.as_ref().ok()?)));
+        let peers: Vec<CompanyFinancials> =
+            fetched().filter(|(label, _)| peer_labels.contains(label)).map(|(_, data)| data.clone()).collect();
+        let stats = peer_stats(&peers);
+        let ranks: BTreeMap<&String, BTreeMap<String, PeerRank>> =
+            fetched().filter(|(label, _)| subjects.contains(label)).map(|(label, data)| (label, peer_ranks(data, &stats))).collect();
+        return emit(&opts, &json_text(&json!({ "benchmark": ranks, "failed": failed_tickers(&batch) }), &opts));
     }
+    if opts.compare {
+        let mut companies = Vec::with_capacity(2);
+        for (_, res) in batch {
+            companies.push(res?);
+        }
+        return emit(&opts, &json_text(&json!(compare(&companies[0], &companies[1])), &opts));
+    }
+    emit(&opts, &render(&batch, &opts, true))
+}
+
+/// Tickers du lot en échec, avec leur erreur.
+fn failed_tickers(batch: &[(String, Result<CompanyFinancials>)]) -> Vec<FailedTicker> {
+    batch
+        .iter()
+        .filter_map(|(ticker, res)| res.as_ref().err().map(|e| FailedTicker { ticker: ticker.clone(), error: e.to_string() }))
+        .collect()
+}
 
-    println!("{}", serde_json::json!({
-        "ticker": target_ticker,
-        "cik": target_cik,
-        "name": facts.entityName,
-        "financials": results
-    }));
+/// Signale les noms de `--only` qu'aucune config ni dérivation ne produit, avec la liste
+/// des métriques disponibles. Les autres noms restent appliqués.
+fn warn_unknown_metrics(opts: &Options) {
+    let Some(only) = &opts.selection.only else { return };
+    let metrics = &opts.fetch.metrics;
+    let mut valid: Vec<&str> = [Taxonomy::UsGaap, Taxonomy::IfrsFull]
+        .into_iter()
+        .flat_map(|t| metrics.for_taxonomy(t).iter().map(|def| def.name))
+        .chain(DERIVED_METRICS.iter().copied())
+        .collect();
+    valid.sort_unstable();
+    valid.dedup();
+    let unknown: Vec<&str> = only.iter().map(String::as_str).filter(|name| !valid.contains(name)).collect();
+    if !unknown.is_empty() {
+        diagnostic(opts, &format!(
+            "Avertissement : --only ignore les métriques inconnues {}. Métriques disponibles : {}",
+            unknown.join(", "),
+            valid.join(", ")
+        ));
+    }
+}
 
+/// Barre de progression d'un lot sur stderr (`n/total`, dernier ticker traité, temps écoulé).
+/// Masquée avec `--no-progress` ou `--quiet`, quand stderr n'est pas un terminal, ou quand stdout est ce même
+/// terminal, pour ne pas mêler la barre à la sortie.
+fn progress_bar(opts: &Options, total: usize) -> ProgressBar {
+    let stderr = std::io::stderr();
+    let shared_terminal = opts.out.is_none() && std::io::stdout().is_terminal();
+    if opts.no_progress || opts.quiet || total < 2 || !stderr.is_terminal() || shared_terminal {
+        return ProgressBar::hidden();
+    }
+    let bar = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::stderr());
+    if let Ok(style) = ProgressStyle::with_template("[{elapsed_precise}] {bar:30} {pos}/{len} {msg}") {
+        bar.set_style(style);
+    }
+    bar
+}
+
+/// Logs sur stderr uniquement, pour ne pas polluer la sortie JSON sur stdout.
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::OFF,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    tracing_subscriber::fmt().with_writer(std::io::stderr).with_max_level(level).init();
+}
+
+/// Entreprise demandée : par ticker (résolu via le mapping) ou directement par CIK.
+/// Sources complémentaires à la SEC : taux de change (`--convert-usd`) et cours (`--quote`).
+#[derive(Clone, Copy)]
+struct Sources<'a> {
+    fx: Option<&'a CachedRates<Frankfurter>>,
+    quotes: Option<&'a CachedQuotes<Stooq>>,
+}
+
+enum Target {
+    Ticker(String),
+    Cik(u64),
+}
+
+impl Target {
+    fn label(&self) -> String {
+        match self {
+            Target::Ticker(ticker) => normalize_ticker(ticker),
+            Target::Cik(cik) => pad_cik(*cik),
+        }
+    }
+
+    /// Récupère l'entreprise puis, avec `--convert-usd`, convertit ses montants en USD, avec
+    /// `--diff`, la compare à sa dernière archive et, avec `--quote`, y joint son cours. Un cours indisponible n'est pas une erreur : l'entreprise
+    /// est restituée sans multiples.
+    async fn fetch(
+        &self,
+        client: &SecClient,
+        cache: Option<&Cache>,
+        mapping: &[TickerEntry],
+        opts: &FetchOptions,
+        sources: Sources<'_>,
+    ) -> Result<CompanyFinancials> {
+        let mut data = match self {
+            Target::Ticker(ticker) => fetch_company(client, cache, mapping, ticker, opts).await?,
+            Target::Cik(cik) => fetch_company_by_cik(client, cache, *cik, opts).await?,
+        };
+        if let Some(rates) = sources.fx {
+            convert_to_usd(&mut data, rates).await?;
+        }
+        if let (Some(cache), true) = (cache, opts.diff) {
+            record_snapshot(cache, &mut data, opts);
+        }
+        if let (Some(quotes), Some(symbol)) = (sources.quotes, self.quote_symbol(&data, mapping)) {
+            match quotes.quote(&symbol).await {
+                Ok(quote) => data.quote = Some(quote),
+                Err(e) => warn!(ticker = %symbol, error = %e, "cours indisponible"),
+            }
+        }
+        Ok(data)
+    }
+
+    /// Symbole à coter : le ticker demandé ou, pour un CIK, celui que lui associe le mapping. Le
+    /// libellé d'un CIK (`0000320193`) n'est pas un symbole : sans ticker connu, pas de cours.
+    fn quote_symbol(&self, data: &CompanyFinancials, mapping: &[TickerEntry]) -> Option<String> {
+        match self {
+            Target::Ticker(_) => Some(data.ticker.clone()).filter(|ticker| !ticker.is_empty()),
+            Target::Cik(cik) => mapping.iter().find(|entry| entry.cik_str == *cik).map(|entry| normalize_ticker(&entry.ticker)),
+        }
+    }
+}
+
+/// Sortie de `--list-metrics` : métriques de chaque taxonomie (config éventuellement modifiée
+/// par `--metrics`), puis les dérivées qu'elles ne définissent pas déjà.
+fn metrics_json(config: &MetricsConfig) -> Value {
+    let listing = |defs: &[MetricDef]| -> Vec<Value> {
+        defs.iter()
+            .map(|def| {
+                let period = if def.is_instant { "instant" } else { "flow" };
+                json!({ "name": def.name, "tags": def.tags, "unit": def.expected_unit.name(), "period": period })
+            })
+            .collect()
+    };
+    let configured = |name: &str| config.us_gaap.iter().chain(&config.ifrs_full).any(|def| def.name == name);
+    let derived: Vec<Value> = DERIVED_METRICS
+        .iter()
+        .filter(|name| !configured(name))
+        .map(|name| {
+            let (period, unit) = derived_kind(name);
+            json!({ "name": name, "unit": unit, "period": period })
+        })
+        .collect();
+    json!({ "us-gaap": listing(&config.us_gaap), "ifrs-full": listing(&config.ifrs_full), "derived": derived })
+}
+
+/// Sortie de `concepts` : concepts de la taxonomie retenue, triés par nom.
+fn concepts_json(ticker: &str, cik: u64, facts: &CompanyFacts) -> Value {
+    let (taxonomy, concepts) = match select_taxonomy(facts) {
+        Some((taxonomy, f)) => (Some(taxonomy), list_concepts(f)),
+        None => (None, Vec::new()),
+    };
+    json!({ "ticker": ticker, "cik": cik, "name": facts.entity_name, "taxonomy": taxonomy, "concepts": concepts })
+}
+
+/// Sortie de `cache` : `companyfacts` en cache avec leur taille et leur âge.
+fn cache_info_json(cache: Option<&Cache>) -> Value {
+    let facts = cache.map(Cache::list_facts).unwrap_or_default();
+    let total_bytes: u64 = facts.iter().map(|f| f.bytes).sum();
+    json!({ "dir": cache.map(Cache::dir), "facts": facts, "total_bytes": total_bytes })
+}
+
+/// Exports SQLite (`--sqlite`) et Parquet (`--parquet`) des tickers récupérés avec succès.
+fn store(opts: &Options, batch: &[(String, Result<CompanyFinancials>)]) -> Result<()> {
+    if opts.sqlite.is_none() && opts.parquet.is_none() { return Ok(()); }
+    let companies: Vec<CompanyFinancials> = batch.iter().filter_map(|(_, res)| res.as_ref().ok().cloned()).collect();
+    if let Some(path) = &opts.sqlite {
+        let rows = export_sqlite(path, &companies)?;
+        diagnostic(opts, &format!("{} lignes enregistrées dans {}", rows, path.display()));
+    }
+    if let Some(path) = &opts.parquet {
+        let rows = export_parquet(path, &companies)?;
+        diagnostic(opts, &format!("{} lignes écrites dans {}", rows, path.display()));
+    }
+    Ok(())
+}
+
+/// Écrit la sortie sur stdout, ou dans le fichier `--out` (dossiers parents créés au besoin)
+/// avec une simple confirmation sur stderr.
+fn emit(opts: &Options, content: &str) -> Result<()> {
+    let Some(path) = &opts.out else {
+        println!("{}", content);
+        return Ok(());
+    };
+
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{}\n", content))
+    };
+    write().map_err(|source| EngineError::Write { path: path.clone(), source })?;
+    diagnostic(opts, &format!("Sortie écrite dans {}", path.display()));
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Message de diagnostic sur stderr, jamais sur stdout (réservé aux données), sauf `--quiet`.
+fn diagnostic(opts: &Options, message: &str) {
+    if !opts.quiet {
+        eprintln!("{}", message);
+    }
+}
+
+/// JSON compact par défaut (adapté aux pipes), indenté avec `--pretty`, nombres arrondis
+/// selon leur nature avec `--round`.
+fn json_text(value: &impl Serialize, opts: &Options) -> String {
+    let text = to_json_string(value, opts.pretty, opts.rounding, &MetricKinds::new(&opts.fetch.metrics));
+    // Les clés des sorties sont toutes des chaînes : la sérialisation ne peut pas échouer
+    text.unwrap_or_default()
+}
+
+/// Élément d'un lot JSON : l'entreprise, ou `{ticker, error}` en cas d'échec.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BatchItem {
+    Company(Box<EngineOutput>),
+    Failed(FailedTicker),
+}
+
+/// Sérialise les résultats au format demandé. En JSON, un lot donne un tableau où chaque
+/// échec devient un objet `{ticker, error}` ; en CSV, les échecs sont signalés sur stderr
+/// et en tableau, par une ligne à la place du tableau de l'entreprise.
+fn render(batch: &[(String, Result<CompanyFinancials>)], opts: &Options, is_batch: bool) -> String {
+    match opts.format {
+        Format::Json => {
+            let mut items: Vec<BatchItem> = batch
+                .iter()
+                .map(|(ticker, res)| match res {
+                    Ok(data) => BatchItem::Company(Box::new(to_output(data, opts))),
+                    Err(e) => BatchItem::Failed(FailedTicker { ticker: ticker.clone(), error: e.to_string() }),
+                })
+                .collect();
+            if is_batch { json_text(&items, opts) } else { json_text(&items.remove(0), opts) }
+        }
+        Format::Csv => {
+            let companies: Vec<CompanyFinancials> = batch
+                .iter()
+                .filter_map(|(ticker, res)| match res {
+                    Ok(data) => Some(data.clone()),
+                    Err(e) => { diagnostic(opts, &format!("Erreur pour {} : {}", ticker, e)); None }
+                })
+                .collect();
+            to_csv_batch_with(&companies, &opts.fetch.metrics, &opts.selection).trim_end().to_string()
+        }
+        Format::Table => batch
+            .iter()
+            .map(|(ticker, res)| match res {
+                Ok(data) => to_table_with(data, &opts.fetch.metrics, &opts.selection),
+                Err(e) => format!("{} - erreur : {}\n", ticker, e),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string(),
+    }
+}
+
+/// Ligne de commande. Sans sous-commande, les arguments sont ceux de `fetch` :
+/// `edgar_fetcher AAPL` équivaut à `edgar_fetcher fetch AAPL`.
+#[derive(Parser)]
+#[command(name = "edgar_fetcher", version, about = "Extraction des états financiers publiés sur EDGAR (SEC)")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    fetch: FetchArgs,
+    #[command(flatten)]
+    global: GlobalArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Extrait les états financiers d'un ou plusieurs tickers (sous-commande par défaut).
+    Fetch(Box<FetchArgs>),
+    /// Compare deux tickers côte à côte.
+    Compare {
+        a: String,
+        b: String,
+        #[command(flatten)]
+        extract: ExtractArgs,
+    },
+    /// Valeurs d'un concept pour tous les déclarants (API frames), ex. `Revenues/USD/CY2022`.
+    Frame {
+        #[arg(value_name = "CONCEPT/UNITE/PERIODE", value_parser = frame_query)]
+        query: FrameQuery,
+    },
+    /// Liste les concepts XBRL publiés par une entreprise, sans extraction.
+    Concepts {
+        ticker: Option<String>,
+        #[arg(long, value_parser = cik_value, conflicts_with = "ticker")]
+        cik: Option<u64>,
+        /// `companyfacts` local lu à la place d'un téléchargement.
+        #[arg(long)]
+        facts_file: Option<PathBuf>,
+    },
+    /// Liste les `companyfacts` en cache avec leur taille et leur âge.
+    Cache,
+}
+
+/// Options communes à toutes les sous-commandes.
+#[derive(Args)]
+struct GlobalArgs {
+    /// Requêtes par seconde vers la SEC.
+    #[arg(long, global = true, default_value_t = DEFAULT_RATE, value_parser = positive_rate)]
+    rate: f64,
+    /// Nouvelles tentatives après une erreur transitoire.
+    #[arg(long, global = true, default_value_t = DEFAULT_MAX_RETRIES)]
+    max_retries: u32,
+    /// Délai maximal d'une requête HTTP, corps compris, en secondes ; une requête expirée est retentée.
+    #[arg(long, global = true, value_name = "SECS", default_value_t = DEFAULT_TIMEOUT.as_secs() as u16, value_parser = positive_u16)]
+    timeout: u16,
+    /// Contact déclaré à la SEC (sinon `SEC_USER_AGENT`).
+    #[arg(long, global = true)]
+    user_agent: Option<String>,
+    /// Re-télécharge le mapping des tickers même s'il est frais.
+    #[arg(long, global = true)]
+    refresh_cache: bool,
+    /// Dossier du cache à la place de l'emplacement de la plateforme.
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+    /// Aucune requête réseau : mapping et companyfacts lus exclusivement depuis le cache.
+    #[arg(long, global = true, conflicts_with = "refresh_cache")]
+    offline: bool,
+    /// Fichier de sortie ; stdout par défaut.
+    #[arg(long, global = true)]
+    out: Option<PathBuf>,
+    /// JSON indenté plutôt que sur une ligne.
+    #[arg(long, global = true)]
+    pretty: bool,
+    /// Arrondit les montants du JSON à N chiffres significatifs, les ratios et montants par
+    /// action à 4 décimales (pleine précision par défaut).
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=15))]
+    round: Option<u32>,
+    /// Pleine précision dans le JSON, comme par défaut ; incompatible avec `--round`.
+    #[arg(long, global = true, conflicts_with = "round")]
+    raw: bool,
+    /// Logs détaillés sur stderr (`-v` : debug, `-vv` : trace).
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Aucun diagnostic sur stderr, hors erreur fatale.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+}
+
+/// Options de `fetch`.
+#[derive(Args)]
+struct FetchArgs {
+    tickers: Vec<String>,
+    /// CIK récupéré sans passer par le mapping des tickers (répétable).
+    #[arg(long, value_parser = cik_value)]
+    cik: Vec<u64>,
+    /// Recherche par nom d'entreprise au lieu d'un ticker.
+    #[arg(long)]
+    name: Option<String>,
+    /// `companyfacts` local traité hors ligne à la place d'un téléchargement.
+    #[arg(long)]
+    facts_file: Option<PathBuf>,
+    /// Statistiques de groupe sur les tickers d'un fichier (JSON uniquement).
+    #[arg(long, value_name = "FICHIER")]
+    peers: Option<PathBuf>,
+    /// Rangs centiles des ratios de chaque ticker parmi les pairs d'un fichier (même secteur).
+    #[arg(long, value_name = "FICHIER", conflicts_with_all = ["peers", "facts_file"])]
+    benchmark: Option<PathBuf>,
+    /// Lit les tickers sur l'entrée standard, un par ligne (`#` pour commenter).
+    #[arg(long, conflicts_with = "facts_file")]
+    stdin: bool,
+    #[arg(long, default_value = "json", value_parser = PossibleValuesParser::new(["json", "csv", "table"]).map(|f| format_value(&f)))]
+    format: Format,
+    /// Métriques de `financials` retenues, séparées par des virgules.
+    #[arg(long, value_delimiter = ',')]
+    only: Vec<String>,
+    /// Ordre des métriques : alphabétique ou celui de la config.
+    #[arg(long, default_value = "alpha", value_parser = PossibleValuesParser::new(["alpha", "config"]).map(|s| sort_value(&s)))]
+    sort: MetricSort,
+    /// Base SQLite alimentée en plus de la sortie.
+    #[arg(long)]
+    sqlite: Option<PathBuf>,
+    /// Fichier Parquet écrit en plus de la sortie.
+    #[arg(long)]
+    parquet: Option<PathBuf>,
+    /// Cours de l'action, pour la capitalisation et la valeur d'entreprise.
+    #[arg(long, value_parser = positive_price)]
+    price: Option<f64>,
+    /// Demande le cours de chaque ticker au fournisseur de cours.
+    #[arg(long, conflicts_with_all = ["price", "facts_file"])]
+    quote: bool,
+    /// Valorisation DCF (hypothèses ajustables par `--dcf-*`).
+    #[arg(long)]
+    dcf: bool,
+    /// Croissance annuelle du FCF (fraction, ex. 0.05).
+    #[arg(long, value_parser = rate_value)]
+    dcf_growth: Option<f64>,
+    /// Taux d'actualisation (fraction).
+    #[arg(long, value_parser = rate_value)]
+    dcf_discount: Option<f64>,
+    /// Croissance perpétuelle (fraction).
+    #[arg(long, value_parser = rate_value)]
+    dcf_terminal: Option<f64>,
+    /// Nombre d'années projetées.
+    #[arg(long, value_parser = positive_u16)]
+    dcf_years: Option<u16>,
+    /// Fenêtre (en exercices) des résultats normalisés.
+    #[arg(long, value_parser = positive_u16)]
+    normalized_years: Option<u16>,
+    /// Fenêtre (en exercices) du calcul de CAGR ; tout l'historique par défaut.
+    #[arg(long, value_parser = positive_u16)]
+    cagr_years: Option<u16>,
+    /// Pente annuelle (fraction de marge) au-delà de laquelle une marge est en expansion ou en contraction.
+    #[arg(long, value_parser = margin_threshold)]
+    margin_threshold: Option<f64>,
+    /// Téléchargements simultanés d'un lot.
+    #[arg(long, default_value_t = DEFAULT_CONCURRENCY as u16, value_parser = positive_u16)]
+    concurrency: u16,
+    /// Compare les séries à la dernière extraction archivée aux mêmes options, puis les archive (section `diff`).
+    #[arg(long, conflicts_with = "facts_file")]
+    diff: bool,
+    /// Désactive la barre de progression des lots.
+    #[arg(long)]
+    no_progress: bool,
+    /// Affiche le schéma JSON de la sortie au lieu de lancer une extraction.
+    #[arg(long)]
+    print_schema: bool,
+    /// Liste les métriques produites (tags, unité, flux ou stock) au lieu de lancer une extraction.
+    #[arg(long)]
+    list_metrics: bool,
+    #[command(flatten)]
+    extract: ExtractArgs,
+}
+
+/// Options d'extraction, communes à `fetch` et `compare`.
+#[derive(Args)]
+struct ExtractArgs {
+    /// Chiffres sur douze mois glissants (section `ttm`).
+    #[arg(long)]
+    ttm: bool,
+    /// Séries annuelles ou trimestrielles (`quarterly`, JSON uniquement).
+    #[arg(long, default_value = "annual", value_parser = PossibleValuesParser::new(["annual", "quarterly"]).map(|p| period_value(&p)))]
+    period: Period,
+    /// Limite l'historique aux N derniers exercices.
+    #[arg(long, value_parser = positive_u16)]
+    years: Option<u16>,
+    #[arg(long, value_parser = positive_u16)]
+    min_year: Option<u16>,
+    #[arg(long, value_parser = positive_u16)]
+    max_year: Option<u16>,
+    /// Corrige l'historique des divisions d'actions détectées.
+    #[arg(long)]
+    adjust_splits: bool,
+    /// Ajoute la section `segments`.
+    #[arg(long)]
+    segments: bool,
+    /// Rabat une métrique sans tag publié sur un concept au nom proche.
+    #[arg(long)]
+    fuzzy: bool,
+    /// Ne lit que le tag canonique de chaque métrique, sans repli (ni `--fuzzy`).
+    #[arg(long, conflicts_with = "fuzzy")]
+    strict_taxonomy: bool,
+    /// Retire les valeurs aberrantes au lieu de seulement les signaler.
+    #[arg(long)]
+    strict: bool,
+    /// Demande uniquement les concepts extraits (`companyconcept`) plutôt que le companyfacts complet.
+    #[arg(long, conflicts_with = "fuzzy")]
+    lean: bool,
+    /// Multiple de la médiane au-delà (ou en deçà) duquel une valeur est aberrante.
+    #[arg(long, value_parser = outlier_factor)]
+    outlier_factor: Option<f64>,
+    /// Fichier TOML modifiant les métriques extraites.
+    #[arg(long)]
+    metrics: Option<PathBuf>,
+    /// Convertit en USD les montants publiés dans une autre devise.
+    #[arg(long)]
+    convert_usd: bool,
+}
+
+impl ExtractArgs {
+    fn apply(self, opts: &mut Options) -> Result<()> {
+        if let Some(path) = &self.metrics {
+            opts.fetch.metrics = MetricsConfig::load(path)?;
+        }
+        // Après `--metrics` : le tag canonique d'une métrique du fichier est son premier tag
+        if self.strict_taxonomy {
+            opts.fetch.metrics = opts.fetch.metrics.canonical();
+        }
+        opts.convert_usd = self.convert_usd;
+        opts.fetch = FetchOptions {
+            period: self.period,
+            ttm: self.ttm,
+            years: self.years,
+            min_year: self.min_year,
+            max_year: self.max_year,
+            adjust_splits: self.adjust_splits,
+            segments: self.segments,
+            fuzzy: self.fuzzy,
+            outlier_factor: self.outlier_factor,
+            strict: self.strict,
+            lean: self.lean,
+            ..std::mem::take(&mut opts.fetch)
+        };
+        Ok(())
+    }
+}
+
+impl FetchArgs {
+    fn apply(self, opts: &mut Options) -> Result<()> {
+        opts.tickers = self.tickers;
+        if let Some(path) = &self.peers {
+            opts.peers = true;
+            opts.tickers.extend(read_peer_file(path)?);
+        }
+        if let Some(path) = &self.benchmark {
+            opts.benchmark = read_peer_file(path)?;
+        }
+        if self.stdin {
+            let text = std::io::read_to_string(std::io::stdin())
+                .map_err(|source| EngineError::Read { path: PathBuf::from("<stdin>"), source })?;
+            opts.stdin = true;
+            opts.tickers.extend(parse_ticker_list(&text));
+        }
+        opts.ciks = self.cik;
+        opts.name = self.name;
+        opts.facts_file = self.facts_file;
+        opts.format = self.format;
+        let only: Vec<String> = self.only.iter().map(|n| n.trim()).filter(|n| !n.is_empty()).map(String::from).collect();
+        if only.is_empty() && !self.only.is_empty() {
+            return Err(EngineError::InvalidArgument("--only attend une liste de métriques séparées par des virgules".to_string()));
+        }
+        opts.selection = MetricSelection { only: (!only.is_empty()).then_some(only), sort: self.sort };
+        opts.sqlite = self.sqlite;
+        opts.parquet = self.parquet;
+        opts.price = self.price;
+        opts.quote = self.quote;
+        let dcf_requested = self.dcf
+            || self.dcf_growth.is_some()
+            || self.dcf_discount.is_some()
+            || self.dcf_terminal.is_some()
+            || self.dcf_years.is_some();
+        opts.dcf = dcf_requested.then(|| {
+            let defaults = DcfAssumptions::default();
+            DcfAssumptions {
+                growth: self.dcf_growth.unwrap_or(defaults.growth),
+                discount: self.dcf_discount.unwrap_or(defaults.discount),
+                terminal_growth: self.dcf_terminal.unwrap_or(defaults.terminal_growth),
+                years: self.dcf_years.map_or(defaults.years, u32::from),
+            }
+        });
+        opts.normalized_years = self.normalized_years;
+        opts.cagr_years = self.cagr_years;
+        opts.margin_threshold = self.margin_threshold;
+        opts.concurrency = self.concurrency.into();
+        opts.no_progress = self.no_progress;
+        opts.print_schema = self.print_schema;
+        opts.list_metrics = self.list_metrics;
+        opts.fetch.diff = self.diff;
+        self.extract.apply(opts)
+    }
+}
+
+/// Analyse la ligne de commande ; quitte avec l'aide ou l'erreur de `clap` le cas échéant.
+///
+/// Les options de `fetch` placées avant une sous-commande explicite relèveraient de la
+/// sous-commande implicite et seraient ignorées : elles sont refusées.
+fn parse_cli() -> Cli {
+    let mut command = Cli::command();
+    let matches = command.get_matches_mut();
+    if matches.subcommand().is_some() {
+        let fetch = FetchArgs::augment_args(clap::Command::new("fetch"));
+        let misplaced = fetch.get_arguments().find(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine));
+        if let Some(arg) = misplaced {
+            let message = format!("'{}' se place après la sous-commande", arg.get_long().map_or_else(|| arg.get_id().to_string(), |l| format!("--{}", l)));
+            command.error(ErrorKind::ArgumentConflict, message).exit();
+        }
+    }
+    Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+}
+
+/// Options du programme à partir de la ligne de commande analysée, et contrôles croisés
+/// que `clap` ne peut pas exprimer.
+fn options(cli: Cli) -> Result<Options> {
+    let Cli { command, fetch, global } = cli;
+    let mut opts = Options {
+        rate: global.rate,
+        max_retries: global.max_retries,
+        timeout: Duration::from_secs(global.timeout.into()),
+        user_agent: global.user_agent,
+        refresh_cache: global.refresh_cache,
+        cache_dir: global.cache_dir,
+        out: global.out,
+        pretty: global.pretty,
+        rounding: global.round.map_or(Rounding::RAW, Rounding::significant),
+        verbose: global.verbose,
+        quiet: global.quiet,
+        fetch: FetchOptions { offline: global.offline, ..FetchOptions::default() },
+        ..Options::default()
+    };
+    match command.unwrap_or(Command::Fetch(Box::new(fetch))) {
+        Command::Fetch(args) => args.apply(&mut opts)?,
+        Command::Compare { a, b, extract } => {
+            opts.compare = true;
+            opts.tickers = vec![a, b];
+            extract.apply(&mut opts)?;
+        }
+        Command::Frame { query } => opts.frame = Some(query),
+        Command::Concepts { ticker, cik, facts_file } => {
+            if ticker.is_none() && cik.is_none() && facts_file.is_none() {
+                return Err(EngineError::InvalidArgument("concepts attend un ticker, un CIK ou --facts-file".to_string()));
+            }
+            opts.concepts = true;
+            opts.tickers.extend(ticker);
+            opts.ciks.extend(cik);
+            opts.facts_file = facts_file;
+        }
+        Command::Cache => opts.cache_info = true,
+    }
+
+    if opts.fetch.offline && (opts.frame.is_some() || opts.quote || opts.convert_usd || opts.fetch.lean) {
+        return Err(EngineError::InvalidArgument(
+            "--offline n'utilise que le cache : incompatible avec frame, --quote, --convert-usd et --lean".to_string(),
+        ));
+    }
+    if opts.print_schema || opts.list_metrics || opts.cache_info || opts.frame.is_some() { return Ok(opts); }
+    if opts.tickers.is_empty() && opts.ciks.is_empty() && opts.name.is_none() && opts.facts_file.is_none() { return Err(EngineError::MissingTickerArg); }
+    if let (Some(min), Some(max)) = (opts.fetch.min_year, opts.fetch.max_year) {
+        if min > max {
+            return Err(EngineError::InvalidArgument(format!("--min-year ({}) est postérieur à --max-year ({})", min, max)));
+        }
+    }
+    if opts.price.is_some() && opts.tickers.len() + opts.ciks.len() > 1 {
+        return Err(EngineError::InvalidArgument("--price ne s'applique qu'à un seul ticker".to_string()));
+    }
+    if opts.convert_usd && (opts.fetch.ttm || opts.fetch.period == Period::Quarterly || opts.facts_file.is_some()) {
+        return Err(EngineError::InvalidArgument(
+            "--convert-usd ne s'applique qu'aux séries annuelles téléchargées (sans --ttm, --period quarterly ni --facts-file)".to_string(),
+        ));
+    }
+    if (opts.peers || !opts.benchmark.is_empty()) && opts.format != Format::Json {
+        return Err(EngineError::InvalidArgument("--peers et --benchmark ne produisent que du JSON".to_string()));
+    }
+    // CSV et tableau ne restituent que les séries annuelles
+    if opts.fetch.period == Period::Quarterly && opts.format != Format::Json {
+        return Err(EngineError::InvalidArgument("--period quarterly ne produit que du JSON".to_string()));
+    }
+    Ok(opts)
+}
+
+fn format_value(raw: &str) -> Format {
+    match raw {
+        "csv" => Format::Csv,
+        "table" => Format::Table,
+        _ => Format::Json,
+    }
+}
+
+fn sort_value(raw: &str) -> MetricSort {
+    if raw == "config" { MetricSort::Config } else { MetricSort::Alphabetical }
+}
+
+fn period_value(raw: &str) -> Period {
+    if raw == "quarterly" { Period::Quarterly } else { Period::Annual }
+}
+
+fn positive_u16(raw: &str) -> std::result::Result<u16, String> {
+    match raw.parse::<u16>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err("entier > 0 attendu".to_string()),
+    }
+}
+
+fn positive_rate(raw: &str) -> std::result::Result<f64, String> {
+    match raw.parse::<f64>() {
+        Ok(r) if r > 0.0 => Ok(r),
+        _ => Err("nombre > 0 attendu".to_string()),
+    }
+}
+
+fn positive_price(raw: &str) -> std::result::Result<f64, String> {
+    match raw.parse::<f64>() {
+        Ok(p) if p > 0.0 && p.is_finite() => Ok(p),
+        _ => Err("cours > 0 attendu".to_string()),
+    }
+}
+
+fn margin_threshold(raw: &str) -> std::result::Result<f64, String> {
+    match raw.parse::<f64>() {
+        Ok(t) if t >= 0.0 && t.is_finite() => Ok(t),
+        _ => Err("pente >= 0 attendue, en fraction de marge par an (ex. 0.005)".to_string()),
+    }
+}
+
+fn outlier_factor(raw: &str) -> std::result::Result<f64, String> {
+    match raw.parse::<f64>() {
+        Ok(f) if f > 1.0 && f.is_finite() => Ok(f),
+        _ => Err("facteur > 1 attendu".to_string()),
+    }
+}
+
+/// Taux en fraction (`0.08` pour 8 %).
+fn rate_value(raw: &str) -> std::result::Result<f64, String> {
+    match raw.parse::<f64>() {
+        Ok(r) if r.is_finite() && r > -1.0 => Ok(r),
+        _ => Err("taux décimal attendu (ex. 0.08)".to_string()),
+    }
+}
+
+fn cik_value(raw: &str) -> std::result::Result<u64, String> {
+    parse_cik(raw).map_err(|e| e.to_string())
+}
+
+fn frame_query(raw: &str) -> std::result::Result<FrameQuery, String> {
+    FrameQuery::parse(raw).map_err(|e| e.to_string())
+}
+
+/// Unité de chaque métrique publiée (`USD`, `EUR/shares`, `shares`...).
+fn metric_units(data: &CompanyFinancials) -> BTreeMap<String, String> {
+    data.data_quality
+        .iter()
+        .filter_map(|(name, q)| Some((name.clone(), q.unit.clone()?)))
+        .collect()
+}
+
+fn to_output(data: &CompanyFinancials, opts: &Options) -> EngineOutput {
+    let config = opts.fetch.metrics.for_taxonomy(data.taxonomy.unwrap_or(Taxonomy::UsGaap));
+
+    // Le cours est en USD : ni capitalisation ni multiples sur des montants publiés dans une autre devise
+    let usd_figures = data.fx.is_some() || data.reporting_currency.as_deref().is_none_or(|c| c == "USD");
+    let price = data.quote.as_ref().map(|q| q.price).or(opts.price).filter(|_| usd_figures);
+
+    let ratios = compute_ratios(&data.financials);
+    // En mode trimestriel, les séries deviennent des objets {period, value}
+    let financials = match (&data.quarterly, opts.fetch.period) {
+        (Some(quarterly), Period::Quarterly) => FinancialSeries::Quarterly(OrderedSeries::select(quarterly, &opts.selection, config)),
+        _ => FinancialSeries::Annual(OrderedSeries::select(&data.financials, &opts.selection, config)),
+    };
+    EngineOutput {
+        ticker: data.ticker.clone(),
+        cik: data.cik,
+        name: data.name.clone(),
+        taxonomy: data.taxonomy,
+        reporting_currency: data.reporting_currency.clone(),
+        units: metric_units(data),
+        public_float: data.public_float.clone(),
+        financials,
+        margin_trends: compute_margin_trends(&ratios, opts.margin_threshold.unwrap_or(DEFAULT_MARGIN_THRESHOLD)),
+        ratios: ratios.into_iter().collect(),
+        flags: compute_sign_flags(&data.financials),
+        leverage: compute_leverage(&data.financials),
+        roic: compute_roic(&data.financials),
+        working_capital: compute_working_capital(&data.financials),
+        quality: compute_earnings_quality(&data.financials),
+        dupont: compute_dupont(&data.financials),
+        growth: compute_cagr(&data.financials, &flow_metric_names(config), opts.cagr_years).into_iter().collect(),
+        yoy: yoy_growth(&data.financials).into_iter().collect(),
+        valuation: Valuation {
+            graham: graham_valuation(&data.financials),
+            enterprise_value: enterprise_value(&data.financials, price),
+            dcf: opts.dcf.map(|assumptions| dcf_valuation(&data.financials, assumptions)),
+        },
+        quote: data.quote.clone(),
+        multiples: price.map(|price| multiples(&data.financials, data.ttm.as_ref(), price)),
+        normalized: opts.normalized_years.map(|years| normalized_earnings(&data.financials, years, price)),
+        scores: Scores {
+            piotroski: piotroski(data),
+            altman_z: altman_z(data).map(|z_score| AltmanZ { z_score, zone: altman_zone(z_score) }),
+        },
+        data_quality: data.data_quality.clone().into_iter().collect(),
+        ttm: data.ttm.clone().map(|ttm| ttm.into_iter().collect()),
+        splits: data.splits.clone(),
+        segments: data.segments.clone(),
+        fx: data.fx.clone(),
+        diff: data.diff.clone(),
+        warning: data.warning.clone(),
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+
+use crate::error::{EngineError, Result};
+use crate::extract::{MetricDef, UnitKind, IFRS_METRICS, US_GAAP_METRICS};
+use crate::models::Taxonomy;
+
+/// Configs de métriques effectivement utilisées, par taxonomie.
+/// Par défaut : les listes intégrées (`US_GAAP_METRICS`, `IFRS_METRICS`).
+#[derive(Debug, Clone)]
+pub struct MetricsConfig {
+    pub us_gaap: Vec<MetricDef>,
+    pub ifrs_full: Vec<MetricDef>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig { us_gaap: US_GAAP_METRICS.to_vec(), ifrs_full: IFRS_METRICS.to_vec() }
+    }
+}
+
+/// Fichier `--metrics` :
+///
+/// ```toml
+/// mode = "merge"            # ou "replace"
+///
+/// [[metric]]
+/// name = "Lease Liabilities"
+/// tags = ["OperatingLeaseLiability"]
+/// is_instant = true
+/// unit = "monetary"         # "monetary", "shares" ou "per_share"
+/// taxonomy = "us-gaap"      # optionnel ("us-gaap" par défaut, ou "ifrs-full")
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MetricsFile {
+    #[serde(default)]
+    mode: Mode,
+    #[serde(default)]
+    metric: Vec<MetricEntry>,
+}
+
+/// `merge` : une entrée remplace la métrique intégrée de même nom, ou s'ajoute à la liste.
+/// `replace` : les entrées d'une taxonomie remplacent toute sa liste intégrée
+/// (une taxonomie absente du fichier garde la sienne).
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    #[default]
+    Merge,
+    Replace,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MetricEntry {
+    name: String,
+    tags: Vec<String>,
+    #[serde(default)]
+    is_instant: bool,
+    unit: UnitName,
+    #[serde(default)]
+    taxonomy: TaxonomyName,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum UnitName {
+    Monetary,
+    Shares,
+    PerShare,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Hash)]
+enum TaxonomyName {
+    #[default]
+    #[serde(rename = "us-gaap")]
+    UsGaap,
+    #[serde(rename = "ifrs-full")]
+    IfrsFull,
+}
+
+impl MetricsConfig {
+    /// Config de métriques associée à une taxonomie.
+    pub fn for_taxonomy(&self, taxonomy: Taxonomy) -> &[MetricDef] {
+        match taxonomy {
+            Taxonomy::UsGaap => &self.us_gaap,
+            Taxonomy::IfrsFull => &self.ifrs_full,
+        }
+    }
+
+    /// Config réduite aux tags canoniques (`--strict-taxonomy`) : une métrique dont le premier
+    /// tag n'est pas publié est manquante plutôt que lue sur un concept de repli.
+    pub fn canonical(&self) -> Self {
+        let canonical = |defs: &[MetricDef]| defs.iter().map(|def| def.canonical()).collect();
+        MetricsConfig { us_gaap: canonical(&self.us_gaap), ifrs_full: canonical(&self.ifrs_full) }
+    }
+
+    /// Charge un fichier `--metrics` et l'applique aux listes intégrées.
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |message: String| EngineError::MetricsFile { path: path.to_path_buf(), message };
+        let text = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        Self::from_toml(&text).map_err(invalid)
+    }
+
+    /// Comme `load`, à partir du contenu TOML ; l'erreur décrit l'entrée fautive.
+    pub fn from_toml(text: &str) -> std::result::Result<Self, String> {
+        let file: MetricsFile = toml::from_str(text).map_err(|e| e.message().to_string())?;
+
+        let mut seen = HashSet::new();
+        for (i, entry) in file.metric.iter().enumerate() {
+            let label = format!("métrique n°{} ('{}')", i + 1, entry.name);
+            if entry.name.trim().is_empty() {
+                return Err(format!("métrique n°{} : 'name' est vide", i + 1));
+            }
+            if entry.tags.is_empty() || entry.tags.iter().any(|t| t.trim().is_empty()) {
+                return Err(format!("{} : 'tags' doit contenir au moins un concept XBRL non vide", label));
+            }
+            if !seen.insert((entry.taxonomy, entry.name.as_str())) {
+                return Err(format!("{} : définie plusieurs fois", label));
+            }
+        }
+
+        let mut config = MetricsConfig::default();
+        for (taxonomy, list) in [(TaxonomyName::UsGaap, &mut config.us_gaap), (TaxonomyName::IfrsFull, &mut config.ifrs_full)] {
+            let entries: Vec<&MetricEntry> = file.metric.iter().filter(|e| e.taxonomy == taxonomy).collect();
+            if file.mode == Mode::Replace && !entries.is_empty() {
+                list.clear();
+            }
+            for entry in entries {
+                let def = entry.to_def();
+                match list.iter_mut().find(|d| d.name == def.name) {
+                    Some(existing) => *existing = def,
+                    None => list.push(def),
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl MetricEntry {
+    /// Les définitions vivent jusqu'à la fin du programme (chargées une fois au démarrage) :
+    /// on fige leurs chaînes pour garder `MetricDef` identique aux listes intégrées.
+    fn to_def(&self) -> MetricDef {
+        let name: &'static str = Box::leak(self.name.trim().to_string().into_boxed_str());
+        let tags: Vec<&'static str> = self
+            .tags
+            .iter()
+            .map(|t| &*Box::leak(t.trim().to_string().into_boxed_str()))
+            .collect();
+        let tags: &'static [&'static str] = Box::leak(tags.into_boxed_slice());
+        let unit = match self.unit {
+            UnitName::Monetary => UnitKind::Monetary,
+            UnitName::Shares => UnitKind::Shares,
+            UnitName::PerShare => UnitKind::PerShare,
+        };
+        MetricDef { name, tags, is_instant: self.is_instant, expected_unit: unit, exclusive_tags: false }
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::diff::SnapshotDiff;
+use crate::extract::DataQuality;
+use crate::fx::FxConversion;
+use crate::quote::Quote;
+use crate::segments::SegmentReport;
+use crate::splits::SplitEvent;
+
+/// Entrée du fichier `company_tickers.json` publié par la SEC. Seuls le CIK (nombre ou
+/// chaîne, `cik_str` ou `cik`) et le ticker sont indispensables ; les champs inconnus sont ignorés.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TickerEntry {
+    #[serde(alias = "cik", deserialize_with = "cik_number_or_string")]
+    pub cik_str: u64,
+    pub ticker: String,
+    #[serde(default, alias = "name")]
+    pub title: String,
+}
+
+fn cik_number_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawCik {
+        Number(u64),
+        Text(String),
+    }
+    match RawCik::deserialize(deserializer)? {
+        RawCik::Number(cik) => Ok(cik),
+        RawCik::Text(text) => text.trim().parse().map_err(|_| de::Error::custom(format!("CIK invalide : '{}'", text))),
+    }
+}
+
+/// Réponse de l'API `companyfacts` pour un CIK donné.
+#[derive(Deserialize, Debug)]
+pub struct CompanyFacts {
+    #[serde(default)]
+    pub cik: Option<u64>,
+    #[serde(rename = "entityName")]
+    pub entity_name: String,
+    pub facts: FactsContainer,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FactsContainer {
+    #[serde(rename = "us-gaap")]
+    pub us_gaap: Option<HashMap<String, FactData>>,
+    #[serde(rename = "ifrs-full")]
+    pub ifrs_full: Option<HashMap<String, FactData>>,
+    /// Informations de page de garde (actions en circulation, flottant...).
+    pub dei: Option<HashMap<String, FactData>>,
+}
+
+/// Réponse de l'API `companyconcept` : les faits d'un seul concept pour un CIK.
+#[derive(Deserialize, Debug)]
+pub struct CompanyConcept {
+    #[serde(rename = "entityName")]
+    pub entity_name: String,
+    pub units: HashMap<String, Vec<FactUnit>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FactData {
+    pub units: HashMap<String, Vec<FactUnit>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FactUnit {
+    pub val: Option<f64>,
+    /// Numéro d'enregistrement (accession number) du dépôt d'où provient le fait.
+    pub accn: Option<String>,
+    pub fy: Option<u16>,
+    pub fp: Option<String>,
+    pub form: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub filed: Option<String>,
+    pub frame: Option<String>,
+}
+
+/// Taxonomie XBRL dont proviennent les chiffres extraits.
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Taxonomy {
+    #[serde(rename = "us-gaap")]
+    UsGaap,
+    #[serde(rename = "ifrs-full")]
+    IfrsFull,
+}
+
+/// Résultat consolidé pour une entreprise : une série (année, valeur) par métrique.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CompanyFinancials {
+    pub ticker: String,
+    pub cik: u64,
+    pub name: String,
+    pub taxonomy: Option<Taxonomy>,
+    /// Devise des montants publiés (`USD`, `EUR`...), sans conversion.
+    pub reporting_currency: Option<String>,
+    pub financials: HashMap<String, Vec<(u16, f64)>>,
+    /// Dernier flottant publié (`dei:EntityPublicFloat`), daté de sa mesure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_float: Option<PeriodValue>,
+    /// Séries trimestrielles, présentes en mode `--period quarterly` ou `--ttm`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarterly: Option<HashMap<String, Vec<PeriodValue>>>,
+    /// Chiffres sur douze mois glissants, présents en mode `--ttm`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttm: Option<HashMap<String, PeriodValue>>,
+    /// Divisions d'actions détectées et corrigées, en mode `--adjust-splits`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splits: Option<Vec<SplitEvent>>,
+    /// Chiffre d'affaires par segment, en mode `--segments`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segments: Option<SegmentReport>,
+    /// Taux appliqués en mode `--convert-usd`, si une conversion a eu lieu.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fx: Option<FxConversion>,
+    /// Dernier cours, en mode `--quote`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<Quote>,
+    /// Changements depuis la dernière archive des séries, en mode `--diff`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<SnapshotDiff>,
+    /// Diagnostic d'extraction des séries publiées : tag retenu, faits écartés, conflits.
+    pub data_quality: DataQuality,
+    /// Anomalie empêchant l'extraction (ex. aucun fait us-gaap ni ifrs-full).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+impl CompanyFinancials {
+    /// Exercices présents dans au moins une série, par ordre croissant et sans doublon.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use edgar_fetcher::models::CompanyFinancials;
+    ///
+    /// let data = CompanyFinancials {
+    ///     financials: HashMap::from([
+    ///         ("Revenue".to_string(), vec![(2022, 100.0), (2023, 120.0)]),
+    ///         ("Net Income".to_string(), vec![(2021, 8.0), (2023, 15.0)]),
+    ///     ]),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(data.fiscal_years(), vec![2021, 2022, 2023]);
+    ///
+    /// // Tableau exercice par exercice, trous compris
+    /// for year in data.fiscal_years() {
+    ///     let revenue = data.value("Revenue", year);
+    ///     println!("{} : {:?}", year, revenue);
+    /// }
+    /// ```
+    pub fn fiscal_years(&self) -> Vec<u16> {
+        let mut years: Vec<u16> = self.financials.values().flatten().map(|&(year, _)| year).collect();
+        years.sort_unstable();
+        years.dedup();
+        years
+    }
+
+    /// Valeur d'une métrique pour un exercice, `None` si la métrique ou l'exercice manque.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use edgar_fetcher::models::CompanyFinancials;
+    ///
+    /// let data = CompanyFinancials {
+    ///     financials: HashMap::from([("Revenue".to_string(), vec![(2022, 100.0), (2023, 120.0)])]),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(data.value("Revenue", 2023), Some(120.0));
+    /// assert_eq!(data.value("Revenue", 2021), None);
+    /// assert_eq!(data.value("EBITDA", 2023), None);
+    ///
+    /// // Croissance d'un exercice sur l'autre
+    /// let growth = data.value("Revenue", 2023).zip(data.value("Revenue", 2022)).map(|(n, p)| n / p - 1.0);
+    /// assert!((growth.unwrap() - 0.2).abs() < 1e-12);
+    /// ```
+    pub fn value(&self, metric: &str, year: u16) -> Option<f64> {
+        self.financials.get(metric)?.iter().find(|&&(y, _)| y == year).map(|&(_, value)| value)
+    }
+}
+
+/// Valeur d'une période nommée (ex. `2023-Q2`).
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct PeriodValue {
+    pub period: String,
+    pub value: f64,
+}
+
+impl PeriodValue {
+    /// Exercice d'une période trimestrielle `AAAA-Qn`.
+    pub fn fiscal_year(&self) -> Option<u16> {
+        self.period.split_once("-Q")?.0.parse().ok()
+    }
+}
@@ -0,0 +1,552 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use serde_json::ser::{CharEscape, CompactFormatter, Formatter, PrettyFormatter};
+
+use crate::derive::{derived_kind, DERIVED_METRICS};
+use crate::diff::SnapshotDiff;
+use crate::extract::{MetricDef, MetricQuality, UnitKind};
+use crate::metrics::MetricsConfig;
+use crate::fx::FxConversion;
+use crate::models::{CompanyFinancials, PeriodValue, Taxonomy};
+use crate::ratios::{DupontYear, EarningsQuality, Leverage, MarginTrend, Roic, SignFlags, WorkingCapital};
+use crate::scores::Piotroski;
+use crate::segments::SegmentReport;
+use crate::splits::SplitEvent;
+use crate::quote::Quote;
+use crate::valuation::{DcfValuation, EnterpriseValue, GrahamValuation, Multiples, Normalized};
+
+/// Format de sortie de la CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+    /// Tableau aligné pour le terminal.
+    Table,
+}
+
+/// Ordre des métriques dans `financials`, le CSV et le tableau (`--sort`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetricSort {
+    #[default]
+    Alphabetical,
+    /// Ordre de la config de métriques ; les dérivées suivent, par ordre alphabétique.
+    Config,
+}
+
+/// Décimales conservées avec `--round` pour les ratios et montants par action.
+pub const RATIO_DECIMALS: u32 = 4;
+
+/// Sections dont toutes les valeurs sont des ratios ou des taux, quel que soit le nom des séries.
+const DECIMAL_SECTIONS: &[&str] = &["ratios", "yoy", "growth"];
+
+/// Sections de diagnostic, toujours écrites en pleine précision.
+const UNROUNDED_SECTIONS: &[&str] = &["data_quality", "fx"];
+
+/// Champs de la sortie qui portent un montant (ou un nombre d'actions).
+const AMOUNT_FIELDS: &[&str] = &[
+    "public_float", "net_debt", "nopat", "invested_capital", "working_capital", "change", "total_debt", "cash",
+    "market_cap", "enterprise_value", "base_fcf", "shares", "consolidated",
+];
+
+/// Champs de la sortie qui portent un ratio, un taux ou un montant par action.
+const DECIMAL_FIELDS: &[&str] = &[
+    "debt_to_equity", "roic", "cash_conversion", "accruals_ratio", "slope", "net_margin", "asset_turnover",
+    "equity_multiplier", "roe", "reported_roe", "intrinsic_value_per_share", "eps", "book_value_per_share",
+    "graham_number", "ev_to_ebit", "ev_to_revenue", "normalized_pe", "price", "pe", "pb", "p_fcf", "dividend_yield",
+    "z_score", "ratio", "growth", "discount", "terminal_growth",
+];
+
+/// Nature d'un nombre de la sortie, qui décide de son arrondi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberKind {
+    /// Montant ou nombre d'actions : chiffres significatifs.
+    Amount,
+    /// Ratio, taux ou montant par action : décimales.
+    Decimal,
+}
+
+/// Nature de chaque métrique, d'après la dimension de la config (deux taxonomies) et celle des
+/// métriques dérivées.
+#[derive(Debug, Clone, Default)]
+pub struct MetricKinds(HashMap<String, NumberKind>);
+
+impl MetricKinds {
+    pub fn new(config: &MetricsConfig) -> Self {
+        let configured = config.us_gaap.iter().chain(&config.ifrs_full).map(|def| {
+            let kind = if def.expected_unit == UnitKind::PerShare { NumberKind::Decimal } else { NumberKind::Amount };
+            (def.name.to_string(), kind)
+        });
+        let derived = DERIVED_METRICS.iter().map(|name| {
+            let kind = if derived_kind(name).1 == "monetary" { NumberKind::Amount } else { NumberKind::Decimal };
+            (name.to_string(), kind)
+        });
+        // La config prime sur une dérivée de même nom
+        MetricKinds(derived.chain(configured).collect())
+    }
+
+    /// Nature d'un nombre d'après les clés des objets qui l'entourent, de l'extérieur vers
+    /// l'intérieur ; `None` s'il n'est pas classé (il reste alors en pleine précision).
+    fn classify(&self, keys: &[String]) -> Option<NumberKind> {
+        if keys.iter().any(|key| UNROUNDED_SECTIONS.contains(&key.as_str())) {
+            return None;
+        }
+        if keys.iter().any(|key| DECIMAL_SECTIONS.contains(&key.as_str())) {
+            return Some(NumberKind::Decimal);
+        }
+        // La clé la plus proche l'emporte : `ev_to_ebit` dans `enterprise_value` reste un ratio
+        keys.iter().rev().find_map(|key| match key.as_str() {
+            k if AMOUNT_FIELDS.contains(&k) => Some(NumberKind::Amount),
+            k if DECIMAL_FIELDS.contains(&k) => Some(NumberKind::Decimal),
+            k => self.0.get(k).copied(),
+        })
+    }
+}
+
+/// Arrondi des nombres de la sortie JSON (`--round`), appliqué à l'écriture seulement : les
+/// calculs gardent la pleine précision. Les entiers (exercices, CIK) ne sont jamais arrondis.
+/// Pleine précision par défaut (et avec `--raw`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rounding {
+    /// Chiffres significatifs des montants.
+    pub significant: Option<u32>,
+    /// Décimales des ratios et montants par action.
+    pub decimals: Option<u32>,
+}
+
+impl Rounding {
+    /// Pleine précision partout (`--raw`).
+    pub const RAW: Rounding = Rounding { significant: None, decimals: None };
+
+    /// `--round N` : N chiffres significatifs pour les montants, `RATIO_DECIMALS` décimales
+    /// pour le reste.
+    pub fn significant(digits: u32) -> Self {
+        Rounding { significant: Some(digits), decimals: Some(RATIO_DECIMALS) }
+    }
+
+    /// `383285000000` -> `383000000000` sur 3 chiffres significatifs (montant),
+    /// `3.2899999999` -> `3.29` (ratio).
+    pub fn apply(self, value: f64, kind: NumberKind) -> f64 {
+        let rounded = match (kind, self.significant, self.decimals) {
+            (NumberKind::Amount, Some(digits), _) if value != 0.0 => {
+                let scale = 10f64.powi(value.abs().log10().floor() as i32 + 1 - digits.max(1) as i32);
+                (value / scale).round() * scale
+            }
+            (NumberKind::Decimal, _, Some(decimals)) => {
+                let scale = 10f64.powi(decimals as i32);
+                (value * scale).round() / scale
+            }
+            _ => value,
+        };
+        // Pas de `-0.0` pour un petit nombre négatif arrondi à zéro
+        if rounded == 0.0 { 0.0 } else { rounded }
+    }
+}
+
+/// Sérialise en JSON (indenté avec `pretty`) en arrondissant les nombres à virgule selon
+/// `rounding` et leur nature (`kinds`), sans changer l'ordre des clés.
+pub fn to_json_string(value: &impl Serialize, pretty: bool, rounding: Rounding, kinds: &MetricKinds) -> serde_json::Result<String> {
+    let mut out = Vec::new();
+    if pretty {
+        value.serialize(&mut serde_json::Serializer::with_formatter(&mut out, RoundingFormatter::new(PrettyFormatter::new(), rounding, kinds)))?;
+    } else {
+        value.serialize(&mut serde_json::Serializer::with_formatter(&mut out, RoundingFormatter::new(CompactFormatter, rounding, kinds)))?;
+    }
+    // serde_json n'écrit que de l'UTF-8 valide
+    Ok(String::from_utf8(out).unwrap_or_default())
+}
+
+/// Formateur qui arrondit les `f64` et délègue la mise en page (compacte ou indentée). Il suit
+/// la clé courante de chaque objet ouvert pour connaître la nature des nombres écrits.
+struct RoundingFormatter<'a, F> {
+    inner: F,
+    rounding: Rounding,
+    kinds: &'a MetricKinds,
+    keys: Vec<String>,
+    in_key: bool,
+}
+
+impl<'a, F> RoundingFormatter<'a, F> {
+    fn new(inner: F, rounding: Rounding, kinds: &'a MetricKinds) -> Self {
+        RoundingFormatter { inner, rounding, kinds, keys: Vec::new(), in_key: false }
+    }
+}
+
+impl<F: Formatter> Formatter for RoundingFormatter<'_, F> {
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        let value = match self.kinds.classify(&self.keys) {
+            Some(kind) => self.rounding.apply(value, kind),
+            None => value,
+        };
+        self.inner.write_f64(writer, value)
+    }
+
+    fn write_string_fragment<W: ?Sized + io::Write>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()> {
+        if self.in_key {
+            if let Some(key) = self.keys.last_mut() {
+                key.push_str(fragment);
+            }
+        }
+        self.inner.write_string_fragment(writer, fragment)
+    }
+
+    fn write_char_escape<W: ?Sized + io::Write>(&mut self, writer: &mut W, char_escape: CharEscape) -> io::Result<()> {
+        if let (true, Some(key)) = (self.in_key, self.keys.last_mut()) {
+            match char_escape {
+                CharEscape::Quote => key.push('"'),
+                CharEscape::ReverseSolidus => key.push('\\'),
+                _ => {}
+            }
+        }
+        self.inner.write_char_escape(writer, char_escape)
+    }
+
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        self.inner.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.keys.push(String::new());
+        self.inner.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.keys.pop();
+        self.inner.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        if let Some(key) = self.keys.last_mut() {
+            key.clear();
+        }
+        self.in_key = true;
+        self.inner.begin_object_key(writer, first)
+    }
+
+    fn end_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.in_key = false;
+        self.inner.end_object_key(writer)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.inner.end_object_value(writer)
+    }
+}
+
+/// Métriques retenues en sortie (`--only`) et leur ordre (`--sort`).
+#[derive(Debug, Clone, Default)]
+pub struct MetricSelection {
+    /// `None` : toutes les métriques.
+    pub only: Option<Vec<String>>,
+    pub sort: MetricSort,
+}
+
+impl MetricSelection {
+    /// Noms de `names` à afficher, dans l'ordre demandé.
+    pub fn apply<'a>(&self, names: impl IntoIterator<Item = &'a String>, config: &[MetricDef]) -> Vec<&'a String> {
+        let mut selected: Vec<&String> = names
+            .into_iter()
+            .filter(|name| self.only.as_ref().is_none_or(|only| only.contains(name)))
+            .collect();
+        selected.sort();
+        if self.sort == MetricSort::Config {
+            // Tri stable : les métriques hors config gardent l'ordre alphabétique
+            selected.sort_by_key(|name| config.iter().position(|def| def.name == name.as_str()).unwrap_or(usize::MAX));
+        }
+        selected
+    }
+}
+
+/// Séries par métrique dans un ordre choisi, sérialisées comme un objet JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderedSeries<T>(pub Vec<(String, Vec<T>)>);
+
+impl<T: Clone> OrderedSeries<T> {
+    pub fn select(series: &HashMap<String, Vec<T>>, selection: &MetricSelection, config: &[MetricDef]) -> Self {
+        OrderedSeries(selection.apply(series.keys(), config).into_iter().map(|name| (name.clone(), series[name].clone())).collect())
+    }
+}
+
+impl<T: Serialize> Serialize for OrderedSeries<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, values) in &self.0 {
+            map.serialize_entry(name, values)?;
+        }
+        map.end()
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for OrderedSeries<T> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        <BTreeMap<String, Vec<T>>>::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        <BTreeMap<String, Vec<T>>>::json_schema(gen)
+    }
+}
+
+/// Contrat de la sortie JSON d'une entreprise. Le schéma (`--print-schema`) en est dérivé :
+/// renommer un champ ici change le contrat de façon visible. Les sections indexées par nom
+/// sont triées pour une sortie stable ; `financials` suit l'ordre de `--sort`.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct EngineOutput {
+    pub ticker: String,
+    pub cik: u64,
+    pub name: String,
+    pub taxonomy: Option<Taxonomy>,
+    /// Devise des montants publiés, avant une éventuelle conversion (`fx`).
+    pub reporting_currency: Option<String>,
+    /// Unité de chaque métrique publiée (`USD`, `EUR/shares`, `shares`...).
+    pub units: BTreeMap<String, String>,
+    pub public_float: Option<PeriodValue>,
+    pub financials: FinancialSeries,
+    pub ratios: BTreeMap<String, Vec<(u16, f64)>>,
+    pub flags: SignFlags,
+    /// Sens d'évolution des marges brute, opérationnelle et nette.
+    pub margin_trends: BTreeMap<String, MarginTrend>,
+    pub leverage: Leverage,
+    pub roic: Roic,
+    pub working_capital: WorkingCapital,
+    pub quality: EarningsQuality,
+    /// Décomposition DuPont du ROE par exercice.
+    pub dupont: Vec<DupontYear>,
+    /// CAGR des métriques de flux ; `null` quand il n'a pas de sens.
+    pub growth: BTreeMap<String, Option<f64>>,
+    pub yoy: BTreeMap<String, Vec<(u16, f64)>>,
+    pub valuation: Valuation,
+    /// Cours obtenu par `--quote`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<Quote>,
+    /// Multiples de valorisation, dès qu'un cours est connu et que les montants sont en USD.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiples: Option<Multiples>,
+    /// Résultats lissés, avec `--normalized-years`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<Normalized>,
+    pub scores: Scores,
+    pub data_quality: BTreeMap<String, MetricQuality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttm: Option<BTreeMap<String, PeriodValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splits: Option<Vec<SplitEvent>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segments: Option<SegmentReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fx: Option<FxConversion>,
+    /// Valeurs retraitées et exercices ajoutés depuis la dernière archive, avec `--diff`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<SnapshotDiff>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// Séries de la section `financials` : annuelles par défaut, trimestrielles avec
+/// `--period quarterly`.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum FinancialSeries {
+    /// (exercice, valeur) par métrique.
+    Annual(OrderedSeries<(u16, f64)>),
+    /// Valeurs indexées `AAAA-Qn` par métrique.
+    Quarterly(OrderedSeries<PeriodValue>),
+}
+
+/// Section `valuation`.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct Valuation {
+    pub graham: Option<GrahamValuation>,
+    pub enterprise_value: Option<EnterpriseValue>,
+    /// Présent avec `--dcf` ; `null` si le FCF ou le nombre d'actions manque.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dcf: Option<Option<DcfValuation>>,
+}
+
+/// Section `scores`.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct Scores {
+    pub piotroski: Option<Piotroski>,
+    pub altman_z: Option<AltmanZ>,
+}
+
+/// Z-score d'Altman et zone d'interprétation (`safe`, `grey`, `distress`).
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct AltmanZ {
+    pub z_score: f64,
+    pub zone: &'static str,
+}
+
+/// Entrée d'un lot pour un ticker en échec.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct FailedTicker {
+    pub ticker: String,
+    pub error: String,
+}
+
+/// Tableau CSV : une ligne par métrique, une colonne par exercice (cases vides si absent).
+pub fn to_csv(data: &CompanyFinancials) -> String {
+    to_csv_batch(std::slice::from_ref(data))
+}
+
+/// Comme `to_csv` pour plusieurs entreprises, qui partagent alors les mêmes colonnes
+/// (union triée des exercices de toutes les métriques de toutes les entreprises).
+pub fn to_csv_batch(companies: &[CompanyFinancials]) -> String {
+    to_csv_batch_with(companies, &MetricsConfig::default(), &MetricSelection::default())
+}
+
+/// `to_csv_batch` restreint et ordonné selon `selection` (`--only`, `--sort`).
+pub fn to_csv_batch_with(companies: &[CompanyFinancials], config: &MetricsConfig, selection: &MetricSelection) -> String {
+    let selected: Vec<(&CompanyFinancials, Vec<&String>)> = companies
+        .iter()
+        .map(|c| (c, selection.apply(c.financials.keys(), company_config(c, config))))
+        .collect();
+    let years: BTreeSet<u16> = selected
+        .iter()
+        .flat_map(|(c, metrics)| metrics.iter().flat_map(|m| c.financials[*m].iter().map(|&(y, _)| y)))
+        .collect();
+
+    let mut out = String::from("ticker,metric");
+    for year in &years {
+        out.push_str(&format!(",{}", year));
+    }
+    out.push('\n');
+
+    for (company, metrics) in selected {
+        for metric in metrics {
+            let series = &company.financials[metric];
+            out.push_str(&csv_field(&company.ticker));
+            out.push(',');
+            out.push_str(&csv_field(metric));
+            for year in &years {
+                out.push(',');
+                if let Some(&(_, value)) = series.iter().find(|&&(y, _)| y == *year) {
+                    out.push_str(&value.to_string());
+                }
+            }
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+/// Tableau texte aligné (métriques x exercices) pour une lecture en terminal.
+/// Les montants sont abrégés (`1.2B`, `345.0M`) et les cases manquantes affichent `-`.
+pub fn to_table(data: &CompanyFinancials) -> String {
+    to_table_with(data, &MetricsConfig::default(), &MetricSelection::default())
+}
+
+/// `to_table` restreint et ordonné selon `selection` (`--only`, `--sort`).
+pub fn to_table_with(data: &CompanyFinancials, config: &MetricsConfig, selection: &MetricSelection) -> String {
+    let metrics = selection.apply(data.financials.keys(), company_config(data, config));
+    let years: BTreeSet<u16> = metrics.iter().flat_map(|m| data.financials[*m].iter().map(|&(y, _)| y)).collect();
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut header = vec!["Metric".to_string()];
+    header.extend(years.iter().map(|y| y.to_string()));
+    rows.push(header);
+    for metric in metrics {
+        let series = &data.financials[metric];
+        let mut row = vec![metric.clone()];
+        row.extend(years.iter().map(|year| {
+            series
+                .iter()
+                .find(|&&(y, _)| y == *year)
+                .map_or_else(|| "-".to_string(), |&(_, v)| format_number(v))
+        }));
+        rows.push(row);
+    }
+
+    let columns = rows[0].len();
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect();
+
+    let mut out = format!("{} - {} (CIK {})\n", data.ticker, data.name, data.cik);
+    for (n, row) in rows.iter().enumerate() {
+        let cells: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| if i == 0 { format!("{:<w$}", cell, w = widths[i]) } else { format!("{:>w$}", cell, w = widths[i]) })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+        if n == 0 {
+            out.push_str(&"-".repeat(widths.iter().sum::<usize>() + 2 * (columns - 1)));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Config de métriques de la taxonomie d'une entreprise (us-gaap à défaut).
+fn company_config<'a>(data: &CompanyFinancials, config: &'a MetricsConfig) -> &'a [MetricDef] {
+    config.for_taxonomy(data.taxonomy.unwrap_or(Taxonomy::UsGaap))
+}
+
+/// `383285000000` -> `383.3B`, `-2.5e6` -> `-2.5M`, `6.13` -> `6.13`.
+/// La partie entière reçoit des séparateurs de milliers (`1,234.5B`). Le suffixe est choisi
+/// après arrondi : `999950` -> `1.0M`, pas `1,000.0K`.
+pub fn format_number(value: f64) -> String {
+    const TIERS: [(f64, &str, usize); 4] = [(1e9, "B", 1), (1e6, "M", 1), (1e3, "K", 1), (1.0, "", 2)];
+    let abs = value.abs();
+    let mut tier = TIERS.iter().position(|&(unit, _, _)| abs >= unit).unwrap_or(TIERS.len() - 1);
+    let (formatted, suffix) = loop {
+        let (unit, suffix, decimals) = TIERS[tier];
+        let formatted = format!("{:.*}", decimals, abs / unit);
+        // Arrondi jusqu'à 1000 : on passe au suffixe supérieur (sauf pour B, le plus grand)
+        if tier == 0 || !formatted.parse::<f64>().is_ok_and(|rounded| rounded >= 1e3) {
+            break (formatted, suffix);
+        }
+        tier -= 1;
+    };
+    let (int_part, frac_part) = formatted.split_once('.').unwrap_or((&formatted, ""));
+    let sign = if value < 0.0 { "-" } else { "" };
+    format!("{}{}.{}{}", sign, group_thousands(int_part), frac_part, suffix)
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Échappe un champ CSV (RFC 4180) s'il contient une virgule, un guillemet ou un saut de ligne.
+fn csv_field(raw: &str) -> String {
+    if raw.contains([',', '"', '\n']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw.to_string()
+    }
+}
@@ -0,0 +1,62 @@
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt16Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+
+use crate::error::{EngineError, Result};
+use crate::models::CompanyFinancials;
+
+/// Schéma des lignes exportées : format long, une ligne par (ticker, métrique, exercice).
+/// Les types sont figés : un changement ici casse les pipelines qui relisent le fichier.
+pub fn parquet_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("ticker", DataType::Utf8, false),
+        Field::new("cik", DataType::UInt64, false),
+        Field::new("metric", DataType::Utf8, false),
+        Field::new("fiscal_year", DataType::UInt16, false),
+        Field::new("value", DataType::Float64, false),
+        // Absent pour les métriques dérivées, qui n'ont pas de fait source
+        Field::new("unit", DataType::Utf8, true),
+    ])
+}
+
+/// Écrit les séries dans un fichier Parquet (remplacé s'il existe), triées par ticker
+/// (ordre du lot), métrique puis exercice. Renvoie le nombre de lignes écrites.
+pub fn export_parquet(path: &Path, companies: &[CompanyFinancials]) -> Result<usize> {
+    let (mut tickers, mut ciks, mut metrics, mut years, mut values, mut units) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for company in companies {
+        let mut names: Vec<&String> = company.financials.keys().collect();
+        names.sort();
+        for name in names {
+            let unit = company.data_quality.get(name).and_then(|q| q.unit.as_deref());
+            for &(year, value) in &company.financials[name] {
+                tickers.push(company.ticker.as_str());
+                ciks.push(company.cik);
+                metrics.push(name.as_str());
+                years.push(year);
+                values.push(value);
+                units.push(unit);
+            }
+        }
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(tickers)),
+        Arc::new(UInt64Array::from(ciks)),
+        Arc::new(StringArray::from(metrics)),
+        Arc::new(UInt16Array::from(years)),
+        Arc::new(Float64Array::from(values)),
+        Arc::new(StringArray::from(units)),
+    ];
+    let schema = Arc::new(parquet_schema());
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+    let file = File::create(path).map_err(|source| EngineError::Write { path: path.to_path_buf(), source })?;
+    let mut writer = ArrowWriter::try_new(file, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(batch.num_rows())
+}
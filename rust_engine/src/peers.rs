@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+use crate::error::{EngineError, Result};
+use crate::models::CompanyFinancials;
+use crate::ratios::compute_ratios;
+
+/// Statistiques transversales d'un ratio sur le groupe de pairs (dernier exercice de chacun).
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerStat {
+    pub mean: f64,
+    pub median: f64,
+    /// Valeur de chaque membre ayant publié le ratio.
+    pub members: BTreeMap<String, f64>,
+}
+
+/// Position d'un ratio de l'entreprise dans son groupe de pairs (`--benchmark`).
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PeerRank {
+    /// Valeur du dernier exercice publié par l'entreprise.
+    pub value: f64,
+    /// Rang centile (0 à 100) : part des pairs sous cette valeur, les ex-aequo comptant pour moitié.
+    pub percentile: f64,
+    /// Pairs ayant publié le ratio, l'entreprise elle-même exclue.
+    pub peers: usize,
+}
+
+/// Lit un fichier de tickers (voir `parse_ticker_list`).
+pub fn read_peer_file(path: &Path) -> Result<Vec<String>> {
+    let text = fs::read_to_string(path).map_err(|source| EngineError::Read { path: path.to_path_buf(), source })?;
+    Ok(parse_ticker_list(&text))
+}
+
+/// Liste de tickers : un par ligne (ou séparés par des virgules), lignes vides et
+/// commentaires `#` ignorés.
+pub fn parse_ticker_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Moyenne et médiane de chaque ratio sur le dernier exercice publié par chaque membre.
+pub fn peer_stats(companies: &[CompanyFinancials]) -> BTreeMap<String, PeerStat> {
+    let mut by_ratio: HashMap<String, BTreeMap<String, f64>> = HashMap::new();
+    for company in companies {
+        for (name, series) in compute_ratios(&company.financials) {
+            if let Some(&(_, value)) = series.last() {
+                by_ratio.entry(name).or_default().insert(company.ticker.clone(), value);
+            }
+        }
+    }
+
+    by_ratio
+        .into_iter()
+        .map(|(name, members)| {
+            let mut values: Vec<f64> = members.values().copied().collect();
+            values.sort_by(f64::total_cmp);
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            (name, PeerStat { mean, median: median(&values), members })
+        })
+        .collect()
+}
+
+/// Rangs centiles des ratios du dernier exercice de `company` parmi les membres de `stats`,
+/// l'entreprise elle-même exclue. Un ratio qu'aucun pair ne publie n'a pas de rang.
+///
+/// Le rang situe la valeur sans la juger : un centile élevé est favorable pour une marge,
+/// défavorable pour `SBC / Revenue`.
+pub fn peer_ranks(company: &CompanyFinancials, stats: &BTreeMap<String, PeerStat>) -> BTreeMap<String, PeerRank> {
+    compute_ratios(&company.financials)
+        .into_iter()
+        .filter_map(|(name, series)| {
+            let &(_, value) = series.last()?;
+            let others: Vec<f64> = stats
+                .get(&name)?
+                .members
+                .iter()
+                .filter(|(ticker, _)| **ticker != company.ticker)
+                .map(|(_, &v)| v)
+                .collect();
+            if others.is_empty() {
+                return None;
+            }
+            let below = others.iter().filter(|&&v| v < value).count() as f64;
+            let ties = others.iter().filter(|&&v| v == value).count() as f64;
+            let percentile = 100.0 * (below + ties / 2.0) / others.len() as f64;
+            Some((name, PeerRank { value, percentile, peers: others.len() }))
+        })
+        .collect()
+}
+
+/// Médiane d'une liste triée non vide.
+pub(crate) fn median(sorted: &[f64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] }
+}
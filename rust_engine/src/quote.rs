@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::cache::Cache;
+use crate::error::{EngineError, Result};
+use crate::http::HttpClient;
+
+/// Fournisseur de cours par défaut : Stooq (CSV gratuit, sans clé, cours de clôture récent).
+pub const DEFAULT_QUOTE_URL: &str = "https://stooq.com";
+
+/// Remplace l'hôte du fournisseur de cours (proxy, serveur de test).
+pub const QUOTE_BASE_URL_ENV: &str = "QUOTE_BASE_URL";
+
+/// Durée de validité d'un cours en cache : assez courte pour rester « live », assez longue
+/// pour qu'un lot relancé ne redemande pas les mêmes tickers.
+pub const QUOTE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Dernier cours connu d'un ticker (`--quote`).
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Quote {
+    /// Symbole interrogé chez le fournisseur (`aapl.us`).
+    pub symbol: String,
+    pub price: f64,
+    /// Séance du cours, si le fournisseur la donne.
+    pub date: Option<NaiveDate>,
+}
+
+/// Source de cours par ticker.
+pub trait QuoteProvider {
+    fn quote(&self, ticker: &str) -> impl Future<Output = Result<Quote>> + Send;
+}
+
+/// Client Stooq : `GET /q/l/?s={symbole}&f=sd2c&h&e=csv`.
+#[derive(Debug)]
+pub struct Stooq {
+    http: HttpClient,
+    base: String,
+}
+
+impl Stooq {
+    pub fn new(http: HttpClient, base: &str) -> Self {
+        Stooq { http, base: base.trim_end_matches('/').to_string() }
+    }
+
+    /// Hôte par défaut, sauf surcharge par `QUOTE_BASE_URL`.
+    pub fn from_env(http: HttpClient) -> Self {
+        let base = env::var(QUOTE_BASE_URL_ENV).ok().filter(|v| !v.trim().is_empty());
+        Stooq::new(http, base.as_deref().unwrap_or(DEFAULT_QUOTE_URL))
+    }
+}
+
+/// Symbole Stooq d'un ticker américain : minuscules, suffixe `.us`, classe d'action
+/// séparée par un tiret (`BRK.B` -> `brk-b.us`).
+pub fn stooq_symbol(ticker: &str) -> String {
+    format!("{}.us", ticker.trim().to_lowercase().replace('.', "-"))
+}
+
+impl QuoteProvider for Stooq {
+    async fn quote(&self, ticker: &str) -> Result<Quote> {
+        let symbol = stooq_symbol(ticker);
+        let url = format!("{}/q/l/?s={}&f=sd2c&h&e=csv", self.base, symbol);
+        let body = self.http.fetch_with_retry(&url).await?.text();
+        parse_stooq_csv(&body, &symbol).ok_or_else(|| EngineError::QuoteUnavailable(ticker.to_string()))
+    }
+}
+
+/// Ligne de données `Symbol,Date,Close` ; un symbole inconnu donne `N/D` à la place du cours.
+fn parse_stooq_csv(body: &str, symbol: &str) -> Option<Quote> {
+    let row = body.lines().nth(1)?;
+    let mut fields = row.split(',').skip(1);
+    let date = fields.next().and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok());
+    let price = fields.next()?.trim().parse::<f64>().ok().filter(|p| p.is_finite() && *p > 0.0)?;
+    Some(Quote { symbol: symbol.to_string(), price, date })
+}
+
+/// Cours mémorisés par ticker pour le lot en cours et, avec un cache disque, pendant `QUOTE_TTL`.
+pub struct CachedQuotes<P> {
+    provider: P,
+    quotes: Mutex<HashMap<String, Quote>>,
+    cache: Option<Cache>,
+}
+
+impl<P: QuoteProvider> CachedQuotes<P> {
+    pub fn new(provider: P, cache: Option<Cache>) -> Self {
+        let quotes = cache.as_ref().map(|c| c.load_quotes(QUOTE_TTL)).unwrap_or_default();
+        CachedQuotes { provider, quotes: Mutex::new(quotes), cache }
+    }
+
+    pub async fn quote(&self, ticker: &str) -> Result<Quote> {
+        if let Some(quote) = self.lock().get(ticker) {
+            return Ok(quote.clone());
+        }
+        let quote = self.provider.quote(ticker).await?;
+        debug!(ticker, price = quote.price, "cours téléchargé");
+        self.lock().insert(ticker.to_string(), quote.clone());
+        if let Some(cache) = &self.cache {
+            cache.store_quote(ticker, &quote);
+        }
+        Ok(quote)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Quote>> {
+        self.quotes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Débit par défaut : la SEC tolère 10 req/s, on garde de la marge.
+pub const DEFAULT_RATE: f64 = 8.0;
+
+/// Jetons que le seau peut accumuler.
+const BUCKET_CAPACITY: f64 = 1.0;
+
+/// Limiteur "token bucket" partagé par toutes les requêtes vers la SEC.
+///
+/// Le seau contient au plus un jeton, dès le départ, et se recharge en continu à `rate`
+/// jetons par seconde : deux requêtes sont espacées d'au moins `1 / rate`, si bien qu'aucune
+/// fenêtre d'une seconde, démarrage à froid compris, n'en voit passer plus de `rate`
+/// (un seau de `rate` jetons laisserait passer une rafale, puis la recharge, soit près du
+/// double). `acquire` suspend la tâche appelante tant
+/// qu'aucun jeton n'est disponible ; il est partagé entre toutes les requêtes
+/// concurrentes, qui se répartissent donc le même débit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64) -> Self {
+        RateLimiter { rate, state: Mutex::new(Bucket { tokens: BUCKET_CAPACITY, last_refill: Instant::now() }) }
+    }
+
+    /// Attend qu'un jeton soit disponible puis le consomme.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.rate).min(BUCKET_CAPACITY);
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                // Temps nécessaire pour regagner le jeton manquant
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(DEFAULT_RATE)
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, HashMap};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::derive::{combine, effective_tax_rate, TaxRateOmission};
+
+/// Section `leverage` : endettement par exercice.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Leverage {
+    /// `Total Debt - Cash & Equiv.` (trésorerie non publiée comptée nulle).
+    pub net_debt: Vec<(u16, f64)>,
+    /// `Total Debt / Total Equity`, uniquement pour des capitaux propres positifs.
+    pub debt_to_equity: Vec<(u16, f64)>,
+    /// Exercices à capitaux propres nuls ou négatifs, où le D/E n'a pas de sens.
+    pub negative_equity_years: Vec<u16>,
+    /// `EBIT / Interest Expense` ; `null` quand aucune charge d'intérêts n'est publiée
+    /// ou qu'elle est nulle (pas de service de la dette).
+    pub interest_coverage: Vec<(u16, Option<f64>)>,
+}
+
+/// Section `roic` : rentabilité du capital investi et ses composantes.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Roic {
+    /// `EBIT × (1 - taux d'imposition effectif)`, pour les exercices où le taux a un sens.
+    pub nopat: Vec<(u16, f64)>,
+    /// `Total Equity + Total Debt - Cash & Equiv.` (dette et trésorerie non publiées comptées nulles).
+    pub invested_capital: Vec<(u16, f64)>,
+    /// `NOPAT / capital investi`, uniquement pour un capital investi positif.
+    pub roic: Vec<(u16, f64)>,
+}
+
+/// Section `working_capital` : besoin en fonds de roulement par exercice.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct WorkingCapital {
+    /// `Total Current Assets - Total Current Liabilities`.
+    pub working_capital: Vec<(u16, f64)>,
+    /// Variation par rapport à l'exercice précédent ; absente sans exercice N-1.
+    pub change: Vec<(u16, f64)>,
+}
+
+/// Section `quality` : qualité des résultats, part du bénéfice convertie en trésorerie.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct EarningsQuality {
+    /// `Free Cash Flow / Net Income`, uniquement pour un résultat net positif.
+    pub cash_conversion: Vec<(u16, f64)>,
+    /// `(Net Income - Operating Cash Flow) / Total Assets` : élevé, le résultat repose sur
+    /// des régularisations plutôt que sur des encaissements.
+    pub accruals_ratio: Vec<(u16, f64)>,
+}
+
+/// Ratios sans objet quand leur dénominateur est nul ou négatif : (ratio, numérateur, dénominateur).
+const SIGN_SENSITIVE: &[(&str, &str, &str)] = &[
+    ("ROE", "Net Income", "Total Equity"),
+    ("Payout Ratio", "Dividends Paid", "Net Income"),
+    ("Cash Conversion", "Free Cash Flow", "Net Income"),
+];
+
+/// Section `flags` : exercices à capitaux propres, résultat ou FCF négatifs, et ratios
+/// que ces signes rendent trompeurs.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct SignFlags {
+    /// Capitaux propres nuls ou négatifs.
+    pub negative_equity_years: Vec<u16>,
+    /// Résultat net négatif.
+    pub net_loss_years: Vec<u16>,
+    /// Free cash flow négatif.
+    pub negative_fcf_years: Vec<u16>,
+    /// Exercices où chaque ratio a été omis (`meaningless`) plutôt que calculé mécaniquement.
+    pub meaningless: BTreeMap<String, Vec<u16>>,
+    /// Exercices publiant impôt et résultat avant impôt mais sans `Effective Tax Rate`.
+    pub omitted_tax_rate: Vec<OmittedTaxRate>,
+}
+
+/// Exercice dont le taux d'imposition effectif est omis, et pourquoi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct OmittedTaxRate {
+    pub fiscal_year: u16,
+    pub reason: TaxRateOmission,
+}
+
+/// Pente annuelle (en fraction de marge par exercice, `0.005` = 0,5 point) au-delà de
+/// laquelle une marge est en expansion ou en contraction (`--margin-threshold`).
+pub const DEFAULT_MARGIN_THRESHOLD: f64 = 0.005;
+
+/// Ratios de `ratios` dont la section `margin_trends` qualifie l'évolution.
+const MARGIN_RATIOS: [&str; 3] = ["Gross Margin", "Operating Margin", "Net Margin"];
+
+/// Sens d'évolution d'une marge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendDirection {
+    Expanding,
+    Stable,
+    Contracting,
+}
+
+/// Tendance d'une marge sur les exercices disponibles (section `margin_trends`).
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct MarginTrend {
+    pub direction: TrendDirection,
+    /// Pente de la régression linéaire de la marge sur l'exercice, par an.
+    pub slope: f64,
+    pub first_year: u16,
+    pub last_year: u16,
+}
+
+/// Écart relatif toléré entre le ROE reconstitué par DuPont et le ROE direct.
+pub const DUPONT_TOLERANCE: f64 = 0.05;
+
+/// Décomposition DuPont d'un exercice : `ROE = marge nette × rotation de l'actif × levier`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct DupontYear {
+    pub fiscal_year: u16,
+    /// `Net Income / Revenue`.
+    pub net_margin: f64,
+    /// `Revenue / actif moyen`.
+    pub asset_turnover: f64,
+    /// `actif moyen / capitaux propres moyens`.
+    pub equity_multiplier: f64,
+    /// Produit des trois composantes.
+    pub roe: f64,
+    /// ROE direct, calculé indépendamment des composantes : celui de la section `ratios`
+    /// (`Net Income / Total Equity` de clôture), s'il est calculable.
+    pub reported_roe: Option<f64>,
+    /// Écart au ROE direct supérieur à `DUPONT_TOLERANCE` : forte variation des capitaux
+    /// propres sur l'exercice (augmentation de capital, rachats massifs) ou séries incohérentes.
+    pub diverges: bool,
+}
+
+/// Calcule les ratios financiers par exercice à partir des séries consolidées.
+///
+/// Une année sans numérateur, sans dénominateur ou avec un dénominateur nul est omise.
+pub fn compute_ratios(results: &HashMap<String, Vec<(u16, f64)>>) -> HashMap<String, Vec<(u16, f64)>> {
+    let definitions = [
+        ("Gross Margin", "Gross Profit", "Revenue"),
+        ("Net Margin", "Net Income", "Revenue"),
+        ("Operating Margin", "Operating Income (EBIT)", "Revenue"),
+        ("EBITDA Margin", "EBITDA", "Revenue"),
+        ("R&D Intensity", "R&D", "Revenue"),
+        // Coût de la dilution : SBC rapportée au chiffre d'affaires et au flux d'exploitation
+        ("SBC / Revenue", "SBC", "Revenue"),
+        ("SBC / OCF", "SBC", "Operating Cash Flow"),
+        ("ROA", "Net Income", "Total Assets"),
+        ("Current Ratio", "Total Current Assets", "Total Current Liabilities"),
+    ];
+
+    // Rotations : flux de l'exercice rapporté au solde moyen (ouverture + clôture) / 2
+    let turnovers = [
+        ("Asset Turnover", "Revenue", "Total Assets"),
+        ("Inventory Turnover", "Cost of Revenue", "Inventory"),
+    ];
+
+    // Capitaux propres négatifs : un ROE positif pourrait naître de deux signes négatifs
+    let roe = combine(results, "Net Income", "Total Equity", |ni, equity| (equity > 0.0).then(|| ni / equity));
+
+    let mut ratios = HashMap::new();
+    let computed = definitions
+        .into_iter()
+        .map(|(name, numerator, denominator)| (name, ratio(results, numerator, denominator)))
+        .chain([("ROE", roe)])
+        .chain(turnovers.into_iter().map(|(name, flow, balance)| (name, turnover(results, flow, balance))));
+    for (name, series) in computed {
+        if !series.is_empty() {
+            ratios.insert(name.to_string(), series);
+        }
+    }
+    ratios
+}
+
+/// Qualifie l'évolution des marges brute, opérationnelle et nette à partir des séries de
+/// `compute_ratios` : pente des moindres carrés, `stable` tant que sa valeur absolue ne
+/// dépasse pas `threshold`. Il faut au moins deux exercices.
+pub fn compute_margin_trends(ratios: &HashMap<String, Vec<(u16, f64)>>, threshold: f64) -> BTreeMap<String, MarginTrend> {
+    MARGIN_RATIOS
+        .iter()
+        .filter_map(|&name| {
+            let series = ratios.get(name).filter(|s| s.len() >= 2)?;
+            let slope = regression_slope(series)?;
+            let direction = match slope {
+                s if s > threshold => TrendDirection::Expanding,
+                s if s < -threshold => TrendDirection::Contracting,
+                _ => TrendDirection::Stable,
+            };
+            let years = series.iter().map(|&(year, _)| year);
+            let (first_year, last_year) = (years.clone().min()?, years.max()?);
+            Some((name.to_string(), MarginTrend { direction, slope, first_year, last_year }))
+        })
+        .collect()
+}
+
+/// Pente de la droite des moindres carrés `valeur = a + pente × exercice`.
+fn regression_slope(series: &[(u16, f64)]) -> Option<f64> {
+    let n = series.len() as f64;
+    let mean_x = series.iter().map(|&(x, _)| f64::from(x)).sum::<f64>() / n;
+    let mean_y = series.iter().map(|&(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = series.iter().map(|&(x, y)| (f64::from(x) - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = series.iter().map(|&(x, _)| (f64::from(x) - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// `flow / solde moyen` année par année. Le solde moyen est celui de l'ouverture (clôture de
+/// l'exercice précédent) et de la clôture quand les deux existent, sinon le solde de clôture.
+fn turnover(results: &HashMap<String, Vec<(u16, f64)>>, flow: &str, balance: &str) -> Vec<(u16, f64)> {
+    let balances: HashMap<u16, f64> = results.get(balance).into_iter().flatten().copied().collect();
+    results
+        .get(flow)
+        .into_iter()
+        .flatten()
+        .filter_map(|&(year, value)| {
+            let average = average_balance(&balances, year)?;
+            (average != 0.0).then(|| (year, value / average))
+        })
+        .collect()
+}
+
+/// Solde moyen d'un exercice : (ouverture + clôture) / 2, ou la clôture seule sans bilan d'ouverture.
+fn average_balance(balances: &HashMap<u16, f64>, year: u16) -> Option<f64> {
+    let closing = *balances.get(&year)?;
+    Some(year
+        .checked_sub(1)
+        .and_then(|prev| balances.get(&prev))
+        .map_or(closing, |opening| (opening + closing) / 2.0))
+}
+
+/// `numerator / denominator` année par année, en écartant les dénominateurs nuls.
+pub(crate) fn ratio(results: &HashMap<String, Vec<(u16, f64)>>, numerator: &str, denominator: &str) -> Vec<(u16, f64)> {
+    combine(results, numerator, denominator, |n, d| (d != 0.0).then(|| n / d))
+}
+
+/// Dette nette et ratio dette / capitaux propres de chaque exercice.
+pub fn compute_leverage(results: &HashMap<String, Vec<(u16, f64)>>) -> Leverage {
+    let cash: HashMap<u16, f64> = results.get("Cash & Equiv.").into_iter().flatten().copied().collect();
+    let net_debt = results
+        .get("Total Debt")
+        .into_iter()
+        .flatten()
+        .map(|&(year, debt)| (year, debt - cash.get(&year).copied().unwrap_or(0.0)))
+        .collect();
+
+    let negative_equity_years = results
+        .get("Total Equity")
+        .into_iter()
+        .flatten()
+        .filter(|&&(_, equity)| equity <= 0.0)
+        .map(|&(year, _)| year)
+        .collect();
+
+    let interest: HashMap<u16, f64> = results.get("Interest Expense").into_iter().flatten().copied().collect();
+    let interest_coverage = results
+        .get("Operating Income (EBIT)")
+        .into_iter()
+        .flatten()
+        .map(|&(year, ebit)| (year, interest.get(&year).filter(|&&i| i != 0.0).map(|i| ebit / i)))
+        .collect();
+
+    Leverage {
+        net_debt,
+        debt_to_equity: combine(results, "Total Debt", "Total Equity", |debt, equity| (equity > 0.0).then(|| debt / equity)),
+        negative_equity_years,
+        interest_coverage,
+    }
+}
+
+/// ROIC de chaque exercice, à partir du taux d'imposition effectif dérivé
+/// (`Effective Tax Rate`) : sans taux exploitable, le NOPAT de l'année n'est pas calculé.
+pub fn compute_roic(results: &HashMap<String, Vec<(u16, f64)>>) -> Roic {
+    let tax_rates: HashMap<u16, f64> = results.get("Effective Tax Rate").into_iter().flatten().copied().collect();
+    let nopat: Vec<(u16, f64)> = results
+        .get("Operating Income (EBIT)")
+        .into_iter()
+        .flatten()
+        .filter_map(|&(year, ebit)| tax_rates.get(&year).map(|rate| (year, ebit * (1.0 - rate))))
+        .collect();
+
+    let debt: HashMap<u16, f64> = results.get("Total Debt").into_iter().flatten().copied().collect();
+    let cash: HashMap<u16, f64> = results.get("Cash & Equiv.").into_iter().flatten().copied().collect();
+    let invested_capital: Vec<(u16, f64)> = results
+        .get("Total Equity")
+        .into_iter()
+        .flatten()
+        .map(|&(year, equity)| {
+            let debt = debt.get(&year).copied().unwrap_or(0.0);
+            (year, equity + debt - cash.get(&year).copied().unwrap_or(0.0))
+        })
+        .collect();
+
+    let capital: HashMap<u16, f64> = invested_capital.iter().copied().collect();
+    let roic = nopat
+        .iter()
+        .filter_map(|&(year, n)| capital.get(&year).filter(|&&c| c > 0.0).map(|c| (year, n / c)))
+        .collect();
+    Roic { nopat, invested_capital, roic }
+}
+
+/// Fonds de roulement de chaque exercice publiant actifs et passifs courants, et sa
+/// variation d'un exercice au suivant (le premier exercice, sans N-1, n'en a pas).
+pub fn compute_working_capital(results: &HashMap<String, Vec<(u16, f64)>>) -> WorkingCapital {
+    let working_capital =
+        combine(results, "Total Current Assets", "Total Current Liabilities", |assets, liabilities| Some(assets - liabilities));
+    let by_year: HashMap<u16, f64> = working_capital.iter().copied().collect();
+    let change = working_capital
+        .iter()
+        .filter_map(|&(year, wc)| by_year.get(&year.checked_sub(1)?).map(|prior| (year, wc - prior)))
+        .collect();
+    WorkingCapital { working_capital, change }
+}
+
+/// Exercices de signe défavorable et ratios omis en conséquence : ROE, payout et conversion
+/// en trésorerie ne sont pas calculés sur un dénominateur nul ou négatif.
+pub fn compute_sign_flags(results: &HashMap<String, Vec<(u16, f64)>>) -> SignFlags {
+    let years_where = |name: &str, invalid: fn(f64) -> bool| -> Vec<u16> {
+        results.get(name).into_iter().flatten().filter(|&&(_, v)| invalid(v)).map(|&(year, _)| year).collect()
+    };
+    let meaningless = SIGN_SENSITIVE
+        .iter()
+        .filter_map(|&(name, numerator, denominator)| {
+            let years: Vec<u16> = combine(results, numerator, denominator, |_, d| (d <= 0.0).then_some(0.0))
+                .into_iter()
+                .map(|(year, _)| year)
+                .collect();
+            (!years.is_empty()).then(|| (name.to_string(), years))
+        })
+        .collect();
+    let pretax: HashMap<u16, f64> = results.get("Pretax Income").into_iter().flatten().copied().collect();
+    let omitted_tax_rate = results
+        .get("Income Tax")
+        .into_iter()
+        .flatten()
+        .filter_map(|&(fiscal_year, tax)| {
+            let reason = effective_tax_rate(tax, *pretax.get(&fiscal_year)?).err()?;
+            Some(OmittedTaxRate { fiscal_year, reason })
+        })
+        .collect();
+    SignFlags {
+        negative_equity_years: years_where("Total Equity", |v| v <= 0.0),
+        net_loss_years: years_where("Net Income", |v| v < 0.0),
+        negative_fcf_years: years_where("Free Cash Flow", |v| v < 0.0),
+        meaningless,
+        omitted_tax_rate,
+    }
+}
+
+/// Conversion du résultat en trésorerie et ratio d'accruals de chaque exercice. Un résultat
+/// nul ou négatif rend la conversion sans objet (division par zéro, signe inversé).
+pub fn compute_earnings_quality(results: &HashMap<String, Vec<(u16, f64)>>) -> EarningsQuality {
+    let assets: HashMap<u16, f64> = results.get("Total Assets").into_iter().flatten().copied().collect();
+    let accruals = combine(results, "Net Income", "Operating Cash Flow", |income, cash| Some(income - cash));
+    let accruals_ratio = accruals
+        .into_iter()
+        .filter_map(|(year, accrual)| assets.get(&year).filter(|&&a| a > 0.0).map(|a| (year, accrual / a)))
+        .collect();
+    EarningsQuality {
+        cash_conversion: combine(results, "Free Cash Flow", "Net Income", |fcf, income| (income > 0.0).then(|| fcf / income)),
+        accruals_ratio,
+    }
+}
+
+/// Décomposition DuPont de chaque exercice disposant du résultat, du chiffre d'affaires,
+/// de l'actif et de capitaux propres moyens positifs.
+pub fn compute_dupont(results: &HashMap<String, Vec<(u16, f64)>>) -> Vec<DupontYear> {
+    let assets: HashMap<u16, f64> = results.get("Total Assets").into_iter().flatten().copied().collect();
+    let equity: HashMap<u16, f64> = results.get("Total Equity").into_iter().flatten().copied().collect();
+    let asset_turnover: HashMap<u16, f64> = turnover(results, "Revenue", "Total Assets").into_iter().collect();
+    let reported: HashMap<u16, f64> = ratio(results, "Net Income", "Total Equity").into_iter().collect();
+
+    ratio(results, "Net Income", "Revenue")
+        .into_iter()
+        .filter_map(|(year, net_margin)| {
+            let asset_turnover = *asset_turnover.get(&year)?;
+            let avg_equity = average_balance(&equity, year).filter(|&e| e > 0.0)?;
+            let equity_multiplier = average_balance(&assets, year)? / avg_equity;
+            let roe = net_margin * asset_turnover * equity_multiplier;
+            let reported_roe = reported.get(&year).copied();
+            let diverges = reported_roe.is_some_and(|r| (roe - r).abs() > DUPONT_TOLERANCE * r.abs());
+            Some(DupontYear { fiscal_year: year, net_margin, asset_turnover, equity_multiplier, roe, reported_roe, diverges })
+        })
+        .collect()
+}
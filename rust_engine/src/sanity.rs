@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::extract::DataQuality;
+use crate::peers::median;
+
+/// Écart maximal, en multiple de la médiane de la série, au-delà duquel une valeur est suspecte
+/// (`--outlier-factor`), dans un sens comme dans l'autre. Une croissance même très forte reste
+/// en deçà ; un fait mal étiqueté (BPA publié en cents, montant en milliers au lieu d'unités
+/// ou l'inverse) le dépasse largement.
+pub const DEFAULT_OUTLIER_FACTOR: f64 = 100.0;
+
+/// Nombre minimal d'exercices pour qu'une médiane serve de référence.
+pub const OUTLIER_MIN_YEARS: usize = 3;
+
+/// Valeur extraite jugée aberrante au regard du reste de la série.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Outlier {
+    pub fiscal_year: u16,
+    pub value: f64,
+    /// Médiane de la série, toutes années confondues.
+    pub median: f64,
+    /// Valeur retirée de la série (`--strict`) ; sinon seulement signalée.
+    pub dropped: bool,
+}
+
+/// Signale dans `quality` les valeurs dont la valeur absolue dépasse `factor` fois celle de la
+/// médiane de leur série, ou lui est inférieure d'autant, et les retire des séries si `drop`
+/// est vrai.
+///
+/// Seules les métriques publiées (présentes dans `quality`) sont contrôlées, avant le calcul
+/// des dérivées : une valeur retirée ne contamine donc pas les ratios. Une série de moins de
+/// `OUTLIER_MIN_YEARS` exercices ou de médiane nulle n'a pas de référence et n'est pas contrôlée.
+pub fn check_outliers(financials: &mut HashMap<String, Vec<(u16, f64)>>, quality: &mut DataQuality, factor: f64, drop: bool) {
+    for (name, series) in financials.iter_mut() {
+        let Some(metric_quality) = quality.get_mut(name) else { continue };
+        if series.len() < OUTLIER_MIN_YEARS {
+            continue;
+        }
+        let mut sorted: Vec<f64> = series.iter().map(|&(_, v)| v).collect();
+        sorted.sort_by(f64::total_cmp);
+        let median = median(&sorted);
+        if median == 0.0 {
+            continue;
+        }
+        let outliers: Vec<Outlier> = series
+            .iter()
+            .filter(|&&(_, value)| value.abs() > factor * median.abs() || value.abs() < median.abs() / factor)
+            .map(|&(fiscal_year, value)| Outlier { fiscal_year, value, median, dropped: drop })
+            .collect();
+        if drop {
+            series.retain(|&(year, _)| !outliers.iter().any(|o| o.fiscal_year == year));
+        }
+        metric_quality.outliers = outliers;
+    }
+}
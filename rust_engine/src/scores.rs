@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::models::CompanyFinancials;
+
+/// Critère d'un score composite, évalué sur l'exercice courant.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Criterion {
+    pub name: &'static str,
+    pub passed: bool,
+}
+
+/// F-score de Piotroski et détail des neuf critères.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Piotroski {
+    /// Exercice évalué (comparé à l'exercice précédent).
+    pub fiscal_year: u16,
+    pub score: u8,
+    pub criteria: Vec<Criterion>,
+}
+
+/// F-score de Piotroski (0-9) sur les deux derniers exercices.
+pub fn piotroski_fscore(data: &CompanyFinancials) -> Option<u8> {
+    piotroski(data).map(|p| p.score)
+}
+
+/// Évalue les neuf critères de Piotroski entre le dernier exercice disposant d'un résultat net
+/// et le précédent. Les ratios d'actif (ROA, rotation) utilisent l'actif de fin d'exercice.
+/// L'absence totale de dette long terme compte comme une dette nulle (critère rempli) ; toute autre donnée
+/// manquante sur l'un des deux exercices donne `None`.
+pub fn piotroski(data: &CompanyFinancials) -> Option<Piotroski> {
+    let results = &data.financials;
+    let year = results.get("Net Income")?.iter().map(|&(y, _)| y).max()?;
+    let prev = year.checked_sub(1)?;
+    let v = |metric: &str, y: u16| value(results, metric, y);
+
+    let (ni, ni_prev) = (v("Net Income", year)?, v("Net Income", prev)?);
+    let ocf = v("Operating Cash Flow", year)?;
+    let (assets, assets_prev) = (v("Total Assets", year)?, v("Total Assets", prev)?);
+    let current_ratio = |y| Some(v("Total Current Assets", y)? / v("Total Current Liabilities", y)?);
+    let debt = |y| if results.contains_key("Long Term Debt") { v("Long Term Debt", y) } else { Some(0.0) };
+    let (shares, shares_prev) = (v("Shares Outstanding", year)?, v("Shares Outstanding", prev)?);
+    let gross_margin = |y| Some(v("Gross Profit", y)? / v("Revenue", y)?);
+    let turnover = |y, a: f64| Some(v("Revenue", y)? / a);
+
+    let criteria = vec![
+        Criterion { name: "Positive Net Income", passed: ni > 0.0 },
+        Criterion { name: "Positive Operating Cash Flow", passed: ocf > 0.0 },
+        Criterion { name: "Cash Flow > Net Income", passed: ocf > ni },
+        Criterion { name: "Rising ROA", passed: ni / assets > ni_prev / assets_prev },
+        Criterion { name: "Rising Current Ratio", passed: current_ratio(year)? > current_ratio(prev)? },
+        Criterion { name: "Lower Long Term Debt", passed: debt(year)? < debt(prev)? || debt(year)? == 0.0 },
+        Criterion { name: "No Dilution", passed: shares <= shares_prev },
+        Criterion { name: "Rising Gross Margin", passed: gross_margin(year)? > gross_margin(prev)? },
+        Criterion { name: "Rising Asset Turnover", passed: turnover(year, assets)? > turnover(prev, assets_prev)? },
+    ];
+    let score = criteria.iter().filter(|c| c.passed).count() as u8;
+    Some(Piotroski { fiscal_year: year, score, criteria })
+}
+
+/// Z-score d'Altman (modèle d'origine, 1968) du dernier exercice :
+/// `1,2·BFR/A + 1,4·RN/A + 3,3·EBIT/A + 0,6·CP/D + 1,0·CA/A` où A est l'actif total,
+/// BFR le fonds de roulement, RN les réserves (bénéfices non distribués) et D le passif total.
+/// Les capitaux propres comptables remplacent la capitalisation boursière, faute de cours.
+/// `None` si l'un des postes manque pour cet exercice.
+pub fn altman_z(data: &CompanyFinancials) -> Option<f64> {
+    let results = &data.financials;
+    let year = results.get("Total Assets")?.iter().map(|&(y, _)| y).max()?;
+    let v = |metric: &str| value(results, metric, year);
+
+    let assets = v("Total Assets")?;
+    let liabilities = v("Total Liabilities")?;
+    if assets == 0.0 || liabilities == 0.0 { return None; }
+    let working_capital = v("Total Current Assets")? - v("Total Current Liabilities")?;
+
+    Some(
+        1.2 * working_capital / assets
+            + 1.4 * v("Retained Earnings")? / assets
+            + 3.3 * v("Operating Income (EBIT)")? / assets
+            + 0.6 * v("Total Equity")? / liabilities
+            + v("Revenue")? / assets,
+    )
+}
+
+/// Zone d'interprétation du Z-score : `safe` (> 2,99), `grey` (1,81-2,99), `distress` (< 1,81).
+pub fn altman_zone(z: f64) -> &'static str {
+    if z > 2.99 {
+        "safe"
+    } else if z >= 1.81 {
+        "grey"
+    } else {
+        "distress"
+    }
+}
+
+/// Valeur d'une métrique pour un exercice donné.
+fn value(results: &HashMap<String, Vec<(u16, f64)>>, metric: &str, year: u16) -> Option<f64> {
+    results.get(metric)?.iter().find(|&&(y, _)| y == year).map(|&(_, v)| v)
+}
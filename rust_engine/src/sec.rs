@@ -0,0 +1,324 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde_json::Value;
+use tracing::{debug, error, instrument, warn};
+
+use crate::cache::Cache;
+use crate::error::{EngineError, Result};
+use crate::http::HttpClient;
+use crate::models::{CompanyConcept, CompanyFacts, FactData, FactsContainer, TickerEntry};
+
+/// Hôte des fichiers SEC (`company_tickers.json`).
+pub const DEFAULT_FILES_URL: &str = "https://www.sec.gov";
+
+/// Hôte des API XBRL (`companyfacts`, `frames`).
+pub const DEFAULT_DATA_URL: &str = "https://data.sec.gov";
+
+/// Remplace l'hôte des fichiers et, sauf `SEC_DATA_BASE_URL`, celui des API
+/// (un proxy ou un serveur de test sert généralement les deux).
+pub const BASE_URL_ENV: &str = "SEC_BASE_URL";
+
+/// Remplace uniquement l'hôte des API XBRL.
+pub const DATA_BASE_URL_ENV: &str = "SEC_DATA_BASE_URL";
+
+/// Accès aux endpoints SEC : toutes les requêtes passent par ses méthodes, et donc par
+/// le limiteur de débit et la politique de retry du `HttpClient` sous-jacent.
+#[derive(Debug)]
+pub struct SecClient {
+    http: HttpClient,
+    files_base: String,
+    data_base: String,
+}
+
+impl SecClient {
+    /// Client pointant vers les hôtes donnés (URL sans `/` final).
+    pub fn new(http: HttpClient, files_base: &str, data_base: &str) -> Self {
+        SecClient {
+            http,
+            files_base: files_base.trim_end_matches('/').to_string(),
+            data_base: data_base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Hôtes réels de la SEC, sauf surcharge par `SEC_BASE_URL` / `SEC_DATA_BASE_URL`.
+    pub fn from_env(http: HttpClient) -> Self {
+        let var = |name| env::var(name).ok().filter(|v: &String| !v.trim().is_empty());
+        let files = var(BASE_URL_ENV);
+        let data = var(DATA_BASE_URL_ENV).or_else(|| files.clone());
+        SecClient::new(http, files.as_deref().unwrap_or(DEFAULT_FILES_URL), data.as_deref().unwrap_or(DEFAULT_DATA_URL))
+    }
+
+    pub fn http(&self) -> &HttpClient {
+        &self.http
+    }
+
+    /// URL complète d'un chemin de l'hôte des fichiers (`/files/...`).
+    pub fn files_url(&self, path: &str) -> String {
+        format!("{}{}", self.files_base, path)
+    }
+
+    /// URL complète d'un chemin de l'hôte des API (`/api/xbrl/...`).
+    pub fn data_url(&self, path: &str) -> String {
+        format!("{}{}", self.data_base, path)
+    }
+
+    /// Télécharge le mapping ticker -> CIK (`company_tickers.json`).
+    /// À appeler une seule fois par exécution, puis à réutiliser pour chaque ticker.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn fetch_mapping(&self) -> Result<Vec<TickerEntry>> {
+        let url_mapping = self.files_url("/files/company_tickers.json");
+        let body = self.http.fetch_with_retry(&url_mapping).await?.body;
+        let entries = parse_mapping(&body)?;
+        debug!(entries = entries.len(), "mapping téléchargé");
+        Ok(entries)
+    }
+
+    /// Télécharge le `companyfacts` d'un CIK. Si une copie est en cache, on envoie
+    /// `If-None-Match` / `If-Modified-Since` et on la réutilise sur un 304.
+    pub async fn fetch_facts(&self, cache: Option<&Cache>, cik_padded: &str) -> Result<CompanyFacts> {
+        self.fetch_facts_retaining(cache, cik_padded, None).await
+    }
+
+    /// Comme `fetch_facts`, en ne gardant des taxonomies financières que les concepts de
+    /// `retain` (voir `parse_facts_retaining`). Le cache conserve le document complet.
+    #[instrument(level = "debug", skip(self, cache, retain))]
+    pub async fn fetch_facts_retaining(
+        &self,
+        cache: Option<&Cache>,
+        cik_padded: &str,
+        retain: Option<&RetainedConcepts>,
+    ) -> Result<CompanyFacts> {
+        let parse = |body: &[u8]| match retain {
+            Some(retain) => parse_facts_retaining(body, retain),
+            None => parse_facts(body),
+        };
+        let url_facts = self.data_url(&format!("/api/xbrl/companyfacts/CIK{}.json", cik_padded));
+        let cached = cache.and_then(|c| c.load_facts(cik_padded));
+
+        let mut headers = HeaderMap::new();
+        if let Some((_, meta)) = &cached {
+            if let Some(etag) = meta.etag.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(date) = meta.last_modified.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(IF_MODIFIED_SINCE, date);
+            }
+        }
+
+        let resp = self.http.fetch_with_headers(&url_facts, headers).await?;
+        if resp.status == StatusCode::NOT_MODIFIED {
+            if let Some((body, _)) = cached {
+                debug!(bytes = body.len(), "304 : facts repris du cache");
+                return Ok(parse(&body)?);
+            }
+        }
+
+        let etag = header_string(&resp.headers, ETAG);
+        let last_modified = header_string(&resp.headers, LAST_MODIFIED);
+        let body = resp.body;
+        debug!(bytes = body.len(), "facts téléchargés");
+        let facts = parse(&body)?;
+        // Une réponse illisible n'est pas mise en cache : elle serait resservie sur un 304
+        if let Some(cache) = cache {
+            cache.store_facts(cik_padded, &body, etag, last_modified);
+        }
+        Ok(facts)
+    }
+
+    /// Télécharge les faits d'un seul concept (`companyconcept`), sans cache.
+    /// `None` si le déclarant ne publie pas ce concept (404).
+    #[instrument(level = "debug", skip(self))]
+    pub async fn fetch_concept(&self, cik_padded: &str, taxonomy: &str, concept: &str) -> Result<Option<CompanyConcept>> {
+        let url = self.data_url(&format!("/api/xbrl/companyconcept/CIK{}/{}/{}.json", cik_padded, taxonomy, concept));
+        match self.http.fetch_with_retry(&url).await {
+            Ok(resp) => Ok(Some(resp.json()?)),
+            Err(EngineError::Http(e)) if e.status() == Some(StatusCode::NOT_FOUND) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Analyse `company_tickers.json`. Formes acceptées : objet indexé (`{"0": {...}}`, forme
+/// actuelle), tableau d'entrées, ou table `{"fields": [...], "data": [[...]]}` (forme de
+/// `company_tickers_exchange.json`). Les entrées illisibles sont ignorées avec un
+/// avertissement ; un document sans aucune entrée lisible est une erreur.
+pub fn parse_mapping(body: &[u8]) -> Result<Vec<TickerEntry>> {
+    let document: Value = serde_json::from_slice(body)?;
+    let raw: Vec<Value> = match document {
+        Value::Array(items) => items,
+        Value::Object(map) => match (map.get("fields"), map.get("data")) {
+            (Some(Value::Array(fields)), Some(Value::Array(rows))) => rows.iter().filter_map(|row| table_entry(fields, row)).collect(),
+            _ => map.into_iter().map(|(_, entry)| entry).collect(),
+        },
+        _ => Vec::new(),
+    };
+    let total = raw.len();
+    let entries: Vec<TickerEntry> = raw.into_iter().filter_map(|entry| serde_json::from_value(entry).ok()).collect();
+    if entries.is_empty() {
+        error!(total, "company_tickers.json : aucune entrée lisible (format modifié par la SEC ?)");
+        return Err(EngineError::UnrecognizedMapping);
+    }
+    if entries.len() < total {
+        warn!(skipped = total - entries.len(), "entrées illisibles ignorées dans company_tickers.json");
+    }
+    Ok(entries)
+}
+
+/// Ligne d'une table `{"fields", "data"}` convertie en objet `{champ: valeur}`.
+fn table_entry(fields: &[Value], row: &Value) -> Option<Value> {
+    let values = row.as_array()?;
+    let object = fields.iter().zip(values).filter_map(|(field, value)| Some((field.as_str()?.to_string(), value.clone())));
+    Some(Value::Object(object.collect()))
+}
+
+/// Désérialise un `companyfacts`. Seul le premier document JSON est lu : des octets
+/// parasites après sa fin (réponse mal terminée) ne font pas perdre les faits déjà analysés.
+/// En cas d'erreur, la position (ligne, colonne) est journalisée.
+pub fn parse_facts(body: &[u8]) -> serde_json::Result<CompanyFacts> {
+    let mut stream = serde_json::Deserializer::from_slice(body).into_iter::<CompanyFacts>();
+    match stream.next() {
+        Some(Ok(facts)) => {
+            if stream.byte_offset() < body.len() && !body[stream.byte_offset()..].iter().all(u8::is_ascii_whitespace) {
+                warn!(offset = stream.byte_offset(), "octets ignorés après la fin du companyfacts");
+            }
+            Ok(facts)
+        }
+        Some(Err(e)) => {
+            warn!(line = e.line(), column = e.column(), "companyfacts illisible : {}", e);
+            Err(e)
+        }
+        None => serde_json::from_slice(body),
+    }
+}
+
+/// Concepts us-gaap / ifrs-full conservés à l'analyse d'un `companyfacts`.
+pub type RetainedConcepts = HashSet<&'static str>;
+
+/// Comme `parse_facts`, en ne matérialisant des taxonomies financières que les concepts de
+/// `retain` : les autres sont parcourus sans allocation. Les faits `dei` sont tous gardés.
+///
+/// Un gros déclarant publie des milliers de concepts pour quelques dizaines extraits :
+/// l'arbre désérialisé, bien plus lourd que le JSON brut, est réduit d'autant
+/// (voir `benches/parse_facts.rs`).
+pub fn parse_facts_retaining(body: &[u8], retain: &RetainedConcepts) -> serde_json::Result<CompanyFacts> {
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    match CompanyFactsSeed(retain).deserialize(&mut deserializer) {
+        Ok(facts) => {
+            if deserializer.end().is_err() {
+                warn!("octets ignorés après la fin du companyfacts");
+            }
+            Ok(facts)
+        }
+        Err(e) => {
+            warn!(line = e.line(), column = e.column(), "companyfacts illisible : {}", e);
+            Err(e)
+        }
+    }
+}
+
+/// Document `companyfacts` complet, taxonomies filtrées.
+struct CompanyFactsSeed<'a>(&'a RetainedConcepts);
+
+impl<'de> DeserializeSeed<'de> for CompanyFactsSeed<'_> {
+    type Value = CompanyFacts;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<CompanyFacts, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CompanyFactsSeed<'_> {
+    type Value = CompanyFacts;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("un objet companyfacts")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<CompanyFacts, A::Error> {
+        let (mut cik, mut entity_name, mut facts) = (None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "cik" => cik = map.next_value::<Option<u64>>()?,
+                "entityName" => entity_name = Some(map.next_value::<String>()?),
+                "facts" => facts = Some(map.next_value_seed(FactsSeed(self.0))?),
+                _ => { map.next_value::<IgnoredAny>()?; }
+            }
+        }
+        Ok(CompanyFacts {
+            cik,
+            entity_name: entity_name.ok_or_else(|| de::Error::missing_field("entityName"))?,
+            facts: facts.ok_or_else(|| de::Error::missing_field("facts"))?,
+        })
+    }
+}
+
+/// Objet `facts` : taxonomies financières filtrées, `dei` entier, les autres ignorées.
+struct FactsSeed<'a>(&'a RetainedConcepts);
+
+impl<'de> DeserializeSeed<'de> for FactsSeed<'_> {
+    type Value = FactsContainer;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<FactsContainer, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FactsSeed<'_> {
+    type Value = FactsContainer;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("un objet de taxonomies")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<FactsContainer, A::Error> {
+        let mut container = FactsContainer { us_gaap: None, ifrs_full: None, dei: None };
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "us-gaap" => container.us_gaap = Some(map.next_value_seed(ConceptsSeed(self.0))?),
+                "ifrs-full" => container.ifrs_full = Some(map.next_value_seed(ConceptsSeed(self.0))?),
+                "dei" => container.dei = map.next_value()?,
+                _ => { map.next_value::<IgnoredAny>()?; }
+            }
+        }
+        Ok(container)
+    }
+}
+
+/// Concepts d'une taxonomie : seuls ceux de `retain` sont désérialisés.
+struct ConceptsSeed<'a>(&'a RetainedConcepts);
+
+impl<'de> DeserializeSeed<'de> for ConceptsSeed<'_> {
+    type Value = HashMap<String, FactData>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ConceptsSeed<'_> {
+    type Value = HashMap<String, FactData>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("un objet de concepts")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Self::Value, A::Error> {
+        let mut concepts = HashMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if self.0.contains(key.as_str()) {
+                concepts.insert(key, map.next_value::<FactData>()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(concepts)
+    }
+}
+
+fn header_string(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(str::to_string)
+}
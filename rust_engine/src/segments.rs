@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::extract::{extract_financials, MetricDef, UnitKind};
+use crate::models::FactData;
+
+/// Concept dont on cherche la ventilation par segment.
+pub const SEGMENT_CONCEPT: &str = "RevenueFromContractWithCustomerExcludingAssessedTax";
+
+/// Motif d'absence de ventilation, repris dans la sortie.
+///
+/// L'API `companyfacts` ne diffuse que les faits non dimensionnels : les valeurs rattachées à
+/// un membre d'axe (`StatementBusinessSegmentsAxis`, `srt:StatementGeographicalAxis`...) n'y
+/// figurent pas, seul le total consolidé est publié. La ventilation impose de lire l'instance
+/// XBRL du dépôt, hors du périmètre de ce moteur.
+pub const SEGMENTS_UNAVAILABLE: &str =
+    "companyfacts ne contient que des faits non dimensionnels : aucune ventilation par segment n'est publiée";
+
+/// Section `segments` (`--segments`) : ce que `companyfacts` expose pour le concept de chiffre
+/// d'affaires ventilable.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct SegmentReport {
+    pub concept: &'static str,
+    /// Chiffre d'affaires consolidé par exercice, seule donnée disponible pour le concept.
+    pub consolidated: Vec<(u16, f64)>,
+    pub note: &'static str,
+}
+
+/// Rapport de segmentation à partir des faits d'une taxonomie.
+pub fn segment_report(facts: &HashMap<String, FactData>) -> SegmentReport {
+    let def = MetricDef::flow(SEGMENT_CONCEPT, &[SEGMENT_CONCEPT], UnitKind::Monetary);
+    let consolidated = extract_financials(facts, &[def]).remove(SEGMENT_CONCEPT).unwrap_or_default();
+    SegmentReport { concept: SEGMENT_CONCEPT, consolidated, note: SEGMENTS_UNAVAILABLE }
+}
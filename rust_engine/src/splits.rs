@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Hausse (ou baisse) minimale d'une année sur l'autre du nombre d'actions pour soupçonner
+/// une division (ou un regroupement) : x1,5. Rachats et émissions courantes restent bien en deçà.
+pub const SPLIT_MIN_JUMP: f64 = 1.5;
+
+/// Écart relatif toléré entre le saut observé et un ratio de division usuel
+/// (les rachats de l'année faussent légèrement le rapport).
+pub const SPLIT_TOLERANCE: f64 = 0.10;
+
+/// Ratios de division reconnus : entiers 2 à 20 et fractions simples (3:2, 5:2).
+const SPLIT_RATIOS: &[f64] = &[
+    1.5, 2.0, 2.5, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0,
+    11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 19.0, 20.0,
+];
+
+/// Métriques par action, divisées par le ratio de division.
+const PER_SHARE_METRICS: &[&str] = &["EPS Diluted", "Dividends Per Share"];
+
+/// Division détectée : `ratio` actions nouvelles pour une ancienne (< 1 pour un regroupement),
+/// effective entre `fiscal_year - 1` et `fiscal_year` sur le nombre d'actions.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+pub struct SplitEvent {
+    pub fiscal_year: u16,
+    pub ratio: f64,
+}
+
+/// Détecte les divisions d'actions sur `Shares Outstanding` et ramène l'historique à la base
+/// la plus récente : actions multipliées, métriques par action divisées par le ratio.
+///
+/// Heuristique : un saut d'au moins `SPLIT_MIN_JUMP` (à la tolérance près) entre deux exercices consécutifs, proche
+/// (à `SPLIT_TOLERANCE` près) d'un ratio usuel. Les 10-K postérieurs à une division retraitent
+/// souvent les comparatifs : la rupture des séries par action peut donc se trouver quelques
+/// exercices avant celle du nombre d'actions. On l'y cherche, en retenant le saut le plus
+/// proche du ratio parmi les exercices antérieurs ou égaux à la division.
+pub fn adjust_splits(financials: &mut HashMap<String, Vec<(u16, f64)>>) -> Vec<SplitEvent> {
+    let Some(shares) = financials.get("Shares Outstanding") else { return Vec::new() };
+    let events: Vec<SplitEvent> = shares
+        .windows(2)
+        .filter(|w| w[1].0 == w[0].0 + 1 && w[0].1 > 0.0)
+        .filter_map(|w| split_ratio(w[1].1 / w[0].1).map(|ratio| SplitEvent { fiscal_year: w[1].0, ratio }))
+        .collect();
+
+    for event in &events {
+        back_adjust(financials, "Shares Outstanding", event.fiscal_year, event.ratio);
+        for &metric in PER_SHARE_METRICS {
+            let Some(series) = financials.get(metric) else { continue };
+            if let Some(year) = per_share_break(series, event) {
+                back_adjust(financials, metric, year, 1.0 / event.ratio);
+            }
+        }
+    }
+    events
+}
+
+/// Ratio usuel correspondant à un saut, s'il est assez marqué.
+fn split_ratio(jump: f64) -> Option<f64> {
+    let (magnitude, reverse) = if jump >= 1.0 { (jump, false) } else { (1.0 / jump, true) };
+    if !magnitude.is_finite() || magnitude < SPLIT_MIN_JUMP / (1.0 + SPLIT_TOLERANCE) { return None; }
+    let ratio = SPLIT_RATIOS
+        .iter()
+        .copied()
+        .filter(|&r| (magnitude / r - 1.0).abs() <= SPLIT_TOLERANCE)
+        .min_by(|a, b| (magnitude / a - 1.0).abs().total_cmp(&(magnitude / b - 1.0).abs()))?;
+    Some(if reverse { 1.0 / ratio } else { ratio })
+}
+
+/// Premier exercice après la rupture d'une série par action : le saut (précédent / courant)
+/// le plus proche du ratio, pour un exercice au plus égal à celui de la division.
+fn per_share_break(series: &[(u16, f64)], event: &SplitEvent) -> Option<u16> {
+    series
+        .windows(2)
+        .filter(|w| w[1].0 <= event.fiscal_year && w[1].1 != 0.0 && w[0].1.signum() == w[1].1.signum())
+        .map(|w| (w[1].0, ((w[0].1 / w[1].1).ln() - event.ratio.ln()).abs()))
+        .filter(|&(_, distance)| distance < event.ratio.ln().abs() / 2.0)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(year, _)| year)
+}
+
+/// Multiplie par `factor` les valeurs antérieures à `from_year`.
+fn back_adjust(financials: &mut HashMap<String, Vec<(u16, f64)>>, metric: &str, from_year: u16, factor: f64) {
+    if let Some(series) = financials.get_mut(metric) {
+        for (year, value) in series.iter_mut() {
+            if *year < from_year { *value *= factor; }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+use rusqlite::{params, Connection};
+
+use crate::error::Result;
+use crate::models::CompanyFinancials;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS financials (
+    ticker      TEXT    NOT NULL,
+    cik         INTEGER NOT NULL,
+    name        TEXT    NOT NULL,
+    metric      TEXT    NOT NULL,
+    fiscal_year INTEGER NOT NULL,
+    value       REAL    NOT NULL,
+    PRIMARY KEY (ticker, metric, fiscal_year)
+)";
+
+/// Enregistre les séries dans une base SQLite (créée au besoin), une ligne par
+/// (ticker, métrique, exercice). Relancer un ticker remplace ses lignes existantes
+/// au lieu de les dupliquer. Renvoie le nombre de lignes écrites.
+pub fn export_sqlite(path: &Path, companies: &[CompanyFinancials]) -> Result<usize> {
+    let mut conn = Connection::open(path)?;
+    conn.execute(SCHEMA, [])?;
+
+    let tx = conn.transaction()?;
+    let mut written = 0;
+    {
+        let mut upsert = tx.prepare(
+            "INSERT INTO financials (ticker, cik, name, metric, fiscal_year, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (ticker, metric, fiscal_year)
+             DO UPDATE SET cik = excluded.cik, name = excluded.name, value = excluded.value",
+        )?;
+        for company in companies {
+            for (metric, series) in &company.financials {
+                for &(year, value) in series {
+                    upsert.execute(params![company.ticker, company.cik as i64, company.name, metric, year, value])?;
+                    written += 1;
+                }
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(written)
+}
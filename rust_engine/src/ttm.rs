@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::extract::MetricDef;
+use crate::models::PeriodValue;
+
+/// Calcule les chiffres sur douze mois glissants (TTM) de chaque métrique.
+///
+/// - Flux : somme des quatre derniers trimestres consécutifs, jusqu'au dernier trimestre publié.
+///   Le T4 n'ayant pas de 10-Q, il est reconstitué en `FY - (T1 + T2 + T3)` quand c'est possible.
+/// - Stocks : dernier snapshot trimestriel.
+/// - Si aucun trimestre n'est plus récent que le dernier exercice complet (ou si la fenêtre de
+///   quatre trimestres est incomplète), on retombe sur la valeur annuelle, notée `AAAA-FY`.
+pub fn compute_ttm(
+    annual: &HashMap<String, Vec<(u16, f64)>>,
+    quarterly: &HashMap<String, Vec<PeriodValue>>,
+    config: &[MetricDef],
+) -> HashMap<String, PeriodValue> {
+    let mut results = HashMap::new();
+
+    for def in config {
+        let years = annual.get(def.name).map(Vec::as_slice).unwrap_or(&[]);
+        let mut quarters: HashMap<(u16, u8), f64> = quarterly
+            .get(def.name)
+            .into_iter()
+            .flatten()
+            .filter_map(|pv| parse_quarter(&pv.period).map(|k| (k, pv.value)))
+            .collect();
+
+        if !def.is_instant {
+            fill_fourth_quarters(&mut quarters, years);
+        }
+
+        let latest_annual = years.last().copied();
+        let latest_quarter = quarters.keys().max().copied();
+
+        let ttm = match (latest_quarter, latest_annual) {
+            // Un exercice complet au moins aussi récent que le dernier trimestre : on le prend tel quel
+            (Some((qy, _)), Some((ay, value))) if ay >= qy && !def.is_instant => Some(annual_value(ay, value)),
+            (Some(key), _) if def.is_instant => Some(PeriodValue { period: quarter_label(key), value: quarters[&key] }),
+            (Some(key), annual_fallback) => match trailing_sum(&quarters, key) {
+                Some(value) => Some(PeriodValue { period: quarter_label(key), value }),
+                None => annual_fallback.map(|(y, v)| annual_value(y, v)),
+            },
+            (None, Some((y, v))) => Some(annual_value(y, v)),
+            (None, None) => None,
+        };
+
+        if let Some(ttm) = ttm {
+            results.insert(def.name.to_string(), ttm);
+        }
+    }
+
+    results
+}
+
+/// Reconstitue les T4 manquants à partir de l'exercice complet et des trois premiers trimestres.
+fn fill_fourth_quarters(quarters: &mut HashMap<(u16, u8), f64>, years: &[(u16, f64)]) {
+    for &(year, total) in years {
+        if quarters.contains_key(&(year, 4)) { continue; }
+        let first_three: Option<f64> = (1..=3).map(|q| quarters.get(&(year, q)).copied()).sum();
+        if let Some(partial) = first_three {
+            quarters.insert((year, 4), total - partial);
+        }
+    }
+}
+
+/// Somme des quatre trimestres consécutifs se terminant à `last`.
+fn trailing_sum(quarters: &HashMap<(u16, u8), f64>, last: (u16, u8)) -> Option<f64> {
+    let mut key = last;
+    let mut total = 0.0;
+    for _ in 0..4 {
+        total += quarters.get(&key)?;
+        key = previous_quarter(key)?;
+    }
+    Some(total)
+}
+
+fn previous_quarter((year, q): (u16, u8)) -> Option<(u16, u8)> {
+    if q > 1 { Some((year, q - 1)) } else { year.checked_sub(1).map(|y| (y, 4)) }
+}
+
+/// `2023-Q2` -> (2023, 2)
+fn parse_quarter(label: &str) -> Option<(u16, u8)> {
+    let (year, q) = label.split_once("-Q")?;
+    Some((year.parse().ok()?, q.parse().ok()?))
+}
+
+fn quarter_label((year, q): (u16, u8)) -> String {
+    format!("{}-Q{}", year, q)
+}
+
+fn annual_value(year: u16, value: f64) -> PeriodValue {
+    PeriodValue { period: format!("{}-FY", year), value }
+}
@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::derive::combine;
+use crate::models::PeriodValue;
+
+/// Hypothèses du modèle DCF (taux exprimés en fraction : 0.05 = 5 %).
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
+pub struct DcfAssumptions {
+    /// Croissance annuelle du FCF pendant la période explicite.
+    pub growth: f64,
+    /// Taux d'actualisation (coût du capital exigé).
+    pub discount: f64,
+    /// Croissance perpétuelle au-delà de la période explicite (Gordon-Shapiro).
+    pub terminal_growth: f64,
+    /// Nombre d'années projetées.
+    pub years: u32,
+}
+
+impl Default for DcfAssumptions {
+    fn default() -> Self {
+        DcfAssumptions { growth: 0.05, discount: 0.10, terminal_growth: 0.025, years: 10 }
+    }
+}
+
+/// Résultat DCF restitué en JSON, avec les entrées et hypothèses utilisées.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct DcfValuation {
+    /// Valeur intrinsèque par action ; `null` si le calcul n'a pas de sens.
+    pub intrinsic_value_per_share: Option<f64>,
+    /// Exercice et montant du FCF servant de base à la projection.
+    pub base_year: u16,
+    pub base_fcf: f64,
+    pub shares: f64,
+    pub assumptions: DcfAssumptions,
+}
+
+/// Valeur intrinsèque par action par actualisation des flux de trésorerie disponibles.
+///
+/// Le dernier FCF de `fcf_history` est projeté sur `years` ans au taux `growth`, chaque flux
+/// étant actualisé au taux `discount`. On ajoute une valeur terminale de Gordon
+/// (`FCF_n × (1 + terminal_growth) / (discount - terminal_growth)`), elle aussi actualisée,
+/// puis on divise par `shares`. Renvoie `NaN` si l'historique est vide, si `shares <= 0`
+/// ou si `discount <= terminal_growth` (valeur terminale infinie).
+pub fn dcf(fcf_history: &[(u16, f64)], growth: f64, discount: f64, terminal_growth: f64, years: u32, shares: f64) -> f64 {
+    let Some(&(_, base)) = fcf_history.iter().max_by_key(|(year, _)| *year) else { return f64::NAN };
+    if shares <= 0.0 || discount <= terminal_growth { return f64::NAN; }
+
+    let mut fcf = base;
+    let mut present_value = 0.0;
+    for t in 1..=years {
+        fcf *= 1.0 + growth;
+        present_value += fcf / (1.0 + discount).powi(t as i32);
+    }
+    let terminal = fcf * (1.0 + terminal_growth) / (discount - terminal_growth);
+    present_value += terminal / (1.0 + discount).powi(years as i32);
+
+    present_value / shares
+}
+
+/// DCF à partir des séries extraites (`Free Cash Flow`, `Shares Outstanding` du dernier exercice).
+/// `None` si l'une des deux séries manque.
+pub fn dcf_valuation(results: &HashMap<String, Vec<(u16, f64)>>, assumptions: DcfAssumptions) -> Option<DcfValuation> {
+    let history = results.get("Free Cash Flow")?;
+    let &(base_year, base_fcf) = history.iter().max_by_key(|(year, _)| *year)?;
+    let (_, shares) = latest(results, "Shares Outstanding")?;
+
+    let value = dcf(history, assumptions.growth, assumptions.discount, assumptions.terminal_growth, assumptions.years, shares);
+    Some(DcfValuation {
+        intrinsic_value_per_share: value.is_finite().then_some(value),
+        base_year,
+        base_fcf,
+        shares,
+        assumptions,
+    })
+}
+
+/// Nombre de Graham : `sqrt(22.5 × BPA × actif net par action)`, plafond de juste valeur
+/// de Benjamin Graham (PER 15 × P/B 1,5). `None` si l'un des deux est négatif ou nul.
+pub fn graham_number(eps: f64, book_value_per_share: f64) -> Option<f64> {
+    (eps > 0.0 && book_value_per_share > 0.0).then(|| (22.5 * eps * book_value_per_share).sqrt())
+}
+
+/// Nombre de Graham restitué en JSON avec ses entrées.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct GrahamValuation {
+    pub fiscal_year: u16,
+    pub eps: f64,
+    pub book_value_per_share: f64,
+    /// `null` quand le BPA ou l'actif net est négatif.
+    pub graham_number: Option<f64>,
+}
+
+/// Nombre de Graham du dernier exercice disposant du BPA dilué, des capitaux propres
+/// et du nombre d'actions (actif net par action = `Total Equity / Shares Outstanding`).
+pub fn graham_valuation(results: &HashMap<String, Vec<(u16, f64)>>) -> Option<GrahamValuation> {
+    let bvps = combine(results, "Total Equity", "Shares Outstanding", |equity, shares| (shares > 0.0).then(|| equity / shares));
+    let eps = results.get("EPS Diluted")?;
+    let (fiscal_year, eps, book_value_per_share) = bvps
+        .iter()
+        .rev()
+        .find_map(|&(year, b)| eps.iter().find(|(y, _)| *y == year).map(|&(_, e)| (year, e, b)))?;
+
+    Some(GrahamValuation { fiscal_year, eps, book_value_per_share, graham_number: graham_number(eps, book_value_per_share) })
+}
+
+/// Valeur d'entreprise du dernier exercice : `capitalisation + dette totale - trésorerie`.
+///
+/// La capitalisation (`prix × actions`) n'existe qu'avec un cours (`--price`) ; sans lui,
+/// seules les composantes dette et trésorerie sont restituées.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EnterpriseValue {
+    pub fiscal_year: u16,
+    pub total_debt: f64,
+    pub cash: f64,
+    pub market_cap: Option<f64>,
+    pub enterprise_value: Option<f64>,
+    pub ev_to_ebit: Option<f64>,
+    pub ev_to_revenue: Option<f64>,
+}
+
+/// Valeur d'entreprise au dernier exercice publiant une dette totale (trésorerie absente = 0).
+pub fn enterprise_value(results: &HashMap<String, Vec<(u16, f64)>>, price: Option<f64>) -> Option<EnterpriseValue> {
+    let (fiscal_year, total_debt) = latest(results, "Total Debt")?;
+    let at = |metric: &str| results.get(metric)?.iter().find(|(y, _)| *y == fiscal_year).map(|&(_, v)| v);
+    let cash = at("Cash & Equiv.").unwrap_or(0.0);
+
+    let market_cap = price.zip(at("Shares Outstanding")).map(|(p, shares)| p * shares);
+    let ev = market_cap.map(|cap| cap + total_debt - cash);
+    let multiple = |metric: &str| ev.zip(at(metric)).and_then(|(ev, d)| (d != 0.0).then(|| ev / d));
+    Some(EnterpriseValue {
+        fiscal_year,
+        total_debt,
+        cash,
+        market_cap,
+        enterprise_value: ev,
+        ev_to_ebit: multiple("Operating Income (EBIT)"),
+        ev_to_revenue: multiple("Revenue"),
+    })
+}
+
+/// Métriques lissées par `--normalized-years`.
+pub const NORMALIZED_METRICS: &[&str] = &["Net Income", "Free Cash Flow", "EPS Diluted"];
+
+/// Valeur normalisée d'une métrique sur les derniers exercices publiés.
+#[derive(Debug, Clone, Serialize, JsonSchema, PartialEq)]
+pub struct NormalizedValue {
+    pub first_year: u16,
+    pub last_year: u16,
+    /// Exercices effectivement disponibles dans la fenêtre (moins que demandé s'il y a des trous).
+    pub years_used: usize,
+    /// Valeur retenue : la médiane amortit les charges ou profits exceptionnels.
+    pub median: f64,
+    pub mean: f64,
+}
+
+/// Section `normalized` : résultats lissés pour les entreprises cycliques (approche de Shiller).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Normalized {
+    /// Fenêtre demandée, en exercices.
+    pub years: u16,
+    pub metrics: BTreeMap<String, NormalizedValue>,
+    /// PER normalisé : `--price / médiane du BPA dilué`, s'il y a un cours et un BPA médian positif.
+    pub normalized_pe: Option<f64>,
+}
+
+/// Médiane et moyenne des `years` derniers exercices (comptés depuis le plus récent de chaque
+/// série) du résultat net, du FCF et du BPA dilué.
+pub fn normalized_earnings(results: &HashMap<String, Vec<(u16, f64)>>, years: u16, price: Option<f64>) -> Normalized {
+    let metrics: BTreeMap<String, NormalizedValue> = NORMALIZED_METRICS
+        .iter()
+        .filter_map(|&name| Some((name.to_string(), normalize(results.get(name)?, years)?)))
+        .collect();
+    let normalized_pe = price
+        .zip(metrics.get("EPS Diluted"))
+        .and_then(|(price, eps)| (eps.median > 0.0).then(|| price / eps.median));
+    Normalized { years, metrics, normalized_pe }
+}
+
+fn normalize(series: &[(u16, f64)], years: u16) -> Option<NormalizedValue> {
+    let &(last_year, _) = series.iter().max_by_key(|(year, _)| *year)?;
+    let mut values: Vec<(u16, f64)> = series.iter().copied().filter(|&(year, _)| u32::from(year) + u32::from(years) > u32::from(last_year)).collect();
+    values.sort_by_key(|&(year, _)| year);
+    let first_year = values.first()?.0;
+
+    let mut sorted: Vec<f64> = values.iter().map(|&(_, v)| v).collect();
+    sorted.sort_by(f64::total_cmp);
+    let n = sorted.len();
+    let median = if n % 2 == 1 { sorted[n / 2] } else { (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0 };
+    let mean = sorted.iter().sum::<f64>() / n as f64;
+    Some(NormalizedValue { first_year, last_year, years_used: n, median, mean })
+}
+
+/// Section `multiples` : cours (`--quote` ou `--price`) rapporté aux derniers fondamentaux.
+/// Chaque multiple vaut `null` si son dénominateur manque ou n'est pas positif.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Multiples {
+    pub price: f64,
+    /// BPA retenu pour le P/E : `ttm` (douze mois glissants, avec `--ttm`) ou `annual`.
+    pub eps_basis: Option<&'static str>,
+    pub pe: Option<f64>,
+    /// Cours / actif net par action du dernier exercice.
+    pub pb: Option<f64>,
+    /// Capitalisation / FCF du dernier exercice.
+    pub p_fcf: Option<f64>,
+    /// Dividende par action (à défaut dividendes versés / capitalisation) rapporté au cours.
+    pub dividend_yield: Option<f64>,
+}
+
+/// Multiples de valorisation au cours `price`.
+pub fn multiples(results: &HashMap<String, Vec<(u16, f64)>>, ttm: Option<&HashMap<String, PeriodValue>>, price: f64) -> Multiples {
+    let per = |denominator: Option<f64>| denominator.filter(|&d| d > 0.0).map(|d| price / d);
+    let (eps_basis, eps) = match ttm.and_then(|t| t.get("EPS Diluted")) {
+        Some(eps) => (Some("ttm"), Some(eps.value)),
+        None => match latest(results, "EPS Diluted") {
+            Some((_, eps)) => (Some("annual"), Some(eps)),
+            None => (None, None),
+        },
+    };
+    let market_cap = latest(results, "Shares Outstanding").map(|(_, shares)| price * shares);
+    let p_fcf = market_cap
+        .zip(latest(results, "Free Cash Flow"))
+        .and_then(|(cap, (_, fcf))| (fcf > 0.0).then(|| cap / fcf));
+    let dividend_yield = match latest(results, "Dividends Per Share") {
+        Some((_, dps)) => Some(dps / price),
+        None => market_cap
+            .zip(latest(results, "Dividends Paid"))
+            .and_then(|(cap, (_, paid))| (cap > 0.0).then(|| paid / cap)),
+    };
+    Multiples {
+        price,
+        eps_basis,
+        pe: per(eps),
+        pb: per(latest(results, "Book Value Per Share").map(|(_, bvps)| bvps)),
+        p_fcf,
+        dividend_yield,
+    }
+}
+
+/// Dernière valeur (exercice le plus récent) d'une métrique.
+pub fn latest(results: &HashMap<String, Vec<(u16, f64)>>, metric: &str) -> Option<(u16, f64)> {
+    results.get(metric)?.iter().copied().max_by_key(|(year, _)| *year)
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::fs;
+
+use edgar_fetcher::cache::{CachedFacts, Cache};
+use edgar_fetcher::diff::SnapshotOptions;
+
+#[test]
+fn cached_facts_are_listed_by_cik_with_size() {
+    let dir = std::env::temp_dir().join(format!("edgar_fetcher_cache_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir);
+    cache.store_facts("0000789019", b"{\"facts\":{}}", None, None);
+    cache.store_facts("0000320193", b"{}", Some("\"v1\"".to_string()), None);
+    // Corps sans métadonnées (écriture interrompue) : ignoré
+    fs::write(dir.join("facts").join("CIK0000000001.json"), b"{").unwrap();
+
+    let listed = cache.list_facts();
+
+    assert_eq!(
+        listed,
+        vec![
+            CachedFacts { cik: 320193, bytes: 2, age_secs: listed[0].age_secs },
+            CachedFacts { cik: 789019, bytes: 12, age_secs: listed[1].age_secs },
+        ]
+    );
+    assert!(listed.iter().all(|f| f.age_secs < 60));
+    assert!(Cache::new(dir.join("absent")).list_facts().is_empty());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn identical_snapshots_are_not_stored_twice() {
+    let dir = std::env::temp_dir().join(format!("edgar_fetcher_snapshots_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir);
+    let options = SnapshotOptions::default();
+    let financials = HashMap::from([("Revenue".to_string(), vec![(2023, 100.0)])]);
+    let restated = HashMap::from([("Revenue".to_string(), vec![(2023, 105.0)])]);
+    assert!(cache.latest_snapshot(1, &options).is_none());
+
+    cache.store_snapshot(1, &financials, &options);
+    cache.store_snapshot(1, &financials, &options);
+    assert_eq!(fs::read_dir(dir.join("snapshots/CIK0000000001")).unwrap().count(), 1);
+
+    // Même seconde, séries différentes : deux archives distinctes, la plus récente d'abord
+    cache.store_snapshot(1, &restated, &options);
+    assert_eq!(fs::read_dir(dir.join("snapshots/CIK0000000001")).unwrap().count(), 2);
+    assert_eq!(cache.latest_snapshot(1, &options).unwrap().financials["Revenue"], vec![(2023, 105.0)]);
+    // Archive produite avec d'autres options : pas de référence
+    assert!(cache.latest_snapshot(1, &SnapshotOptions { years: Some(3), ..SnapshotOptions::default() }).is_none());
+    let _ = fs::remove_dir_all(&dir);
+}
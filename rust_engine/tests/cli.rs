@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn facts_file() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("edgar_fetcher_cli_{}.json", std::process::id()));
+    fs::write(&path, r#"{"entityName":"Test","facts":{"us-gaap":{"Revenues":{"units":{"USD":[
+        {"val":100,"fy":2023,"fp":"FY","form":"10-K","start":"2023-01-01","end":"2023-12-31"}
+    ]}}}}}"#).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_edgar_fetcher")).args(args).output().unwrap()
+}
+
+#[test]
+fn fetch_is_the_implicit_subcommand() {
+    let path = facts_file();
+    let path = path.to_str().unwrap();
+
+    let implicit = run(&["--facts-file", path, "TEST"]);
+    let explicit = run(&["fetch", "--facts-file", path, "TEST"]);
+
+    assert!(implicit.status.success(), "{}", String::from_utf8_lossy(&implicit.stderr));
+    assert_eq!(implicit.stdout, explicit.stdout);
+    let output: serde_json::Value = serde_json::from_slice(&implicit.stdout).unwrap();
+    assert_eq!(output["financials"]["Revenue"], serde_json::json!([[2023, 100.0]]));
+}
+
+#[test]
+fn invalid_and_misplaced_options_are_rejected() {
+    assert!(run(&["--version"]).status.success());
+    assert!(!run(&["--rate", "0", "AAPL"]).status.success());
+    assert!(!run(&["compare", "AAPL"]).status.success());
+    // Option de `fetch` avant une autre sous-commande : refusée plutôt qu'ignorée
+    let misplaced = run(&["--ttm", "cache"]);
+    assert!(!misplaced.status.success());
+    assert!(String::from_utf8_lossy(&misplaced.stderr).contains("--ttm"));
+    // Les séries trimestrielles n'existent qu'en JSON
+    for format in ["csv", "table"] {
+        let quarterly = run(&["--period", "quarterly", "--format", format, "AAPL"]);
+        assert!(!quarterly.status.success());
+        assert!(String::from_utf8_lossy(&quarterly.stderr).contains("--period quarterly"));
+    }
+}
+
+#[test]
+fn list_metrics_reflects_the_metrics_file() {
+    let config = std::env::temp_dir().join(format!("edgar_fetcher_cli_metrics_{}.toml", std::process::id()));
+    fs::write(&config, "[[metric]]\nname = \"Lease Liabilities\"\ntags = [\"OperatingLeaseLiability\"]\nis_instant = true\nunit = \"monetary\"\n").unwrap();
+
+    let output = run(&["--list-metrics", "--metrics", config.to_str().unwrap()]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let listing: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let gaap = listing["us-gaap"].as_array().unwrap();
+    assert_eq!(gaap[0]["name"], "Revenue");
+    assert_eq!(gaap[0]["period"], "flow");
+    let lease = gaap.iter().find(|m| m["name"] == "Lease Liabilities").unwrap();
+    assert_eq!((&lease["tags"], &lease["unit"], &lease["period"]), (&serde_json::json!(["OperatingLeaseLiability"]), &serde_json::json!("monetary"), &serde_json::json!("instant")));
+    let derived = listing["derived"].as_array().unwrap();
+    assert!(derived.iter().any(|m| m["name"] == "Free Cash Flow" && m["period"] == "flow"));
+    // Gross Profit est publié par la config : pas listé une seconde fois parmi les dérivées
+    assert!(!derived.iter().any(|m| m["name"] == "Gross Profit"));
+    let _ = fs::remove_file(&config);
+}
+
+#[test]
+fn usd_price_is_not_applied_to_figures_in_another_currency() {
+    let path = std::env::temp_dir().join(format!("edgar_fetcher_cli_eur_{}.json", std::process::id()));
+    let fact = |val: u64| format!(r#"[{{"val":{},"fy":2023,"fp":"FY","form":"10-K","end":"2023-12-31"}}]"#, val);
+    fs::write(&path, format!(
+        r#"{{"entityName":"Euro","facts":{{"us-gaap":{{"LongTermDebt":{{"units":{{"EUR":{}}}}},"CommonStockSharesOutstanding":{{"units":{{"shares":{}}}}}}}}}}}"#,
+        fact(500), fact(10),
+    )).unwrap();
+
+    let output = run(&["--facts-file", path.to_str().unwrap(), "--price", "20", "EURO"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    // Dette publiée en EUR : valeur d'entreprise sans capitalisation boursière en USD
+    let ev = &output["valuation"]["enterprise_value"];
+    assert_eq!(ev["total_debt"], 500.0);
+    assert!(ev["market_cap"].is_null() && ev["enterprise_value"].is_null(), "{}", ev);
+    assert!(output["multiples"].is_null());
+}
@@ -0,0 +1,122 @@
+use edgar_fetcher::extract::{Period, US_GAAP_METRICS};
+use edgar_fetcher::models::CompanyFacts;
+use edgar_fetcher::segments::SEGMENTS_UNAVAILABLE;
+use edgar_fetcher::{build_company, extract, FetchOptions, NO_FINANCIAL_FACTS};
+use serde_json::json;
+
+#[test]
+fn filer_with_only_dei_facts_gets_a_warning() {
+    let facts: CompanyFacts = serde_json::from_value(json!({
+        "entityName": "Shell Co",
+        "facts": { "dei": { "EntityCommonStockSharesOutstanding": { "units": { "shares": [
+            { "val": 1.0e6, "fy": 2023, "fp": "FY", "form": "10-K", "end": "2024-03-01", "filed": "2024-03-15" }
+        ]}}}}
+    })).unwrap();
+
+    let data = build_company("SHEL".to_string(), 1, facts, &FetchOptions::default());
+
+    assert_eq!(data.taxonomy, None);
+    assert_eq!(data.warning.as_deref(), Some(NO_FINANCIAL_FACTS));
+    assert!(!data.financials.contains_key("Revenue"));
+}
+
+#[test]
+fn extract_runs_the_pipeline_without_network() {
+    let facts: CompanyFacts = serde_json::from_value(json!({
+        "cik": 42,
+        "entityName": "Test Corp",
+        "facts": { "us-gaap": {
+            "NetCashProvidedByUsedInOperatingActivities": { "units": { "USD": [
+                { "val": 50.0, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-02-01" }
+            ]}},
+            "PaymentsToAcquirePropertyPlantAndEquipment": { "units": { "USD": [
+                { "val": 20.0, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-02-01" }
+            ]}}
+        }}
+    })).unwrap();
+
+    let financials = extract(&facts, US_GAAP_METRICS);
+
+    assert_eq!(facts.cik, Some(42));
+    assert_eq!(financials["Free Cash Flow"], vec![(2023, 30.0)]);
+}
+
+#[test]
+fn ifrs_filer_reporting_in_euros_is_flagged_without_conversion() {
+    let facts: CompanyFacts = serde_json::from_value(json!({
+        "entityName": "Euro SE",
+        "facts": { "ifrs-full": {
+            "Revenue": { "units": { "EUR": [
+                { "val": 800.0, "fy": 2023, "fp": "FY", "form": "20-F", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-03-01" }
+            ]}},
+            "DilutedEarningsLossPerShare": { "units": { "EUR/shares": [
+                { "val": 2.5, "fy": 2023, "fp": "FY", "form": "20-F", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-03-01" }
+            ]}}
+        }}
+    })).unwrap();
+
+    let data = build_company("EURO".to_string(), 7, facts, &FetchOptions::default());
+
+    assert_eq!(data.reporting_currency.as_deref(), Some("EUR"));
+    assert_eq!(data.financials["Revenue"], vec![(2023, 800.0)]);
+    assert_eq!(data.data_quality["EPS Diluted"].unit.as_deref(), Some("EUR/shares"));
+    assert_eq!(data.data_quality["Total Assets"].unit, None);
+}
+
+#[test]
+fn segments_mode_reports_consolidated_revenue_and_why_no_breakdown() {
+    let facts: CompanyFacts = serde_json::from_value(json!({
+        "entityName": "Test Corp",
+        "facts": { "us-gaap": { "RevenueFromContractWithCustomerExcludingAssessedTax": { "units": { "USD": [
+            { "val": 383.3, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2022-09-25", "end": "2023-09-30", "filed": "2023-11-03" }
+        ]}}}}
+    })).unwrap();
+    let opts = FetchOptions { segments: true, ..Default::default() };
+
+    let segments = build_company("TEST".to_string(), 1, facts, &opts).segments.unwrap();
+
+    assert_eq!(segments.consolidated, vec![(2023, 383.3)]);
+    assert_eq!(segments.note, SEGMENTS_UNAVAILABLE);
+}
+
+/// Chiffre d'affaires trimestriel (T1 à T3) et annuel de 2019 à 2023.
+fn quarterly_facts() -> CompanyFacts {
+    let facts: Vec<serde_json::Value> = (2019..=2023u16)
+        .flat_map(|year| {
+            let quarter = |q: u16, start: &str, end: &str| json!({ "val": f64::from(year * 10 + q), "fy": year, "fp": format!("Q{}", q), "form": "10-Q",
+                "start": format!("{}-{}", year, start), "end": format!("{}-{}", year, end), "filed": format!("{}-{}", year, end) });
+            [
+                quarter(1, "01-01", "03-31"),
+                quarter(2, "04-01", "06-30"),
+                quarter(3, "07-01", "09-30"),
+                json!({ "val": f64::from(year), "fy": year, "fp": "FY", "form": "10-K",
+                        "start": format!("{}-01-01", year), "end": format!("{}-12-31", year), "filed": format!("{}-02-15", year + 1) }),
+            ]
+        })
+        .collect();
+    serde_json::from_value(json!({ "entityName": "Quarterly Corp", "facts": { "us-gaap": { "Revenues": { "units": { "USD": facts } } } } })).unwrap()
+}
+
+fn quarterly_years(opts: &FetchOptions) -> Vec<u16> {
+    let data = build_company("QTR".to_string(), 1, quarterly_facts(), opts);
+    let mut years: Vec<u16> = data.quarterly.unwrap()["Revenue"].iter().filter_map(|v| v.fiscal_year()).collect();
+    years.dedup();
+    years
+}
+
+#[test]
+fn years_limits_quarterly_series_too() {
+    let opts = FetchOptions { period: Period::Quarterly, years: Some(2), ..FetchOptions::default() };
+
+    assert_eq!(quarterly_years(&opts), vec![2022, 2023]);
+}
+
+#[test]
+fn year_range_limits_quarterly_series_too() {
+    let range = FetchOptions { period: Period::Quarterly, min_year: Some(2020), max_year: Some(2021), ..FetchOptions::default() };
+    // Bornes incluses, appliquées avant `--years`
+    let combined = FetchOptions { years: Some(1), ..range.clone() };
+
+    assert_eq!(quarterly_years(&range), vec![2020, 2021]);
+    assert_eq!(quarterly_years(&combined), vec![2021]);
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::compare::compare;
+use edgar_fetcher::models::CompanyFinancials;
+
+fn company(ticker: &str, series: &[(&str, Vec<(u16, f64)>)]) -> CompanyFinancials {
+    CompanyFinancials {
+        ticker: ticker.to_string(),
+        financials: series.iter().map(|(n, s)| (n.to_string(), s.clone())).collect::<HashMap<_, _>>(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn aligns_latest_values_and_handles_missing_metrics() {
+    let a = company("AAA", &[("Revenue", vec![(2022, 100.0), (2023, 120.0)]), ("Net Income", vec![(2023, 12.0)])]);
+    let b = company("BBB", &[("Revenue", vec![(2023, 150.0)]), ("Long Term Debt", vec![(2023, 50.0)])]);
+    let cmp = compare(&a, &b);
+
+    let revenue = &cmp.metrics["Revenue"];
+    assert_eq!(revenue.winner.as_deref(), Some("BBB"));
+    let aaa = revenue.values["AAA"].unwrap();
+    assert_eq!((aaa.fiscal_year, aaa.value, aaa.yoy), (2023, 120.0, Some(0.2)));
+
+    let debt = &cmp.metrics["Long Term Debt"];
+    assert!(debt.values["AAA"].is_none() && debt.winner.is_none());
+    assert_eq!(cmp.ratios["Net Margin"].values["AAA"].unwrap().value, 0.1);
+    assert!(cmp.ratios["Net Margin"].values["BBB"].is_none());
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use edgar_fetcher::bounded_map;
+use edgar_fetcher::rate_limit::RateLimiter;
+
+#[tokio::test]
+async fn no_more_than_n_tasks_run_at_once() {
+    let (running, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+
+    let results = bounded_map(0..12u64, 3, |i| {
+        let (running, peak) = (&running, &peak);
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            // Durées décroissantes : les tâches finissent dans le désordre
+            tokio::time::sleep(Duration::from_millis(24 - 2 * i)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            i * 10
+        }
+    })
+    .await;
+
+    assert_eq!(peak.load(Ordering::SeqCst), 3);
+    assert_eq!(results, (0..12).map(|i| i * 10).collect::<Vec<_>>());
+}
+
+#[tokio::test(start_paused = true)]
+async fn rate_below_one_request_per_second_still_grants_tokens() {
+    let limiter = RateLimiter::new(0.5);
+    let start = tokio::time::Instant::now();
+
+    // Premier jeton immédiat, le suivant au bout de 1 / 0.5 = 2 s
+    limiter.acquire().await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+    limiter.acquire().await;
+    assert!(start.elapsed() >= Duration::from_secs(2) && start.elapsed() < Duration::from_millis(2100), "{:?}", start.elapsed());
+}
+
+#[tokio::test(start_paused = true)]
+async fn cold_start_never_exceeds_the_rate_in_the_first_second() {
+    let limiter = RateLimiter::new(8.0);
+    let start = tokio::time::Instant::now();
+
+    let mut granted = 0;
+    loop {
+        limiter.acquire().await;
+        if start.elapsed() >= Duration::from_secs(1) {
+            break;
+        }
+        granted += 1;
+    }
+
+    // Jetons à 0, 125, 250... 875 ms : pas de rafale au démarrage
+    assert_eq!(granted, 8);
+}
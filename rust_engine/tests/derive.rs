@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::derive::{derive_metrics, TaxRateOmission, DERIVED_METRICS};
+use edgar_fetcher::ratios::{
+    compute_dupont, compute_earnings_quality, compute_leverage, compute_margin_trends, compute_ratios, compute_roic, compute_sign_flags,
+    compute_working_capital, TrendDirection, DEFAULT_MARGIN_THRESHOLD,
+};
+
+#[test]
+fn ebitda_falls_back_to_bottom_up_ebit() {
+    let mut results = HashMap::from([
+        ("Operating Income (EBIT)".to_string(), vec![(2023, 50.0)]),
+        ("Net Income".to_string(), vec![(2022, 20.0), (2023, 30.0)]),
+        ("Interest Expense".to_string(), vec![(2022, 5.0), (2023, 5.0)]),
+        ("Income Tax".to_string(), vec![(2022, 8.0), (2023, 10.0)]),
+        ("Depreciation & Amortization".to_string(), vec![(2022, 10.0), (2023, 12.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+
+    assert_eq!(results["EBITDA"], vec![(2022, 43.0), (2023, 62.0)]);
+}
+
+#[test]
+fn gross_profit_is_not_created_empty_when_it_cannot_be_computed() {
+    let mut results = HashMap::from([("Revenue".to_string(), vec![(2023, 100.0)])]);
+
+    derive_metrics(&mut results);
+
+    assert!(!results.contains_key("Gross Profit"));
+}
+
+#[test]
+fn leverage_skips_and_flags_negative_equity_years() {
+    let results = HashMap::from([
+        ("Total Debt".to_string(), vec![(2022, 100.0), (2023, 120.0)]),
+        ("Cash & Equiv.".to_string(), vec![(2023, 20.0)]),
+        ("Total Equity".to_string(), vec![(2022, 200.0), (2023, -10.0)]),
+        ("Operating Income (EBIT)".to_string(), vec![(2022, 40.0), (2023, 30.0)]),
+        ("Interest Expense".to_string(), vec![(2022, 0.0), (2023, 6.0)]),
+    ]);
+
+    let leverage = compute_leverage(&results);
+
+    assert_eq!(leverage.net_debt, vec![(2022, 100.0), (2023, 100.0)]);
+    assert_eq!(leverage.debt_to_equity, vec![(2022, 0.5)]);
+    assert_eq!(leverage.negative_equity_years, vec![2023]);
+    assert_eq!(leverage.interest_coverage, vec![(2022, None), (2023, Some(5.0))]);
+}
+
+#[test]
+fn book_value_per_share_and_tangible_book() {
+    let mut results = HashMap::from([
+        ("Total Equity".to_string(), vec![(2022, 500.0), (2023, 600.0)]),
+        ("Shares Outstanding".to_string(), vec![(2023, 100.0)]),
+        ("Goodwill".to_string(), vec![(2022, 100.0), (2023, 100.0)]),
+        ("Intangibles".to_string(), vec![(2023, 50.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+
+    assert_eq!(results["Book Value Per Share"], vec![(2023, 6.0)]);
+    // Incorporels publiés en 2023 seulement : 2022 est une lacune, pas un zéro
+    assert_eq!(results["Tangible Book Value"], vec![(2023, 450.0)]);
+}
+
+#[test]
+fn tangible_book_counts_an_item_never_reported_as_zero() {
+    let mut results = HashMap::from([
+        ("Total Equity".to_string(), vec![(2022, 500.0), (2023, 600.0)]),
+        ("Goodwill".to_string(), vec![(2022, 100.0), (2023, 100.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+
+    assert_eq!(results["Tangible Book Value"], vec![(2022, 400.0), (2023, 500.0)]);
+}
+
+#[test]
+fn roic_uses_effective_tax_rate_and_invested_capital() {
+    let mut results = HashMap::from([
+        ("Operating Income (EBIT)".to_string(), vec![(2022, 100.0), (2023, 120.0)]),
+        ("Income Tax".to_string(), vec![(2022, 20.0), (2023, -5.0)]),
+        ("Pretax Income".to_string(), vec![(2022, 80.0), (2023, 90.0)]),
+        ("Total Equity".to_string(), vec![(2022, 400.0), (2023, 450.0)]),
+        ("Total Debt".to_string(), vec![(2022, 150.0)]),
+        ("Cash & Equiv.".to_string(), vec![(2022, 50.0), (2023, 50.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+    let roic = compute_roic(&results);
+
+    // 2022 : taux 25 %, NOPAT 75, capital 500 ; 2023 : crédit d'impôt, NOPAT non calculé
+    assert_eq!(roic.nopat, vec![(2022, 75.0)]);
+    assert_eq!(roic.invested_capital, vec![(2022, 500.0), (2023, 400.0)]);
+    assert_eq!(roic.roic, vec![(2022, 0.15)]);
+}
+
+#[test]
+fn effective_tax_rate_omits_meaningless_years() {
+    let mut results = HashMap::from([
+        ("Income Tax".to_string(), vec![(2021, 21.0), (2022, -15.0), (2023, 5.0)]),
+        ("Pretax Income".to_string(), vec![(2021, 100.0), (2022, 60.0), (2023, -40.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+
+    // 2022 : crédit d'impôt (taux négatif) ; 2023 : perte avant impôt
+    assert_eq!(results["Effective Tax Rate"], vec![(2021, 0.21)]);
+}
+
+#[test]
+fn omitted_tax_rate_years_are_flagged_with_their_reason() {
+    let mut results = HashMap::from([
+        ("Income Tax".to_string(), vec![(2020, 30.0), (2021, 21.0), (2022, -15.0), (2023, 5.0), (2024, 8.0)]),
+        ("Pretax Income".to_string(), vec![(2020, 20.0), (2021, 100.0), (2022, 60.0), (2023, -40.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+    let flags = compute_sign_flags(&results);
+
+    // 2024 : résultat avant impôt non publié, rien à signaler
+    let omitted: Vec<(u16, TaxRateOmission)> = flags.omitted_tax_rate.iter().map(|o| (o.fiscal_year, o.reason)).collect();
+    assert_eq!(omitted, vec![(2020, TaxRateOmission::AbovePretax), (2022, TaxRateOmission::TaxCredit), (2023, TaxRateOmission::PretaxLoss)]);
+    assert_eq!(serde_json::to_value(flags.omitted_tax_rate[1]).unwrap(), serde_json::json!({ "fiscal_year": 2022, "reason": "tax_credit" }));
+}
+
+#[test]
+fn turnovers_average_opening_and_closing_balances() {
+    let results = HashMap::from([
+        ("Revenue".to_string(), vec![(2022, 300.0), (2023, 330.0)]),
+        ("Total Assets".to_string(), vec![(2022, 100.0), (2023, 120.0)]),
+        ("Cost of Revenue".to_string(), vec![(2022, 200.0), (2023, 240.0)]),
+        ("Inventory".to_string(), vec![(2023, 40.0)]),
+    ]);
+
+    let ratios = compute_ratios(&results);
+
+    // 2022 : pas de bilan d'ouverture, solde de clôture seul ; 2023 : moyenne (100 + 120) / 2
+    assert_eq!(ratios["Asset Turnover"], vec![(2022, 3.0), (2023, 3.0)]);
+    assert_eq!(ratios["Inventory Turnover"], vec![(2023, 6.0)]);
+}
+
+#[test]
+fn dupont_reconstructs_roe_and_flags_divergent_years() {
+    let results = HashMap::from([
+        ("Net Income".to_string(), vec![(2022, 10.0), (2023, 12.0)]),
+        ("Revenue".to_string(), vec![(2022, 100.0), (2023, 120.0)]),
+        ("Total Assets".to_string(), vec![(2022, 200.0), (2023, 200.0)]),
+        ("Total Equity".to_string(), vec![(2022, 100.0), (2023, 200.0)]),
+    ]);
+
+    let dupont = compute_dupont(&results);
+
+    assert_eq!(dupont.len(), 2);
+    let first = &dupont[0];
+    assert_eq!((first.net_margin, first.asset_turnover, first.equity_multiplier), (0.1, 0.5, 2.0));
+    assert!((first.roe - 0.1).abs() < 1e-12);
+    assert!(!first.diverges);
+    // Augmentation de capital en 2023 : 12 / 150 (moyenne) contre 12 / 200 (clôture)
+    let second = &dupont[1];
+    assert!((second.roe - 0.08).abs() < 1e-12);
+    assert_eq!(second.reported_roe, Some(0.06));
+    assert!(second.diverges);
+}
+
+#[test]
+fn derived_metric_names_are_all_listed() {
+    let inputs = [
+        "Operating Cash Flow", "CapEx", "Net Income", "Depreciation & Amortization", "Long Term Debt",
+        "Short Term Debt", "Operating Income (EBIT)", "Income Tax", "Pretax Income", "Dividends Paid",
+        "Total Equity", "Shares Outstanding", "Goodwill", "Intangibles", "Buybacks", "Stock Issuance",
+    ];
+    let mut results: HashMap<String, Vec<(u16, f64)>> =
+        inputs.iter().map(|name| (name.to_string(), vec![(2022, 10.0), (2023, 20.0)])).collect();
+    results.insert("Pretax Income".to_string(), vec![(2022, 40.0), (2023, 80.0)]);
+
+    derive_metrics(&mut results);
+
+    for name in results.keys().filter(|name| !inputs.contains(&name.as_str())) {
+        assert!(DERIVED_METRICS.contains(&name.as_str()), "métrique dérivée non listée : {}", name);
+    }
+}
+
+#[test]
+fn research_and_development_intensity_is_share_of_revenue() {
+    let results = HashMap::from([
+        ("Revenue".to_string(), vec![(2022, 400.0), (2023, 500.0)]),
+        ("R&D".to_string(), vec![(2022, 60.0), (2023, 80.0)]),
+    ]);
+
+    assert_eq!(compute_ratios(&results)["R&D Intensity"], vec![(2022, 0.15), (2023, 0.16)]);
+}
+
+#[test]
+fn sbc_ratios_skip_zero_denominators() {
+    let results = HashMap::from([
+        ("SBC".to_string(), vec![(2022, 10.0), (2023, 20.0)]),
+        ("Revenue".to_string(), vec![(2022, 100.0), (2023, 0.0)]),
+        ("Operating Cash Flow".to_string(), vec![(2022, 40.0), (2023, 80.0)]),
+    ]);
+
+    let ratios = compute_ratios(&results);
+
+    assert_eq!(ratios["SBC / Revenue"], vec![(2022, 0.1)]);
+    assert_eq!(ratios["SBC / OCF"], vec![(2022, 0.25), (2023, 0.25)]);
+}
+
+#[test]
+fn working_capital_change_needs_the_prior_year() {
+    let results = HashMap::from([
+        ("Total Current Assets".to_string(), vec![(2020, 90.0), (2022, 100.0), (2023, 130.0)]),
+        ("Total Current Liabilities".to_string(), vec![(2020, 50.0), (2022, 60.0), (2023, 70.0)]),
+    ]);
+
+    let wc = compute_working_capital(&results);
+
+    assert_eq!(wc.working_capital, vec![(2020, 40.0), (2022, 40.0), (2023, 60.0)]);
+    // 2021 manquant : ni 2020 (premier exercice) ni 2022 n'ont de variation
+    assert_eq!(wc.change, vec![(2023, 20.0)]);
+}
+
+#[test]
+fn earnings_quality_skips_non_positive_income_and_assets() {
+    let results = HashMap::from([
+        ("Free Cash Flow".to_string(), vec![(2022, 90.0), (2023, 20.0)]),
+        ("Net Income".to_string(), vec![(2022, 100.0), (2023, 0.0)]),
+        ("Operating Cash Flow".to_string(), vec![(2022, 80.0), (2023, 30.0)]),
+        ("Total Assets".to_string(), vec![(2022, 1000.0), (2023, 1200.0)]),
+    ]);
+
+    let quality = compute_earnings_quality(&results);
+
+    assert_eq!(quality.cash_conversion, vec![(2022, 0.9)]);
+    assert_eq!(quality.accruals_ratio, vec![(2022, 0.02), (2023, -0.025)]);
+}
+
+#[test]
+fn return_on_assets_uses_closing_assets() {
+    let results = HashMap::from([
+        ("Net Income".to_string(), vec![(2022, 30.0), (2023, 45.0)]),
+        ("Total Assets".to_string(), vec![(2022, 600.0), (2023, 0.0)]),
+    ]);
+
+    assert_eq!(compute_ratios(&results)["ROA"], vec![(2022, 0.05)]);
+}
+
+#[test]
+fn negative_equity_and_losses_are_flagged_and_their_ratios_omitted() {
+    let mut results = HashMap::from([
+        ("Net Income".to_string(), vec![(2022, -20.0), (2023, -10.0)]),
+        ("Total Equity".to_string(), vec![(2022, 50.0), (2023, -40.0)]),
+        ("Dividends Paid".to_string(), vec![(2022, 5.0), (2023, 5.0)]),
+        ("Operating Cash Flow".to_string(), vec![(2022, 10.0), (2023, 15.0)]),
+        ("CapEx".to_string(), vec![(2022, 30.0), (2023, 5.0)]),
+    ]);
+
+    derive_metrics(&mut results);
+    let flags = compute_sign_flags(&results);
+
+    // 2023 : perte sur capitaux propres négatifs, le ROE mécanique serait de +25 %
+    assert_eq!(compute_ratios(&results)["ROE"], vec![(2022, -0.4)]);
+    assert!(!results.contains_key("Payout Ratio"));
+    assert_eq!((flags.negative_equity_years, flags.net_loss_years, flags.negative_fcf_years), (vec![2023], vec![2022, 2023], vec![2022]));
+    assert_eq!(flags.meaningless["ROE"], vec![2023]);
+    assert_eq!(flags.meaningless["Payout Ratio"], vec![2022, 2023]);
+    assert_eq!(flags.meaningless["Cash Conversion"], vec![2022, 2023]);
+}
+
+#[test]
+fn margin_trends_label_the_regression_slope() {
+    let results = HashMap::from([
+        ("Revenue".to_string(), vec![(2021, 100.0), (2022, 100.0), (2023, 100.0)]),
+        ("Gross Profit".to_string(), vec![(2021, 40.0), (2022, 42.0), (2023, 44.0)]),
+        ("Operating Income (EBIT)".to_string(), vec![(2021, 20.0), (2022, 20.2), (2023, 19.9)]),
+        ("Net Income".to_string(), vec![(2021, 15.0), (2022, 12.0), (2023, 9.0)]),
+    ]);
+    let ratios = compute_ratios(&results);
+
+    let trends = compute_margin_trends(&ratios, DEFAULT_MARGIN_THRESHOLD);
+
+    assert_eq!(trends["Gross Margin"].direction, TrendDirection::Expanding);
+    assert!((trends["Gross Margin"].slope - 0.02).abs() < 1e-12);
+    assert_eq!((trends["Gross Margin"].first_year, trends["Gross Margin"].last_year), (2021, 2023));
+    assert_eq!(trends["Operating Margin"].direction, TrendDirection::Stable);
+    assert_eq!(trends["Net Margin"].direction, TrendDirection::Contracting);
+    // Seuil relevé : la baisse de 3 points par an reste sous le seuil
+    assert_eq!(compute_margin_trends(&ratios, 0.05)["Net Margin"].direction, TrendDirection::Stable);
+    // Un seul exercice : pas de tendance
+    let single = compute_ratios(&HashMap::from([("Revenue".to_string(), vec![(2023, 10.0)]), ("Net Income".to_string(), vec![(2023, 1.0)])]));
+    assert!(compute_margin_trends(&single, DEFAULT_MARGIN_THRESHOLD).is_empty());
+}
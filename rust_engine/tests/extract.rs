@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::extract::{
+    apply_cover_shares, extract_financials, extract_with_quality, latest_public_float, list_concepts, MatchKind, Resolution, YearConflict, US_GAAP_METRICS,
+};
+use edgar_fetcher::models::CompanyFacts;
+use serde_json::json;
+
+fn facts(gaap: serde_json::Value) -> CompanyFacts {
+    serde_json::from_value(json!({ "entityName": "Test Corp", "facts": { "us-gaap": gaap } })).unwrap()
+}
+
+fn duration(val: f64, fy: u16, start: &str, end: &str, filed: &str) -> serde_json::Value {
+    json!({ "val": val, "fy": fy, "fp": "FY", "form": "10-K", "start": start, "end": end, "filed": filed })
+}
+
+fn instant(val: f64, fy: u16, end: &str, filed: &str) -> serde_json::Value {
+    json!({ "val": val, "fy": fy, "fp": "FY", "form": "10-K", "end": end, "filed": filed })
+}
+
+#[test]
+fn january_year_end_is_bucketed_by_reported_fiscal_year() {
+    // Distributeur à clôture fin janvier : l'exercice 2023 se termine le 3 février 2024 (53 semaines).
+    let data = facts(json!({
+        "Revenues": { "units": { "USD": [
+            duration(106.0, 2022, "2021-01-31", "2022-01-29", "2023-03-08"),
+            duration(109.1, 2022, "2022-01-30", "2023-01-28", "2023-03-08"),
+            duration(106.0, 2023, "2021-01-31", "2022-01-29", "2024-03-13"),
+            duration(109.1, 2023, "2022-01-30", "2023-01-28", "2024-03-13"),
+            duration(107.4, 2023, "2023-01-29", "2024-02-03", "2024-03-13"),
+        ]}},
+        "StockholdersEquity": { "units": { "USD": [
+            instant(11.2, 2023, "2023-01-28", "2024-03-13"),
+            instant(13.4, 2023, "2024-02-03", "2024-03-13"),
+        ]}}
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["Revenue"], vec![(2021, 106.0), (2022, 109.1), (2023, 107.4)]);
+    assert_eq!(results["Total Equity"], vec![(2022, 11.2), (2023, 13.4)]);
+}
+
+#[test]
+fn missing_fy_falls_back_to_end_year() {
+    let data = facts(json!({
+        "Revenues": { "units": { "USD": [
+            { "val": 50.0, "fp": "FY", "form": "10-K", "start": "2020-01-01", "end": "2020-12-31" },
+        ]}}
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["Revenue"], vec![(2020, 50.0)]);
+}
+
+#[test]
+fn balance_sheet_totals_are_extracted() {
+    // Bilans Apple (millions USD) au 24/09/2022 et 30/09/2023, tels que repris dans le 10-K FY2023
+    let filed = "2023-11-03";
+    let data = facts(json!({
+        "Assets": { "units": { "USD": [instant(352_755.0, 2023, "2022-09-24", filed), instant(352_583.0, 2023, "2023-09-30", filed)] }},
+        "Liabilities": { "units": { "USD": [instant(302_083.0, 2023, "2022-09-24", filed), instant(290_437.0, 2023, "2023-09-30", filed)] }},
+        "AssetsCurrent": { "units": { "USD": [instant(135_405.0, 2023, "2022-09-24", filed), instant(143_566.0, 2023, "2023-09-30", filed)] }},
+        "LiabilitiesCurrent": { "units": { "USD": [instant(153_982.0, 2023, "2022-09-24", filed), instant(145_308.0, 2023, "2023-09-30", filed)] }},
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["Total Assets"], vec![(2022, 352_755.0), (2023, 352_583.0)]);
+    assert_eq!(results["Total Liabilities"], vec![(2022, 302_083.0), (2023, 290_437.0)]);
+    assert_eq!(results["Total Current Assets"], vec![(2022, 135_405.0), (2023, 143_566.0)]);
+    assert_eq!(results["Total Current Liabilities"], vec![(2022, 153_982.0), (2023, 145_308.0)]);
+}
+
+#[test]
+fn buybacks_are_extracted_as_annual_flows() {
+    // Rachats d'actions Apple (millions USD) publiés dans le 10-K FY2023, plus un trimestre à ignorer
+    let filed = "2023-11-03";
+    let data = facts(json!({
+        "PaymentsForRepurchaseOfCommonStock": { "units": { "USD": [
+            duration(85_971.0, 2023, "2020-09-27", "2021-09-25", filed),
+            duration(89_402.0, 2023, "2021-09-26", "2022-09-24", filed),
+            duration(77_550.0, 2023, "2022-09-25", "2023-09-30", filed),
+            { "val": 19_000.0, "fy": 2023, "fp": "Q3", "form": "10-Q", "start": "2023-04-02", "end": "2023-07-01", "filed": "2023-08-04" },
+        ]}}
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["Buybacks"], vec![(2021, 85_971.0), (2022, 89_402.0), (2023, 77_550.0)]);
+    assert!(results["Buybacks"].iter().all(|&(_, v)| v > 0.0));
+}
+
+#[test]
+fn cover_page_share_count_overrides_gaap_shares() {
+    let data: CompanyFacts = serde_json::from_value(json!({
+        "entityName": "Test Corp",
+        "facts": {
+            "us-gaap": { "WeightedAverageNumberOfDilutedSharesOutstanding": { "units": { "shares": [
+                duration(101.0, 2022, "2021-10-01", "2022-09-30", "2022-11-01"),
+                duration(99.0, 2023, "2022-10-01", "2023-09-30", "2023-11-01"),
+            ]}}},
+            "dei": {
+                "EntityCommonStockSharesOutstanding": { "units": { "shares": [
+                    instant(97.5, 2023, "2023-10-20", "2023-11-01"),
+                    { "val": 96.0, "fy": 2024, "fp": "Q1", "form": "10-Q", "end": "2024-01-20", "filed": "2024-02-01" },
+                ]}},
+                "EntityPublicFloat": { "units": { "USD": [
+                    instant(2.0e9, 2022, "2022-03-31", "2022-11-01"),
+                    instant(2.5e9, 2023, "2023-03-31", "2023-11-01"),
+                ]}}
+            }
+        }
+    })).unwrap();
+
+    let (mut results, mut quality) = extract_with_quality(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS, false);
+    let dei = data.facts.dei.as_ref().unwrap();
+    apply_cover_shares(&mut results, &mut quality, dei);
+
+    assert_eq!(results["Shares Outstanding"], vec![(2022, 101.0), (2023, 97.5)]);
+    // Provenance : 2022 reste sur le tag GAAP, 2023 pointe vers la page de garde
+    let shares = &quality["Shares Outstanding"];
+    assert_eq!((shares.matched_tag.as_deref(), shares.unit.as_deref()), (Some("EntityCommonStockSharesOutstanding"), Some("shares")));
+    let sources: Vec<(u16, String)> = shares.sources.iter().map(|s| (s.fiscal_year, s.end.to_string())).collect();
+    assert_eq!(sources, vec![(2022, "2022-09-30".to_string()), (2023, "2023-10-20".to_string())]);
+    let float = latest_public_float(dei).unwrap();
+    assert_eq!((float.period.as_str(), float.value), ("2023-03-31", 2.5e9));
+}
+
+#[test]
+fn cover_page_share_counts_of_each_class_are_summed() {
+    // Deux catégories d'actions dans le même 10-K ; un dépôt antérieur rattaché au même
+    // exercice est écarté
+    let class = |val: f64, accn: &str, end: &str, filed: &str| json!({ "val": val, "accn": accn, "fy": 2023, "fp": "FY", "form": "10-K", "end": end, "filed": filed });
+    let data: CompanyFacts = serde_json::from_value(json!({
+        "entityName": "Two Classes Corp",
+        "facts": { "dei": { "EntityCommonStockSharesOutstanding": { "units": { "shares": [
+            class(5_900.0, "0001652044-24-000022", "2024-01-25", "2024-02-01"),
+            class(5_500.0, "0001652044-24-000022", "2024-01-25", "2024-02-01"),
+            class(6_000.0, "0001652044-23-000016", "2023-01-26", "2023-02-03"),
+        ]}}}}
+    })).unwrap();
+
+    let (mut results, mut quality) = (HashMap::new(), HashMap::new());
+    apply_cover_shares(&mut results, &mut quality, data.facts.dei.as_ref().unwrap());
+
+    assert_eq!(results["Shares Outstanding"], vec![(2023, 11_400.0)]);
+    let source = &quality["Shares Outstanding"].sources[0];
+    assert_eq!((source.accn.as_deref(), source.form.as_deref()), (Some("0001652044-24-000022"), Some("10-K")));
+}
+
+#[test]
+fn fifty_three_week_year_is_kept_and_partial_year_dropped() {
+    // Calendrier 52/53 semaines : exercice 2022 de 364 jours, exercice 2023 de 371 jours.
+    // Le cumul de 361 jours publié dans un 10-Q (fp Q4) n'est pas une année complète
+    // et ne doit pas servir d'exercice 2024.
+    let data = facts(json!({
+        "Revenues": { "units": { "USD": [
+            duration(50.0, 2023, "2021-10-03", "2022-10-01", "2023-11-20"),
+            duration(56.0, 2023, "2022-10-02", "2023-10-07", "2023-11-20"),
+            { "val": 70.0, "fy": 2024, "fp": "Q4", "form": "10-Q", "start": "2023-10-11", "end": "2024-10-05", "filed": "2024-11-18" },
+        ]}}
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["Revenue"], vec![(2022, 50.0), (2023, 56.0)]);
+}
+
+#[test]
+fn research_and_development_keeps_full_year_expense_only() {
+    // Gros investisseur en R&D : le 10-K reprend aussi le T4 seul et le 10-Q un cumul de 9 mois
+    let data = facts(json!({
+        "ResearchAndDevelopmentExpense": { "units": { "USD": [
+            duration(26_251e6, 2022, "2021-09-26", "2022-09-24", "2022-10-28"),
+            duration(29_915e6, 2023, "2022-09-25", "2023-09-30", "2023-11-03"),
+            { "val": 7_307e6, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-07-02", "end": "2023-09-30", "filed": "2023-11-03" },
+            { "val": 22_608e6, "fy": 2024, "fp": "Q3", "form": "10-Q", "start": "2023-10-01", "end": "2024-06-29", "filed": "2024-08-02" },
+        ]}}
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["R&D"], vec![(2022, 26_251e6), (2023, 29_915e6)]);
+}
+
+#[test]
+fn sbc_reads_the_expense_not_the_unrecognized_cost_disclosure() {
+    // Le coût restant à reconnaître dépasse largement la charge : il ne doit jamais l'emporter
+    let data = facts(json!({
+        "ShareBasedCompensation": { "units": { "USD": [
+            duration(9_038e6, 2022, "2021-09-26", "2022-09-24", "2022-10-28"),
+        ]}},
+        "AllocatedShareBasedCompensationExpense": { "units": { "USD": [
+            duration(9_100e6, 2022, "2021-09-26", "2022-09-24", "2022-10-28"),
+            duration(10_833e6, 2023, "2022-09-25", "2023-09-30", "2023-11-03"),
+        ]}},
+        "EmployeeServiceShareBasedCompensationNonvestedAwardsTotalCompensationCostNotYetRecognized": { "units": { "USD": [
+            duration(18_000e6, 2022, "2021-09-26", "2022-09-24", "2022-10-28"),
+        ]}}
+    }));
+
+    let (results, quality) = extract_with_quality(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS, false);
+
+    // L'alternative n'est pas mêlée au tag principal, même pour l'exercice qu'il ne couvre pas
+    assert_eq!(results["SBC"], vec![(2022, 9_038e6)]);
+    assert_eq!(quality["SBC"].matched_tag.as_deref(), Some("ShareBasedCompensation"));
+    assert!(quality["SBC"].conflicts.is_empty());
+
+    // Tag principal absent : repli sur la charge allouée
+    let fallback = facts(json!({
+        "AllocatedShareBasedCompensationExpense": { "units": { "USD": [
+            duration(10_833e6, 2023, "2022-09-25", "2023-09-30", "2023-11-03"),
+        ]}}
+    }));
+    let results = extract_financials(fallback.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+    assert_eq!(results["SBC"], vec![(2023, 10_833e6)]);
+}
+
+#[test]
+fn restated_flow_keeps_latest_filed_value() {
+    // Chiffre d'affaires 2021 retraité à la baisse dans le 10-K suivant, et exercice 2023
+    // connu seulement par deux dépôts non-10-K : dans les deux cas, le plus récent l'emporte.
+    let data = facts(json!({
+        "Revenues": { "units": { "USD": [
+            duration(100.0, 2021, "2021-01-01", "2021-12-31", "2022-02-20"),
+            duration(90.0, 2022, "2021-01-01", "2021-12-31", "2023-02-20"),
+            duration(95.0, 2022, "2022-01-01", "2022-12-31", "2023-02-20"),
+            { "val": 120.0, "fy": 2023, "fp": "FY", "form": "8-K", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-01-25" },
+            { "val": 110.0, "fy": 2023, "fp": "FY", "form": "8-K", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-02-15" },
+        ]}}
+    }));
+
+    let results = extract_financials(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS);
+
+    assert_eq!(results["Revenue"], vec![(2021, 90.0), (2022, 95.0), (2023, 110.0)]);
+}
+
+#[test]
+fn data_quality_reports_matched_tag_filtering_and_conflicts() {
+    // Exercice 2021 retraité (deux valeurs), plus un trimestre écarté par la durée
+    // et un fait sans valeur ; le tag prioritaire `Revenues` est absent.
+    let data = facts(json!({
+        "SalesRevenueNet": { "units": { "USD": [
+            duration(100.0, 2021, "2021-01-01", "2021-12-31", "2022-02-20"),
+            duration(90.0, 2022, "2021-01-01", "2021-12-31", "2023-02-20"),
+            duration(95.0, 2022, "2022-01-01", "2022-12-31", "2023-02-20"),
+            { "val": 25.0, "fy": 2023, "fp": "Q1", "form": "10-Q", "start": "2023-01-01", "end": "2023-03-31", "filed": "2023-05-01" },
+            { "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-02-20" },
+        ]}}
+    }));
+
+    let (results, quality) = extract_with_quality(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS, false);
+
+    assert_eq!(results["Revenue"], vec![(2021, 90.0), (2022, 95.0)]);
+    let revenue = &quality["Revenue"];
+    assert_eq!(revenue.matched_tag.as_deref(), Some("SalesRevenueNet"));
+    assert_eq!((revenue.raw_facts, revenue.filtered_out), (5, 2));
+    assert_eq!(revenue.conflicts, vec![YearConflict { fiscal_year: 2021, values: 2, resolution: Resolution::LatestFiled }]);
+    assert_eq!(quality["Total Assets"].matched_tag, None);
+}
+
+#[test]
+fn data_quality_traces_each_year_to_its_filing() {
+    // Le 10-K/A retraitant 2022 l'emporte : c'est son numéro de dépôt qui doit être restitué
+    let data = facts(json!({
+        "Revenues": { "units": { "USD": [
+            { "val": 100.0, "accn": "0000000001-23-000010", "fy": 2022, "fp": "FY", "form": "10-K", "start": "2022-01-01", "end": "2022-12-31", "filed": "2023-02-20" },
+            { "val": 98.0, "accn": "0000000001-23-000042", "fy": 2022, "fp": "FY", "form": "10-K/A", "start": "2022-01-01", "end": "2022-12-31", "filed": "2023-06-01" },
+            { "val": 110.0, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-01-01", "end": "2023-12-31", "filed": "2024-02-20" },
+        ]}}
+    }));
+
+    let (_, quality) = extract_with_quality(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS, false);
+
+    let sources = &quality["Revenue"].sources;
+    assert_eq!(sources[0].accn.as_deref(), Some("0000000001-23-000042"));
+    assert_eq!(sources[0].form.as_deref(), Some("10-K/A"));
+    // Fait sans `accn` : la source reste restituée, sans numéro
+    assert_eq!((sources[1].accn.as_deref(), sources[1].form.as_deref()), (None, Some("10-K")));
+}
+
+#[test]
+fn ties_are_broken_the_same_way_on_every_run() {
+    // Égalités parfaites de valeur absolue et de date de dépôt : seul le départage (numéro
+    // d'enregistrement, puis rang du tag) décide, pas l'ordre de parcours des `HashMap`
+    let fixture = json!({
+        "Assets": { "units": { "USD": [
+            { "val": -100.0, "accn": "0000000001-23-000002", "fy": 2023, "fp": "Q2", "form": "10-Q", "end": "2023-06-30", "filed": "2023-08-01" },
+            { "val": 100.0, "accn": "0000000001-23-000001", "fy": 2023, "fp": "Q2", "form": "10-Q", "end": "2023-06-30", "filed": "2023-08-01" },
+        ]}},
+        "Revenues": { "units": { "USD": [duration(50.0, 2023, "2023-01-01", "2023-12-31", "2024-02-20")] }},
+        "SalesRevenueNet": { "units": { "USD": [duration(55.0, 2023, "2023-01-01", "2023-12-31", "2024-02-20")] }},
+    });
+    let run = || {
+        let data = facts(fixture.clone());
+        let (results, quality) = extract_with_quality(data.facts.us_gaap.as_ref().unwrap(), US_GAAP_METRICS, false);
+        let mut results: Vec<(String, Vec<(u16, f64)>)> = results.into_iter().collect();
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        (results, serde_json::to_value(quality).unwrap())
+    };
+
+    let first = run();
+    for _ in 0..20 {
+        assert_eq!(run(), first);
+    }
+    let (results, quality) = first;
+    let value = |name: &str| results.iter().find(|(n, _)| n == name).map(|(_, s)| s.clone()).unwrap();
+    assert_eq!(value("Total Assets"), vec![(2023, -100.0)]);
+    assert_eq!(value("Revenue"), vec![(2023, 50.0)]);
+    assert_eq!(quality["Revenue"]["matched_tag"], "Revenues");
+}
+
+#[test]
+fn concepts_are_listed_by_name_with_unit_and_fact_counts() {
+    let data = facts(json!({
+        "Revenues": { "units": { "USD": [duration(1.0, 2023, "2023-01-01", "2023-12-31", "2024-02-01")] }},
+        "EarningsPerShareDiluted": { "units": {
+            "USD/shares": [duration(0.5, 2022, "2022-01-01", "2022-12-31", "2023-02-01"), duration(0.6, 2023, "2023-01-01", "2023-12-31", "2024-02-01")],
+            "EUR/shares": [duration(0.55, 2023, "2023-01-01", "2023-12-31", "2024-02-01")]
+        }}
+    }));
+
+    let concepts = list_concepts(data.facts.us_gaap.as_ref().unwrap());
+
+    let summary: Vec<(&str, usize, usize)> = concepts.iter().map(|c| (c.name.as_str(), c.units, c.facts)).collect();
+    assert_eq!(summary, vec![("EarningsPerShareDiluted", 2, 3), ("Revenues", 1, 1)]);
+}
+
+#[test]
+fn fuzzy_fallback_only_applies_with_the_flag_and_a_close_name() {
+    let data = facts(json!({
+        "InterestExpenseNet": { "units": { "USD": [duration(12.0, 2023, "2023-01-01", "2023-12-31", "2024-02-01")] }},
+        "GoodwillImpairmentLoss": { "units": { "USD": [duration(3.0, 2023, "2023-01-01", "2023-12-31", "2024-02-01")] }}
+    }));
+    let gaap = data.facts.us_gaap.as_ref().unwrap();
+
+    let (strict, _) = extract_with_quality(gaap, US_GAAP_METRICS, false);
+    let (results, quality) = extract_with_quality(gaap, US_GAAP_METRICS, true);
+
+    assert!(strict["Interest Expense"].is_empty());
+    assert_eq!(results["Interest Expense"], vec![(2023, 12.0)]);
+    assert_eq!(quality["Interest Expense"].matched_tag.as_deref(), Some("InterestExpenseNet"));
+    assert_eq!(quality["Interest Expense"].matched_by, Some(MatchKind::Fuzzy));
+    // Nom trop éloigné de `Goodwill` : pas de rapprochement
+    assert!(results["Goodwill"].is_empty());
+    assert_eq!(quality["Goodwill"].matched_by, None);
+}
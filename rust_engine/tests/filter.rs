@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::filter::{last_years, year_range};
+
+#[test]
+fn year_range_bounds_are_inclusive_and_applied_before_last_years() {
+    let mut financials = HashMap::from([
+        ("Revenue".to_string(), (2014..=2023).map(|y| (y, y as f64)).collect::<Vec<_>>()),
+    ]);
+
+    year_range(&mut financials, Some(2015), Some(2020));
+    assert_eq!(financials["Revenue"].first(), Some(&(2015, 2015.0)));
+    assert_eq!(financials["Revenue"].last(), Some(&(2020, 2020.0)));
+
+    last_years(&mut financials, 2);
+    assert_eq!(financials["Revenue"], vec![(2019, 2019.0), (2020, 2020.0)]);
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use edgar_fetcher::fx::{convert_to_usd, CachedRates, Frankfurter, RateProvider};
+use edgar_fetcher::http::HttpClient;
+use edgar_fetcher::models::CompanyFacts;
+use edgar_fetcher::{build_company, FetchOptions, Result};
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Taux fixe par année de la date demandée ; compte les appels pour vérifier le cache.
+struct FixedRates {
+    calls: Arc<AtomicUsize>,
+}
+
+impl RateProvider for FixedRates {
+    async fn usd_rate(&self, _currency: &str, date: NaiveDate) -> Result<f64> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(if date.to_string().starts_with("2022") { 1.0 } else { 1.1 })
+    }
+}
+
+fn euro(val: f64, year: u16, instant: bool) -> Value {
+    let mut fact = json!({ "val": val, "fy": year, "fp": "FY", "form": "20-F",
+                           "end": format!("{}-12-31", year), "filed": format!("{}-03-01", year + 1) });
+    if !instant {
+        fact["start"] = json!(format!("{}-01-01", year));
+    }
+    fact
+}
+
+fn euro_filer() -> CompanyFacts {
+    serde_json::from_value(json!({
+        "entityName": "Euro SE",
+        "facts": { "ifrs-full": {
+            "CashFlowsFromUsedInOperatingActivities": { "units": { "EUR": [euro(100.0, 2022, false), euro(100.0, 2023, false)] }},
+            "PurchaseOfPropertyPlantAndEquipment": { "units": { "EUR": [euro(40.0, 2022, false), euro(40.0, 2023, false)] }},
+            "EquityAttributableToOwnersOfParent": { "units": { "EUR": [euro(500.0, 2023, true)] }},
+            "NumberOfSharesOutstanding": { "units": { "shares": [euro(50.0, 2023, true)] }},
+            "DilutedEarningsLossPerShare": { "units": { "EUR/shares": [euro(2.0, 2023, false)] }}
+        }}
+    })).unwrap()
+}
+
+#[tokio::test]
+async fn euro_amounts_are_converted_and_derived_metrics_recomputed() {
+    let mut data = build_company("EURO".to_string(), 7, euro_filer(), &FetchOptions::default());
+    let calls = Arc::new(AtomicUsize::new(0));
+    let rates = CachedRates::new(FixedRates { calls: calls.clone() }, None);
+
+    convert_to_usd(&mut data, &rates).await.unwrap();
+
+    let f = &data.financials;
+    assert_eq!(f["Operating Cash Flow"][0], (2022, 100.0));
+    assert!((f["Operating Cash Flow"][1].1 - 110.0).abs() < 1e-9);
+    assert!((f["Free Cash Flow"][1].1 - 66.0).abs() < 1e-9);
+    assert!((f["Book Value Per Share"][0].1 - 11.0).abs() < 1e-9);
+    assert!((f["EPS Diluted"][0].1 - 2.2).abs() < 1e-9);
+    assert_eq!(f["Shares Outstanding"], vec![(2023, 50.0)]);
+    assert_eq!(data.reporting_currency.as_deref(), Some("EUR"));
+    assert_eq!(data.data_quality["EPS Diluted"].unit.as_deref(), Some("USD/shares"));
+    assert_eq!(data.fx.as_ref().unwrap().rates["EUR"].len(), 2);
+    // Deux dates de fin distinctes : le fournisseur n'est interrogé qu'une fois par date
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn frankfurter_reads_usd_rate_for_the_fact_end_date() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/2023-12-31"))
+        .and(query_param("from", "EUR"))
+        .and(query_param("to", "USD"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "amount": 1.0, "base": "EUR", "date": "2023-12-29", "rates": { "USD": 1.105 }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let http = HttpClient::new(1000.0, 0, "Tests tests@example.org").unwrap();
+    let rates = CachedRates::new(Frankfurter::new(http, &server.uri()), None);
+    let date = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+
+    assert_eq!(rates.rate("EUR", date).await.unwrap(), 1.105);
+    // Deuxième demande servie par le cache : `expect(1)` échoue sinon
+    assert_eq!(rates.rate("EUR", date).await.unwrap(), 1.105);
+}
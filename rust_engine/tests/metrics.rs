@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::extract::{extract_with_quality, UnitKind, US_GAAP_METRICS};
+use edgar_fetcher::models::FactData;
+use edgar_fetcher::metrics::MetricsConfig;
+
+#[test]
+fn merge_overrides_builtin_and_appends_new_metrics() {
+    let config = MetricsConfig::from_toml(r#"
+        [[metric]]
+        name = "Revenue"
+        tags = ["Revenues"]
+        unit = "monetary"
+
+        [[metric]]
+        name = "Lease Liabilities"
+        tags = ["OperatingLeaseLiability"]
+        is_instant = true
+        unit = "monetary"
+    "#).unwrap();
+
+    assert_eq!(config.us_gaap.len(), US_GAAP_METRICS.len() + 1);
+    let revenue = config.us_gaap.iter().find(|d| d.name == "Revenue").unwrap();
+    assert_eq!(revenue.tags, ["Revenues"]);
+    let lease = config.us_gaap.last().unwrap();
+    assert_eq!((lease.name, lease.is_instant, lease.expected_unit), ("Lease Liabilities", true, UnitKind::Monetary));
+    assert!(!config.ifrs_full.is_empty());
+}
+
+#[test]
+fn replace_mode_and_malformed_entries() {
+    let config = MetricsConfig::from_toml(r#"
+        mode = "replace"
+        [[metric]]
+        name = "Cash"
+        tags = ["Cash"]
+        is_instant = true
+        unit = "monetary"
+    "#).unwrap();
+    assert_eq!(config.us_gaap.len(), 1);
+
+    let err = MetricsConfig::from_toml("[[metric]]\nname = \"X\"\ntags = []\nunit = \"monetary\"").unwrap_err();
+    assert!(err.contains("'X'"), "{}", err);
+    assert!(MetricsConfig::from_toml("[[metric]]\nname = \"X\"\ntags = [\"A\"]\nunit = \"euros\"").is_err());
+}
+
+#[test]
+fn canonical_config_reports_fallback_only_metrics_as_missing() {
+    let facts: HashMap<String, FactData> = serde_json::from_value(serde_json::json!({
+        "RevenueFromContractWithCustomerExcludingAssessedTax": { "units": { "USD": [
+            { "val": 100.0, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-01-01", "end": "2023-12-31" }
+        ]}},
+        "NetIncomeLoss": { "units": { "USD": [
+            { "val": 10.0, "fy": 2023, "fp": "FY", "form": "10-K", "start": "2023-01-01", "end": "2023-12-31" }
+        ]}}
+    })).unwrap();
+    let canonical = MetricsConfig::default().canonical();
+
+    let (results, quality) = extract_with_quality(&facts, &canonical.us_gaap, false);
+
+    assert!(canonical.us_gaap.iter().all(|def| def.tags.len() <= 1));
+    assert!(results["Revenue"].is_empty());
+    assert_eq!(quality["Revenue"].matched_tag, None);
+    assert_eq!(results["Net Income"], vec![(2023, 10.0)]);
+}
@@ -0,0 +1,409 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use edgar_fetcher::cache::Cache;
+use edgar_fetcher::diff::{SnapshotDiff, ValueChange};
+use edgar_fetcher::fx::FxConversion;
+use edgar_fetcher::http::{parse_retry_after, HttpClient, MAX_RETRY_AFTER};
+use edgar_fetcher::metrics::MetricsConfig;
+use edgar_fetcher::models::{CompanyFinancials, Taxonomy};
+use edgar_fetcher::sec::{parse_facts, parse_facts_retaining, SecClient};
+use edgar_fetcher::{
+    build_company, fetch_company, fetch_company_by_cik, load_cached_mapping, load_mapping, record_snapshot, EngineError, FetchOptions, LEAN_MAX_CONCEPTS,
+    MALFORMED_FACTS,
+};
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn annual(val: f64, year: u16) -> Value {
+    json!({ "val": val, "fy": year, "fp": "FY", "form": "10-K",
+            "start": format!("{}-01-01", year), "end": format!("{}-12-31", year), "filed": format!("{}-02-15", year + 1) })
+}
+
+async fn server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/files/company_tickers.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "0": { "cik_str": 1, "ticker": "GAAP", "title": "Gaap Corp" },
+            "1": { "cik_str": 2, "ticker": "IFRS", "title": "Ifrs Plc" },
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyfacts/CIK0000000002.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "cik": 2, "entityName": "Ifrs Plc",
+            "facts": { "ifrs-full": { "Revenue": { "units": { "EUR": [annual(80.0, 2023)] } } } }
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn client(server: &MockServer) -> SecClient {
+    let http = HttpClient::new(1000.0, 2, "Tests tests@example.org").unwrap();
+    SecClient::new(http, &server.uri(), &server.uri())
+}
+
+fn gaap_facts() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "cik": 1, "entityName": "Gaap Corp",
+        "facts": { "us-gaap": { "Revenues": { "units": { "USD": [annual(100.0, 2022), annual(120.0, 2023)] } } } }
+    }))
+}
+
+#[tokio::test]
+async fn extracts_a_company_from_the_mock_server() {
+    let server = server().await;
+    Mock::given(method("GET")).and(path("/api/xbrl/companyfacts/CIK0000000001.json")).respond_with(gaap_facts()).mount(&server).await;
+    let client = client(&server);
+
+    let mapping = load_mapping(&client, None, false).await.unwrap();
+    let data = fetch_company(&client, None, &mapping, "gaap", &FetchOptions::default()).await.unwrap();
+
+    assert_eq!((data.ticker.as_str(), data.cik, data.taxonomy), ("GAAP", 1, Some(Taxonomy::UsGaap)));
+    assert_eq!(data.financials["Revenue"], vec![(2022, 100.0), (2023, 120.0)]);
+}
+
+#[tokio::test]
+async fn unknown_ticker_is_reported() {
+    let server = server().await;
+    let client = client(&server);
+
+    let mapping = load_mapping(&client, None, false).await.unwrap();
+    let err = fetch_company(&client, None, &mapping, "NOPE", &FetchOptions::default()).await.unwrap_err();
+
+    assert!(matches!(err, EngineError::TickerNotFound(t) if t == "NOPE"));
+}
+
+#[tokio::test]
+async fn retries_after_429() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyfacts/CIK0000000001.json"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).and(path("/api/xbrl/companyfacts/CIK0000000001.json")).respond_with(gaap_facts()).mount(&server).await;
+    let client = client(&server);
+
+    let mapping = load_mapping(&client, None, false).await.unwrap();
+    let data = fetch_company(&client, None, &mapping, "GAAP", &FetchOptions::default()).await.unwrap();
+
+    assert_eq!(data.financials["Revenue"].len(), 2);
+}
+
+#[test]
+fn retry_after_is_capped() {
+    assert_eq!(parse_retry_after(" 3 "), Some(Duration::from_secs(3)));
+    assert_eq!(parse_retry_after("60"), Some(MAX_RETRY_AFTER));
+    assert_eq!(parse_retry_after("86400"), Some(MAX_RETRY_AFTER));
+    assert_eq!(parse_retry_after(&u64::MAX.to_string()), Some(MAX_RETRY_AFTER));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+}
+
+#[tokio::test]
+async fn stalled_request_times_out_and_is_retried() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyfacts/CIK0000000001.json"))
+        .respond_with(gaap_facts().set_delay(Duration::from_secs(5)))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).and(path("/api/xbrl/companyfacts/CIK0000000001.json")).respond_with(gaap_facts()).mount(&server).await;
+    let http = HttpClient::with_timeout(1000.0, 1, "Tests tests@example.org", Duration::from_millis(200)).unwrap();
+    let client = SecClient::new(http, &server.uri(), &server.uri());
+
+    let data = fetch_company_by_cik(&client, None, 1, &FetchOptions::default()).await.unwrap();
+
+    assert_eq!(data.financials["Revenue"].len(), 2);
+}
+
+/// Serveur HTTP minimal dont la première réponse envoie ses headers puis bloque au milieu du
+/// corps, les suivantes étant complètes : wiremock ne sait retarder que la réponse entière.
+/// Rend l'URL du serveur et le compteur de connexions.
+fn stalling_body_server(body: Vec<u8>) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&connections);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let first = counter.fetch_add(1, Ordering::SeqCst) == 0;
+            let body = body.clone();
+            std::thread::spawn(move || {
+                let mut stream = stream;
+                let mut request = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while request.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+                    line.clear();
+                }
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+                let sent = if first { &body[..body.len() / 2] } else { &body[..] };
+                let _ = stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(sent)).and_then(|_| stream.flush());
+                if first {
+                    std::thread::sleep(Duration::from_secs(5));
+                }
+            });
+        }
+    });
+    (url, connections)
+}
+
+#[tokio::test]
+async fn body_stalled_after_the_headers_times_out_and_is_retried() {
+    let facts = json!({
+        "cik": 1, "entityName": "Gaap Corp",
+        "facts": { "us-gaap": { "Revenues": { "units": { "USD": [annual(100.0, 2022), annual(120.0, 2023)] } } } }
+    });
+    let (url, connections) = stalling_body_server(serde_json::to_vec(&facts).unwrap());
+    let http = HttpClient::with_timeout(1000.0, 1, "Tests tests@example.org", Duration::from_millis(200)).unwrap();
+    let client = SecClient::new(http, &url, &url);
+
+    let data = fetch_company_by_cik(&client, None, 1, &FetchOptions::default()).await.unwrap();
+
+    assert_eq!(data.financials["Revenue"].len(), 2);
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+    // Même politique pour le mapping
+    let mapping = json!({ "0": { "cik_str": 1, "ticker": "GAAP", "title": "Gaap Corp" } });
+    let (url, connections) = stalling_body_server(serde_json::to_vec(&mapping).unwrap());
+    let http = HttpClient::with_timeout(1000.0, 1, "Tests tests@example.org", Duration::from_millis(200)).unwrap();
+    let client = SecClient::new(http, &url, &url);
+
+    assert_eq!(load_mapping(&client, None, false).await.unwrap().len(), 1);
+    assert_eq!(connections.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn ifrs_only_filer_uses_ifrs_metrics() {
+    let server = server().await;
+    let client = client(&server);
+
+    let mapping = load_mapping(&client, None, false).await.unwrap();
+    let data = fetch_company(&client, None, &mapping, "IFRS", &FetchOptions::default()).await.unwrap();
+
+    assert_eq!(data.taxonomy, Some(Taxonomy::IfrsFull));
+    assert_eq!(data.financials["Revenue"], vec![(2023, 80.0)]);
+}
+
+#[tokio::test]
+async fn explicit_cik_skips_the_mapping() {
+    let server = server().await;
+    let client = client(&server);
+
+    let data = fetch_company_by_cik(&client, None, 2, &FetchOptions::default()).await.unwrap();
+
+    assert_eq!((data.ticker.as_str(), data.cik), ("0000000002", 2));
+    let mapping_hits = server.received_requests().await.unwrap().iter().filter(|r| r.url.path().starts_with("/files/")).count();
+    assert_eq!(mapping_hits, 0);
+}
+
+#[tokio::test]
+async fn truncated_facts_still_yield_ticker_and_cik() {
+    let server = server().await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyfacts/CIK0000000001.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"cik": 1, "entityName": "Gaap Corp", "facts": {"us-gaap": {"Rev"#))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let mapping = load_mapping(&client, None, false).await.unwrap();
+    let data = fetch_company(&client, None, &mapping, "GAAP", &FetchOptions::default()).await.unwrap();
+
+    assert_eq!((data.ticker.as_str(), data.cik), ("GAAP", 1));
+    assert!(data.financials.is_empty());
+    assert!(data.warning.unwrap().starts_with(MALFORMED_FACTS));
+}
+
+#[test]
+fn trailing_garbage_after_facts_is_ignored() {
+    let body = br#"{"cik": 1, "entityName": "Gaap Corp", "facts": {}} <html>"#;
+
+    let facts = parse_facts(body).unwrap();
+
+    assert_eq!((facts.cik, facts.entity_name.as_str()), (Some(1), "Gaap Corp"));
+}
+
+#[test]
+fn retaining_parse_drops_unconfigured_concepts_without_changing_results() {
+    let body = serde_json::to_vec(&json!({
+        "cik": 1, "entityName": "Gaap Corp",
+        "facts": {
+            "dei": { "EntityPublicFloat": { "units": { "USD": [{ "val": 5.0e9, "fy": 2023, "fp": "FY", "form": "10-K", "end": "2023-06-30" }] } } },
+            "us-gaap": {
+                "Revenues": { "units": { "USD": [annual(100.0, 2022), annual(120.0, 2023)] } },
+                "AccretionExpense": { "units": { "USD": [annual(3.0, 2023)] } }
+            },
+            "srt": { "Anything": { "units": { "USD": [annual(1.0, 2023)] } } }
+        }
+    })).unwrap();
+    let opts = FetchOptions::default();
+
+    let retained = parse_facts_retaining(&body, &opts.retained_concepts().unwrap()).unwrap();
+
+    let gaap = retained.facts.us_gaap.as_ref().unwrap();
+    assert!(gaap.contains_key("Revenues") && !gaap.contains_key("AccretionExpense"));
+    assert!(retained.facts.dei.as_ref().unwrap().contains_key("EntityPublicFloat"));
+    let full = build_company("GAAP".to_string(), 1, parse_facts(&body).unwrap(), &opts);
+    let lean = build_company("GAAP".to_string(), 1, retained, &opts);
+    assert_eq!(lean.financials, full.financials);
+    assert_eq!(lean.public_float, full.public_float);
+}
+
+#[tokio::test]
+async fn lean_mode_fetches_only_configured_concepts() {
+    let server = server().await;
+    Mock::given(method("GET")).and(path("/api/xbrl/companyfacts/CIK0000000001.json")).respond_with(gaap_facts()).expect(0).mount(&server).await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyconcept/CIK0000000001/us-gaap/Revenues.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "cik": 1, "taxonomy": "us-gaap", "tag": "Revenues", "entityName": "Gaap Corp",
+            "units": { "USD": [annual(100.0, 2022), annual(120.0, 2023)] }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyconcept/CIK0000000001/dei/EntityPublicFloat.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "cik": 1, "taxonomy": "dei", "tag": "EntityPublicFloat", "entityName": "Gaap Corp",
+            "units": { "USD": [{ "val": 5.0e9, "fy": 2023, "fp": "FY", "form": "10-K", "end": "2023-06-30" }] }
+        })))
+        .mount(&server)
+        .await;
+    let metrics = MetricsConfig::from_toml("mode = \"replace\"\n[[metric]]\nname = \"Revenue\"\ntags = [\"Revenues\", \"SalesRevenueNet\"]\nunit = \"monetary\"").unwrap();
+    let opts = FetchOptions { lean: true, metrics, ..Default::default() };
+
+    // Les concepts non publiés (404) sont simplement absents
+    let data = fetch_company_by_cik(&client(&server), None, 1, &opts).await.unwrap();
+
+    assert_eq!(data.taxonomy, Some(Taxonomy::UsGaap));
+    assert_eq!(data.financials["Revenue"], vec![(2022, 100.0), (2023, 120.0)]);
+    assert_eq!(data.public_float.map(|p| p.value), Some(5.0e9));
+}
+
+#[tokio::test]
+async fn lean_mode_falls_back_to_companyfacts_for_large_configs() {
+    let server = server().await;
+    Mock::given(method("GET")).and(path("/api/xbrl/companyfacts/CIK0000000001.json")).respond_with(gaap_facts()).expect(1).mount(&server).await;
+    let opts = FetchOptions { lean: true, ..Default::default() };
+    assert!(opts.metrics.us_gaap.iter().map(|def| def.tags.len()).sum::<usize>() > LEAN_MAX_CONCEPTS);
+
+    let data = fetch_company_by_cik(&client(&server), None, 1, &opts).await.unwrap();
+
+    assert_eq!(data.financials["Revenue"], vec![(2022, 100.0), (2023, 120.0)]);
+}
+
+/// Extraction suivie de l'archivage `--diff`, comme la CLI (après l'éventuelle conversion).
+async fn fetch_with_diff(server: &MockServer, cache: &Cache, cik: u64, opts: &FetchOptions) -> CompanyFinancials {
+    let mut data = fetch_company_by_cik(&client(server), Some(cache), cik, opts).await.unwrap();
+    if opts.diff {
+        record_snapshot(cache, &mut data, opts);
+    }
+    data
+}
+
+#[tokio::test]
+async fn diff_reports_restated_added_and_removed_years_since_the_last_snapshot() {
+    let dir = std::env::temp_dir().join(format!("edgar_fetcher_diff_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir);
+    let server = server().await;
+    Mock::given(method("GET")).and(path("/api/xbrl/companyfacts/CIK0000000001.json")).respond_with(gaap_facts()).mount(&server).await;
+    let opts = FetchOptions { diff: true, ..Default::default() };
+
+    // Sans --diff, rien n'est archivé
+    let plain = fetch_with_diff(&server, &cache, 1, &FetchOptions::default()).await;
+    assert!(plain.diff.is_none());
+    assert!(cache.latest_snapshot(1, &opts.snapshot_options(&plain)).is_none());
+
+    let first = fetch_with_diff(&server, &cache, 1, &opts).await;
+    assert_eq!(first.diff, Some(SnapshotDiff::default()));
+    // Une extraction tronquée par --years ne sert pas de référence à l'historique complet
+    let truncated = FetchOptions { years: Some(1), ..opts.clone() };
+    let last_year = fetch_with_diff(&server, &cache, 1, &truncated).await;
+    assert_eq!(last_year.diff, Some(SnapshotDiff::default()));
+
+    // 2022 retiré, 2023 retraité, 2024 publié
+    server.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/api/xbrl/companyfacts/CIK0000000001.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "cik": 1, "entityName": "Gaap Corp",
+            "facts": { "us-gaap": { "Revenues": { "units": { "USD": [annual(125.0, 2023), annual(140.0, 2024)] } } } }
+        })))
+        .mount(&server)
+        .await;
+    let second = fetch_with_diff(&server, &cache, 1, &opts).await;
+
+    let diff = second.diff.as_ref().unwrap();
+    assert!(diff.since.is_some());
+    assert_eq!(diff.changed["Revenue"], vec![ValueChange { fiscal_year: 2023, old: 120.0, new: 125.0 }]);
+    assert_eq!(diff.added["Revenue"], vec![2024]);
+    assert_eq!(diff.removed["Revenue"], vec![2022]);
+    assert_eq!(cache.latest_snapshot(1, &opts.snapshot_options(&second)).unwrap().financials["Revenue"], vec![(2023, 125.0), (2024, 140.0)]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn diff_does_not_compare_converted_figures_with_an_original_currency_snapshot() {
+    let dir = std::env::temp_dir().join(format!("edgar_fetcher_diff_fx_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir);
+    let server = server().await;
+    let opts = FetchOptions { diff: true, ..Default::default() };
+
+    // Archive en EUR, puis extraction convertie en USD (taux 1,1)
+    let eur = fetch_with_diff(&server, &cache, 2, &opts).await;
+    assert_eq!(eur.reporting_currency.as_deref(), Some("EUR"));
+    let mut usd = fetch_company_by_cik(&client(&server), Some(&cache), 2, &opts).await.unwrap();
+    usd.financials.insert("Revenue".to_string(), vec![(2023, 88.0)]);
+    usd.fx = Some(FxConversion { to: "USD".to_string(), rates: BTreeMap::new() });
+    record_snapshot(&cache, &mut usd, &opts);
+
+    // Pas de référence en USD : aucun « retraitement » 80 -> 88
+    assert_eq!(usd.diff, Some(SnapshotDiff::default()));
+    assert_eq!(cache.latest_snapshot(2, &opts.snapshot_options(&eur)).unwrap().financials["Revenue"], vec![(2023, 80.0)]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn offline_mode_reads_only_the_cache() {
+    let dir = std::env::temp_dir().join(format!("edgar_fetcher_offline_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir);
+    let server = MockServer::start().await;
+    let opts = FetchOptions { offline: true, ..Default::default() };
+    assert!(matches!(load_cached_mapping(Some(&cache)), Err(EngineError::MappingNotCached)));
+
+    let body = serde_json::to_vec(&json!({
+        "cik": 1, "entityName": "Gaap Corp",
+        "facts": { "us-gaap": { "Revenues": { "units": { "USD": [annual(100.0, 2023)] } } } }
+    }))
+    .unwrap();
+    cache.store_facts("0000000001", &body, None, None);
+    cache.store_mapping(&[edgar_fetcher::models::TickerEntry { cik_str: 1, ticker: "GAAP".to_string(), title: "Gaap Corp".to_string() }]);
+
+    let mapping = load_cached_mapping(Some(&cache)).unwrap();
+    let data = fetch_company(&client(&server), Some(&cache), &mapping, "GAAP", &opts).await.unwrap();
+    let missing = fetch_company_by_cik(&client(&server), Some(&cache), 2, &opts).await;
+
+    assert_eq!(data.financials["Revenue"], vec![(2023, 100.0)]);
+    assert!(matches!(missing, Err(EngineError::CacheMiss(2))), "{:?}", missing);
+    assert!(server.received_requests().await.unwrap().is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
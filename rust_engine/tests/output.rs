@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::models::CompanyFinancials;
+use edgar_fetcher::extract::US_GAAP_METRICS;
+use edgar_fetcher::metrics::MetricsConfig;
+use edgar_fetcher::output::{
+    format_number, to_csv, to_csv_batch_with, to_json_string, EngineOutput, MetricKinds, MetricSelection, MetricSort, NumberKind, OrderedSeries,
+    Rounding,
+};
+use serde_json::{json, Value};
+
+fn company(financials: HashMap<String, Vec<(u16, f64)>>) -> CompanyFinancials {
+    CompanyFinancials {
+        ticker: "TEST".to_string(),
+        cik: 1,
+        name: "Test Corp".to_string(),
+        financials,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn csv_pivots_years_into_columns_with_blanks() {
+    let data = company(HashMap::from([
+        ("Revenue".to_string(), vec![(2021, 10.0), (2022, 12.5)]),
+        ("Cash & Equiv.".to_string(), vec![(2022, 3.0), (2023, 4.0)]),
+    ]));
+
+    assert_eq!(
+        to_csv(&data),
+        "ticker,metric,2021,2022,2023\nTEST,Cash & Equiv.,,3,4\nTEST,Revenue,10,12.5,\n"
+    );
+}
+
+#[test]
+fn table_numbers_are_abbreviated_with_sign() {
+    assert_eq!(format_number(383_285_000_000.0), "383.3B");
+    assert_eq!(format_number(-2_500_000.0), "-2.5M");
+    assert_eq!(format_number(2_345_600_000_000.0), "2,345.6B");
+    assert_eq!(format_number(6.13), "6.13");
+}
+
+#[test]
+fn suffix_is_chosen_after_rounding() {
+    assert_eq!(format_number(999_950.0), "1.0M");
+    assert_eq!(format_number(-999_950.0), "-1.0M");
+    assert_eq!(format_number(999_949.0), "999.9K");
+    assert_eq!(format_number(999.995), "1.0K");
+    assert_eq!(format_number(999.99), "999.99");
+    assert_eq!(format_number(999_950_000.0), "1.0B");
+    // Pas de suffixe au-delà de B
+    assert_eq!(format_number(999_960_000_000.0), "1,000.0B");
+}
+
+#[test]
+fn output_schema_lists_the_contract_fields() {
+    let schema = serde_json::to_value(schemars::schema_for!(EngineOutput)).unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    for key in ["ticker", "cik", "financials", "ratios", "leverage", "valuation", "scores", "data_quality", "warning"] {
+        assert!(properties.contains_key(key), "champ absent du schéma : {}", key);
+    }
+    let required: Vec<&str> = schema["required"].as_array().unwrap().iter().filter_map(|v| v.as_str()).collect();
+    assert!(required.contains(&"ticker") && !required.contains(&"warning"));
+}
+
+#[test]
+fn selection_filters_and_orders_metrics() {
+    let data = company(HashMap::from([
+        ("Revenue".to_string(), vec![(2022, 10.0)]),
+        ("Net Income".to_string(), vec![(2022, 2.0)]),
+        ("Free Cash Flow".to_string(), vec![(2022, 1.0)]),
+        ("CapEx".to_string(), vec![(2022, 3.0)]),
+    ]));
+    let selection = MetricSelection {
+        only: Some(vec!["Free Cash Flow".to_string(), "Net Income".to_string(), "Revenue".to_string()]),
+        sort: MetricSort::Config,
+    };
+
+    // Ordre de la config (Revenue avant Net Income), dérivées ensuite
+    let ordered = OrderedSeries::select(&data.financials, &selection, US_GAAP_METRICS);
+    let names: Vec<&str> = ordered.0.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["Revenue", "Net Income", "Free Cash Flow"]);
+    let json = serde_json::to_string(&ordered).unwrap();
+    assert!(json.starts_with(r#"{"Revenue":"#), "{}", json);
+
+    let alphabetical = MetricSelection { sort: MetricSort::Alphabetical, ..selection };
+    assert_eq!(
+        to_csv_batch_with(&[data], &MetricsConfig::default(), &alphabetical),
+        "ticker,metric,2022\nTEST,Free Cash Flow,1\nTEST,Net Income,2\nTEST,Revenue,10\n"
+    );
+}
+
+#[test]
+fn rounding_applies_significant_figures_to_amounts_and_decimals_to_ratios() {
+    let round = Rounding::significant(3);
+    assert_eq!(round.apply(383285000000.0, NumberKind::Amount), 383000000000.0);
+    assert_eq!(round.apply(-2_546_000.0, NumberKind::Amount), -2_550_000.0);
+    // Petit montant (société publiant en milliers) : chiffres significatifs, pas de décimales
+    assert_eq!(round.apply(412.6, NumberKind::Amount), 413.0);
+    assert_eq!(round.apply(3.2899999999, NumberKind::Decimal), 3.29);
+    // Grand montant par action : décimales, pas de chiffres significatifs
+    assert_eq!(round.apply(541234.56789, NumberKind::Decimal), 541234.5679);
+    assert_eq!(round.apply(-0.00001, NumberKind::Decimal), 0.0);
+    // Pleine précision par défaut
+    assert_eq!(Rounding::default(), Rounding::RAW);
+    assert_eq!(Rounding::default().apply(383285000001.5, NumberKind::Amount), 383285000001.5);
+    assert_eq!(Rounding::RAW.apply(3.2899999999, NumberKind::Decimal), 3.2899999999);
+}
+
+#[test]
+fn json_rounding_keeps_integers_and_key_order() {
+    let series = OrderedSeries(vec![("Revenue".to_string(), vec![(2023u16, 383285000000.0)]), ("EPS Diluted".to_string(), vec![(2023, 6.1299999)])]);
+    let kinds = MetricKinds::new(&MetricsConfig::default());
+
+    assert_eq!(to_json_string(&series, false, Rounding::significant(2), &kinds).unwrap(), r#"{"Revenue":[[2023,380000000000.0]],"EPS Diluted":[[2023,6.13]]}"#);
+    assert!(to_json_string(&series, true, Rounding::RAW, &kinds).unwrap().contains("6.1299999"));
+}
+
+#[test]
+fn json_rounding_follows_the_metric_kind_rather_than_the_magnitude() {
+    let output = json!({
+        "financials": { "Revenue": [[2023, 412.6]], "EPS Diluted": [[2023, 541234.56789]], "Unknown": [[2023, 1.23456789]] },
+        "ratios": { "Revenue": [[2023, 1234.56789]] },
+        "valuation": { "enterprise_value": { "enterprise_value": 383285000000.0, "ev_to_ebit": 12.3456789 } },
+        "data_quality": { "Revenue": { "outliers": [{ "value": 383285000000.0 }] } },
+    });
+    let kinds = MetricKinds::new(&MetricsConfig::default());
+
+    let rounded: Value = serde_json::from_str(&to_json_string(&output, false, Rounding::significant(3), &kinds).unwrap()).unwrap();
+    assert_eq!(rounded["financials"]["Revenue"], json!([[2023, 413.0]]));
+    assert_eq!(rounded["financials"]["EPS Diluted"], json!([[2023, 541234.5679]]));
+    // Nature inconnue : pleine précision
+    assert_eq!(rounded["financials"]["Unknown"], json!([[2023, 1.23456789]]));
+    // Section de ratios, même pour une série qui porte le nom d'un montant
+    assert_eq!(rounded["ratios"]["Revenue"], json!([[2023, 1234.5679]]));
+    assert_eq!(rounded["valuation"]["enterprise_value"], json!({ "enterprise_value": 383000000000.0, "ev_to_ebit": 12.3457 }));
+    assert_eq!(rounded["data_quality"], output["data_quality"]);
+}
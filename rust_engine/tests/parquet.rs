@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::fs::File;
+
+use arrow_array::{Float64Array, StringArray, UInt16Array};
+use edgar_fetcher::extract::MetricQuality;
+use edgar_fetcher::models::CompanyFinancials;
+use edgar_fetcher::parquet_export::{export_parquet, parquet_schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+#[test]
+fn long_format_rows_round_trip_with_their_unit() {
+    let company = CompanyFinancials {
+        ticker: "TEST".to_string(),
+        cik: 42,
+        name: "Test Corp".to_string(),
+        financials: HashMap::from([
+            ("Revenue".to_string(), vec![(2022, 10.0), (2023, 11.0)]),
+            ("Free Cash Flow".to_string(), vec![(2023, 3.0)]),
+        ]),
+        data_quality: HashMap::from([("Revenue".to_string(), MetricQuality { unit: Some("USD".to_string()), ..Default::default() })]),
+        ..Default::default()
+    };
+    let path = std::env::temp_dir().join(format!("edgar_fetcher_{}.parquet", std::process::id()));
+
+    assert_eq!(export_parquet(&path, &[company]).unwrap(), 3);
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.schema().fields(), parquet_schema().fields());
+    let batch = reader.build().unwrap().next().unwrap().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+    let metrics = column("metric");
+    let metrics = metrics.as_any().downcast_ref::<StringArray>().unwrap();
+    let years = column("fiscal_year");
+    let years = years.as_any().downcast_ref::<UInt16Array>().unwrap();
+    let values = column("value");
+    let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+    let units = column("unit");
+    let units = units.as_any().downcast_ref::<StringArray>().unwrap();
+
+    assert_eq!(metrics.iter().flatten().collect::<Vec<_>>(), ["Free Cash Flow", "Revenue", "Revenue"]);
+    assert_eq!(years.values().to_vec(), [2023, 2022, 2023]);
+    assert_eq!(values.values().to_vec(), [3.0, 10.0, 11.0]);
+    assert_eq!(units.iter().collect::<Vec<_>>(), [None, Some("USD"), Some("USD")]);
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::models::CompanyFinancials;
+use edgar_fetcher::peers::{parse_ticker_list, peer_ranks, peer_stats, PeerRank};
+
+fn company(ticker: &str, net_income: f64) -> CompanyFinancials {
+    CompanyFinancials {
+        ticker: ticker.to_string(),
+        financials: HashMap::from([
+            ("Revenue".to_string(), vec![(2022, 100.0), (2023, 100.0)]),
+            ("Net Income".to_string(), vec![(2022, 1.0), (2023, net_income)]),
+        ]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn stats_use_each_members_latest_year() {
+    let stats = peer_stats(&[company("A", 10.0), company("B", 20.0), company("C", 60.0)]);
+    let margin = &stats["Net Margin"];
+    assert_eq!(margin.members.len(), 3);
+    assert!((margin.median - 0.2).abs() < 1e-12);
+    assert!((margin.mean - 0.3).abs() < 1e-12);
+    assert!(!stats.contains_key("ROE"));
+}
+
+#[test]
+fn ticker_list_skips_blanks_and_comments() {
+    let text = "# écran value\nAAPL\n\n  msft  # à surveiller\nBRK.B, KO\n";
+
+    assert_eq!(parse_ticker_list(text), vec!["AAPL", "msft", "BRK.B", "KO"]);
+}
+
+#[test]
+fn ranks_exclude_the_company_from_its_peers() {
+    let peers = [company("A", 10.0), company("B", 20.0), company("C", 60.0), company("D", 20.0)];
+    let stats = peer_stats(&peers);
+
+    let ranks = peer_ranks(&peers[1], &stats);
+
+    // Marge de B (20 %) : au-dessus de A, ex-aequo avec D, sous C
+    assert_eq!(ranks["Net Margin"], PeerRank { value: 0.2, percentile: 50.0, peers: 3 });
+    assert!(!ranks.contains_key("ROA"));
+}
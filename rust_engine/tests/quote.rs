@@ -0,0 +1,44 @@
+use edgar_fetcher::http::HttpClient;
+use edgar_fetcher::quote::{stooq_symbol, CachedQuotes, Stooq};
+use chrono::NaiveDate;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn quotes(server: &MockServer) -> CachedQuotes<Stooq> {
+    let http = HttpClient::new(1000.0, 0, "Tests tests@example.org").unwrap();
+    CachedQuotes::new(Stooq::new(http, &server.uri()), None)
+}
+
+#[tokio::test]
+async fn stooq_close_is_read_and_cached_for_the_batch() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/q/l/"))
+        .and(query_param("s", "brk-b.us"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Symbol,Date,Close\r\nBRK-B.US,2024-05-17,415.5\r\n"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let quotes = quotes(&server);
+
+    let quote = quotes.quote("BRK.B").await.unwrap();
+    assert_eq!((quote.symbol.as_str(), quote.price), ("brk-b.us", 415.5));
+    assert_eq!(quote.date, NaiveDate::from_ymd_opt(2024, 5, 17));
+    // Deuxième demande servie par le cache : `expect(1)` échoue sinon
+    assert_eq!(quotes.quote("BRK.B").await.unwrap(), quote);
+}
+
+#[tokio::test]
+async fn unknown_symbol_is_an_unavailable_quote() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/q/l/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("Symbol,Date,Close\r\nZZZZ.US,N/D,N/D\r\n"))
+        .mount(&server)
+        .await;
+
+    let err = quotes(&server).quote("ZZZZ").await.unwrap_err();
+
+    assert!(err.to_string().contains("ZZZZ"), "{}", err);
+    assert_eq!(stooq_symbol(" aapl "), "aapl.us");
+}
@@ -0,0 +1,69 @@
+use edgar_fetcher::models::TickerEntry;
+use edgar_fetcher::sec::parse_mapping;
+use edgar_fetcher::{normalize_ticker, pad_cik, parse_cik, resolve_cik, EngineError};
+
+fn mapping() -> Vec<TickerEntry> {
+    [
+        (320193, "AAPL", "Apple Inc."),
+        (1067983, "BRK-B", "BERKSHIRE HATHAWAY INC"),
+        (1067983, "BRK-A", "BERKSHIRE HATHAWAY INC"),
+        (14693, "BF-B", "BROWN FORMAN CORP"),
+    ]
+    .into_iter()
+    .map(|(cik, ticker, title)| TickerEntry { cik_str: cik, ticker: ticker.to_string(), title: title.to_string() })
+    .collect()
+}
+
+#[test]
+fn share_class_tickers_accept_dot_notation() {
+    let mapping = mapping();
+    assert_eq!(resolve_cik(&mapping, "BRK.B").unwrap(), 1067983);
+    assert_eq!(resolve_cik(&mapping, "bf.b").unwrap(), 14693);
+    assert_eq!(resolve_cik(&mapping, "BRK-A").unwrap(), 1067983);
+}
+
+#[test]
+fn single_class_tickers_still_resolve() {
+    let mapping = mapping();
+    assert_eq!(resolve_cik(&mapping, " aapl ").unwrap(), 320193);
+    assert!(matches!(resolve_cik(&mapping, "MSFT"), Err(EngineError::TickerNotFound(t)) if t == "MSFT"));
+}
+
+#[test]
+fn normalization_is_canonical() {
+    assert_eq!(normalize_ticker(" brk.b "), "BRK-B");
+    assert_eq!(normalize_ticker("AAPL"), "AAPL");
+}
+
+#[test]
+fn explicit_ciks_are_validated_and_padded() {
+    assert_eq!(parse_cik("0000320193").unwrap(), 320193);
+    assert_eq!(parse_cik("CIK320193").unwrap(), 320193);
+    assert_eq!(pad_cik(320193), "0000320193");
+    assert!(parse_cik("AAPL").is_err());
+    assert!(parse_cik("12345678901").is_err());
+}
+
+#[test]
+fn mapping_tolerates_altered_shapes() {
+    // Champ ajouté, CIK en chaîne, titre absent, entrée corrompue
+    let indexed = br#"{
+        "0": { "cik_str": 320193, "ticker": "AAPL", "title": "Apple Inc.", "exchange": "Nasdaq" },
+        "1": { "cik_str": "0001067983", "ticker": "BRK-B" },
+        "2": { "ticker": "NOCIK" }
+    }"#;
+    let mut entries = parse_mapping(indexed).unwrap();
+    entries.sort_by_key(|e| e.cik_str);
+    let summary: Vec<(u64, &str, &str)> = entries.iter().map(|e| (e.cik_str, e.ticker.as_str(), e.title.as_str())).collect();
+    assert_eq!(summary, vec![(320193, "AAPL", "Apple Inc."), (1067983, "BRK-B", "")]);
+
+    let table = br#"{ "fields": ["cik", "name", "ticker", "exchange"], "data": [[320193, "Apple Inc.", "AAPL", "Nasdaq"]] }"#;
+    let entries = parse_mapping(table).unwrap();
+    assert_eq!(resolve_cik(&entries, "aapl").unwrap(), 320193);
+    assert_eq!(entries[0].title, "Apple Inc.");
+
+    let listed = br#"[{ "cik": 789019, "ticker": "MSFT", "title": "Microsoft" }]"#;
+    assert_eq!(parse_mapping(listed).unwrap()[0].cik_str, 789019);
+
+    assert!(matches!(parse_mapping(br#"{ "status": "maintenance" }"#), Err(EngineError::UnrecognizedMapping)));
+}
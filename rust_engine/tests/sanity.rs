@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::extract::{DataQuality, MetricQuality};
+use edgar_fetcher::sanity::{check_outliers, Outlier, DEFAULT_OUTLIER_FACTOR};
+
+fn sample() -> (HashMap<String, Vec<(u16, f64)>>, DataQuality) {
+    let financials = HashMap::from([
+        // BPA 2022 publié en cents au lieu de dollars
+        ("EPS Diluted".to_string(), vec![(2020, 4.0), (2021, 5.0), (2022, 10_000.0), (2023, 6.0)]),
+        ("Revenue".to_string(), vec![(2021, 100.0), (2022, 4_000.0), (2023, 9_000.0)]),
+        // Métrique dérivée : absente du diagnostic, jamais contrôlée
+        ("Free Cash Flow".to_string(), vec![(2021, 1.0), (2022, 1.0), (2023, 1_000.0)]),
+    ]);
+    let quality = ["EPS Diluted", "Revenue"].map(|name| (name.to_string(), MetricQuality::default())).into();
+    (financials, quality)
+}
+
+#[test]
+fn outliers_are_flagged_without_altering_values() {
+    let (mut financials, mut quality) = sample();
+    let before = financials.clone();
+
+    check_outliers(&mut financials, &mut quality, DEFAULT_OUTLIER_FACTOR, false);
+
+    assert_eq!(financials, before);
+    assert_eq!(quality["EPS Diluted"].outliers, vec![Outlier { fiscal_year: 2022, value: 10_000.0, median: 5.5, dropped: false }]);
+    // Croissance forte mais plausible (x90 sur deux ans, x2,25 la médiane)
+    assert!(quality["Revenue"].outliers.is_empty());
+}
+
+#[test]
+fn strict_drops_outliers_and_records_them() {
+    let (mut financials, mut quality) = sample();
+
+    check_outliers(&mut financials, &mut quality, DEFAULT_OUTLIER_FACTOR, true);
+
+    assert_eq!(financials["EPS Diluted"], vec![(2020, 4.0), (2021, 5.0), (2023, 6.0)]);
+    assert!(quality["EPS Diluted"].outliers[0].dropped);
+    assert_eq!(financials["Free Cash Flow"].len(), 3);
+}
+
+#[test]
+fn values_far_below_the_median_are_flagged_too() {
+    // Chiffre d'affaires 2022 publié en milliers au lieu d'unités
+    let mut financials = HashMap::from([("Revenue".to_string(), vec![(2020, 1_000_000.0), (2021, 1_100_000.0), (2022, 1_200.0), (2023, 1_300_000.0)])]);
+    let mut quality: DataQuality = [("Revenue".to_string(), MetricQuality::default())].into();
+
+    check_outliers(&mut financials, &mut quality, DEFAULT_OUTLIER_FACTOR, false);
+
+    assert_eq!(quality["Revenue"].outliers, vec![Outlier { fiscal_year: 2022, value: 1_200.0, median: 1_050_000.0, dropped: false }]);
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::models::CompanyFinancials;
+use edgar_fetcher::scores::{altman_z, altman_zone, piotroski, piotroski_fscore};
+
+fn company(series: &[(&str, f64, f64)]) -> CompanyFinancials {
+    CompanyFinancials {
+        financials: series
+            .iter()
+            .map(|&(name, prev, cur)| (name.to_string(), vec![(2022, prev), (2023, cur)]))
+            .collect::<HashMap<_, _>>(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn piotroski_scores_each_criterion_on_last_two_years() {
+    let data = company(&[
+        ("Net Income", 80.0, 100.0),
+        ("Operating Cash Flow", 90.0, 120.0),
+        ("Total Assets", 1000.0, 1000.0),
+        ("Total Current Assets", 300.0, 300.0),
+        ("Total Current Liabilities", 200.0, 250.0),
+        ("Long Term Debt", 500.0, 400.0),
+        ("Shares Outstanding", 100.0, 105.0),
+        ("Gross Profit", 400.0, 450.0),
+        ("Revenue", 1000.0, 1000.0),
+    ]);
+    let result = piotroski(&data).unwrap();
+    let failed: Vec<&str> = result.criteria.iter().filter(|c| !c.passed).map(|c| c.name).collect();
+
+    assert_eq!(result.fiscal_year, 2023);
+    assert_eq!(failed, ["Rising Current Ratio", "No Dilution", "Rising Asset Turnover"]);
+    assert_eq!(result.score, 6);
+}
+
+#[test]
+fn piotroski_needs_two_years_of_data() {
+    let mut data = company(&[("Net Income", 80.0, 100.0)]);
+    assert_eq!(piotroski_fscore(&data), None);
+    data.financials.insert("Net Income".to_string(), vec![(2023, 100.0)]);
+    assert_eq!(piotroski_fscore(&data), None);
+}
+
+#[test]
+fn altman_z_combines_five_ratios_of_latest_year() {
+    let mut data = company(&[
+        ("Total Assets", 900.0, 1000.0),
+        ("Total Liabilities", 500.0, 500.0),
+        ("Total Current Assets", 400.0, 400.0),
+        ("Total Current Liabilities", 200.0, 200.0),
+        ("Operating Income (EBIT)", 90.0, 100.0),
+        ("Total Equity", 400.0, 500.0),
+        ("Revenue", 1000.0, 1000.0),
+    ]);
+    assert_eq!(altman_z(&data), None);
+
+    data.financials.insert("Retained Earnings".to_string(), vec![(2023, 300.0)]);
+    // 1.2*0.2 + 1.4*0.3 + 3.3*0.1 + 0.6*1.0 + 1.0 = 2.59
+    let z = altman_z(&data).unwrap();
+    assert!((z - 2.59).abs() < 1e-9, "{}", z);
+    assert_eq!(altman_zone(z), "grey");
+}
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::splits::{adjust_splits, SplitEvent};
+
+#[test]
+fn apple_2020_four_for_one_split_is_back_adjusted() {
+    // Apple, exercices clos fin septembre. Le nombre d'actions (page de garde) saute en 2020 ;
+    // le 10-K 2020 a déjà retraité le BPA 2018-2019, la rupture du BPA est donc entre 2017 et 2018.
+    let mut financials = HashMap::from([
+        ("Shares Outstanding".to_string(), vec![(2017, 5.13e9), (2018, 4.75e9), (2019, 4.44e9), (2020, 16.98e9), (2021, 16.41e9)]),
+        ("EPS Diluted".to_string(), vec![(2017, 9.21), (2018, 2.98), (2019, 2.97), (2020, 3.28), (2021, 5.61)]),
+    ]);
+
+    let events = adjust_splits(&mut financials);
+
+    assert_eq!(events, vec![SplitEvent { fiscal_year: 2020, ratio: 4.0 }]);
+    let shares = &financials["Shares Outstanding"];
+    assert_eq!(shares[2], (2019, 4.44e9 * 4.0));
+    assert_eq!(shares[3], (2020, 16.98e9));
+    let eps = &financials["EPS Diluted"];
+    assert!((eps[0].1 - 9.21 / 4.0).abs() < 1e-9);
+    assert_eq!(&eps[1..], &[(2018, 2.98), (2019, 2.97), (2020, 3.28), (2021, 5.61)]);
+}
+
+#[test]
+fn ordinary_buybacks_are_not_splits() {
+    let mut financials = HashMap::from([
+        ("Shares Outstanding".to_string(), vec![(2021, 100.0), (2022, 92.0), (2023, 120.0)]),
+    ]);
+    assert!(adjust_splits(&mut financials).is_empty());
+    assert_eq!(financials["Shares Outstanding"], vec![(2021, 100.0), (2022, 92.0), (2023, 120.0)]);
+}
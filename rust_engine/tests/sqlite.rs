@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::models::CompanyFinancials;
+use edgar_fetcher::sqlite::export_sqlite;
+use rusqlite::Connection;
+
+fn company(revenue: Vec<(u16, f64)>) -> CompanyFinancials {
+    CompanyFinancials {
+        ticker: "TEST".to_string(),
+        cik: 42,
+        name: "Test Corp".to_string(),
+        financials: HashMap::from([("Revenue".to_string(), revenue)]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn rerunning_a_ticker_replaces_its_rows() {
+    let path = std::env::temp_dir().join(format!("edgar_fetcher_{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    export_sqlite(&path, &[company(vec![(2022, 10.0), (2023, 11.0)])]).unwrap();
+    export_sqlite(&path, &[company(vec![(2023, 12.0), (2024, 13.0)])]).unwrap();
+
+    let conn = Connection::open(&path).unwrap();
+    let rows: Vec<(u16, f64)> = conn
+        .prepare("SELECT fiscal_year, value FROM financials WHERE ticker = 'TEST' ORDER BY fiscal_year")
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(rows, vec![(2022, 10.0), (2023, 12.0), (2024, 13.0)]);
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use edgar_fetcher::models::PeriodValue;
+
+use edgar_fetcher::valuation::{dcf, enterprise_value, graham_number, graham_valuation, multiples, normalized_earnings};
+
+#[test]
+fn dcf_discounts_projection_and_terminal_value() {
+    // FCF 100, sans croissance sur 2 ans, actualisé à 10 %, croissance terminale nulle :
+    // 100/1.1 + 100/1.21 + (100/0.1)/1.21 = 1000 ; sur 10 actions -> 100 par action.
+    let value = dcf(&[(2022, 80.0), (2023, 100.0)], 0.0, 0.10, 0.0, 2, 10.0);
+    assert!((value - 100.0).abs() < 1e-9, "{}", value);
+
+    assert!(dcf(&[(2023, 100.0)], 0.05, 0.02, 0.03, 5, 10.0).is_nan());
+    assert!(dcf(&[], 0.05, 0.10, 0.02, 5, 10.0).is_nan());
+}
+
+#[test]
+fn graham_number_uses_latest_year_with_all_inputs() {
+    assert_eq!(graham_number(-1.0, 10.0), None);
+    assert_eq!(graham_number(2.0, 0.0), None);
+
+    let results = HashMap::from([
+        ("EPS Diluted".to_string(), vec![(2022, 2.0), (2023, 2.5)]),
+        ("Total Equity".to_string(), vec![(2022, 1000.0)]),
+        ("Shares Outstanding".to_string(), vec![(2022, 100.0), (2023, 110.0)]),
+    ]);
+    let graham = graham_valuation(&results).unwrap();
+    assert_eq!((graham.fiscal_year, graham.book_value_per_share), (2022, 10.0));
+    assert!((graham.graham_number.unwrap() - 450f64.sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn enterprise_value_needs_a_price_for_the_equity_part() {
+    let results = HashMap::from([
+        ("Total Debt".to_string(), vec![(2023, 300.0)]),
+        ("Cash & Equiv.".to_string(), vec![(2023, 100.0)]),
+        ("Shares Outstanding".to_string(), vec![(2023, 10.0)]),
+        ("Operating Income (EBIT)".to_string(), vec![(2023, 60.0)]),
+        ("Revenue".to_string(), vec![(2023, 600.0)]),
+    ]);
+
+    let without_price = enterprise_value(&results, None).unwrap();
+    assert_eq!((without_price.total_debt, without_price.cash, without_price.enterprise_value), (300.0, 100.0, None));
+
+    let ev = enterprise_value(&results, Some(40.0)).unwrap();
+    assert_eq!((ev.market_cap, ev.enterprise_value), (Some(400.0), Some(600.0)));
+    assert_eq!((ev.ev_to_ebit, ev.ev_to_revenue), (Some(10.0), Some(1.0)));
+}
+
+#[test]
+fn normalized_earnings_use_the_median_of_the_last_years() {
+    let results = HashMap::from([
+        // 2021 : dépréciation exceptionnelle ; 2018 hors fenêtre de 4 ans
+        ("Net Income".to_string(), vec![(2018, 500.0), (2020, 100.0), (2021, -300.0), (2022, 120.0), (2023, 110.0)]),
+        ("EPS Diluted".to_string(), vec![(2022, 2.0), (2023, 3.0)]),
+    ]);
+
+    let normalized = normalized_earnings(&results, 4, Some(50.0));
+
+    let ni = &normalized.metrics["Net Income"];
+    assert_eq!((ni.first_year, ni.last_year, ni.years_used), (2020, 2023, 4));
+    assert_eq!(ni.median, 105.0);
+    assert_eq!(ni.mean, 7.5);
+    assert_eq!(normalized.metrics["EPS Diluted"].median, 2.5);
+    assert_eq!(normalized.normalized_pe, Some(20.0));
+    assert!(!normalized.metrics.contains_key("Free Cash Flow"));
+}
+
+#[test]
+fn multiples_prefer_ttm_eps_and_skip_non_positive_denominators() {
+    let results = HashMap::from([
+        ("EPS Diluted".to_string(), vec![(2023, 4.0)]),
+        ("Book Value Per Share".to_string(), vec![(2023, 25.0)]),
+        ("Shares Outstanding".to_string(), vec![(2023, 10.0)]),
+        ("Free Cash Flow".to_string(), vec![(2023, -5.0)]),
+        ("Dividends Paid".to_string(), vec![(2023, 20.0)]),
+    ]);
+    let ttm = HashMap::from([("EPS Diluted".to_string(), PeriodValue { period: "2024-Q2".to_string(), value: 5.0 })]);
+
+    let with_ttm = multiples(&results, Some(&ttm), 100.0);
+    assert_eq!((with_ttm.eps_basis, with_ttm.pe), (Some("ttm"), Some(20.0)));
+    assert_eq!(with_ttm.pb, Some(4.0));
+    assert_eq!(with_ttm.p_fcf, None);
+    // Pas de dividende par action : dividendes versés / capitalisation (20 / 1000)
+    assert_eq!(with_ttm.dividend_yield, Some(0.02));
+
+    let annual = multiples(&results, None, 100.0);
+    assert_eq!((annual.eps_basis, annual.pe), (Some("annual"), Some(25.0)));
+}